@@ -0,0 +1,175 @@
+//! Benchmarks for the worksheet diff engine over synthetic worksheets.
+//!
+//! Run with `cargo bench`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use exceldiff::reader::{CellValue, Row, Worksheet};
+use exceldiff::{AlignMode, Tolerance, WorksheetDiffer};
+
+/// Deterministic pseudo-random number generator so runs are comparable
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+}
+
+const CATEGORIES: [&str; 5] = ["Hardware", "Software", "Services", "Support", "Training"];
+
+/// Columns with few distinct values in the low-cardinality worksheets
+const LOW_CARDINALITY_COLUMNS: usize = 40;
+
+fn synthetic_row(id: usize, rng: &mut Lcg) -> Row {
+    vec![
+        CellValue::Int(id as i64),
+        CellValue::String(format!("Customer {}", rng.next() % 100_000)),
+        CellValue::String(CATEGORIES[(rng.next() % 5) as usize].to_string()),
+        CellValue::Float((rng.next() % 1_000_000) as f64 / 100.0),
+        CellValue::Int((rng.next() % 500) as i64),
+        CellValue::Bool(rng.next() & 1 == 0),
+        CellValue::DateTime(45_000.0 + (rng.next() % 365) as f64),
+        CellValue::Empty,
+    ]
+}
+
+/// Build a worksheet pair where `changes` rows are modified, removed and added each
+fn synthetic_pair(rows: usize, changes: usize) -> (Vec<Row>, Vec<Row>) {
+    let mut rng = Lcg(42);
+    let mut sheet1: Vec<Row> = vec![vec![
+        CellValue::String("ID".to_string()),
+        CellValue::String("Name".to_string()),
+        CellValue::String("Category".to_string()),
+        CellValue::String("Amount".to_string()),
+        CellValue::String("Quantity".to_string()),
+        CellValue::String("Active".to_string()),
+        CellValue::String("Date".to_string()),
+        CellValue::String("Notes".to_string()),
+    ]];
+    sheet1.extend((0..rows).map(|id| synthetic_row(id, &mut rng)));
+
+    let mut sheet2 = sheet1.clone();
+    let step = (rows / changes.max(1)).max(1);
+
+    // Modify one cell in every step-th row
+    for row in sheet2.iter_mut().skip(1).step_by(step).take(changes) {
+        row[3] = CellValue::Float((rng.next() % 1_000_000) as f64 / 100.0);
+    }
+    // Remove rows from the middle of each step
+    let mut removed = 0;
+    let mut idx = 1 + step / 2;
+    while removed < changes && idx < sheet2.len() {
+        sheet2.remove(idx);
+        removed += 1;
+        idx += step - 1;
+    }
+    // Add new rows spread over the sheet
+    for n in 0..changes {
+        let pos = (1 + n * step).min(sheet2.len());
+        sheet2.insert(pos, synthetic_row(rows + n, &mut rng));
+    }
+
+    (sheet1, sheet2)
+}
+
+/// Build a wide row of one unique name and many columns with few distinct values
+fn low_cardinality_row(id: usize, rng: &mut Lcg) -> Row {
+    let mut row = vec![CellValue::String(format!("Item {}", id))];
+    row.extend((0..LOW_CARDINALITY_COLUMNS).map(|col| match col % 4 {
+        0 => CellValue::Bool(rng.next() & 1 == 0),
+        1 => CellValue::Empty,
+        2 => CellValue::String(CATEGORIES[(rng.next() % 5) as usize].to_string()),
+        _ => CellValue::Int((rng.next() % 3) as i64),
+    }));
+    row
+}
+
+/// Build a worksheet pair of low-cardinality rows where one flag of every row changed
+///
+/// No row matches exactly, so every row goes through the modified-row search.
+fn low_cardinality_pair(rows: usize) -> (Vec<Row>, Vec<Row>) {
+    let mut rng = Lcg(11);
+    let sheet1: Vec<Row> = (0..rows).map(|id| low_cardinality_row(id, &mut rng)).collect();
+    let mut sheet2 = sheet1.clone();
+    for row in &mut sheet2 {
+        if let CellValue::Bool(flag) = row[1] {
+            row[1] = CellValue::Bool(!flag);
+        }
+    }
+    (sheet1, sheet2)
+}
+
+fn bench_compare(c: &mut Criterion) {
+    let mut group = c.benchmark_group("compare");
+    group.sample_size(10);
+
+    for &rows in &[1_000usize, 10_000, 100_000] {
+        let changes = (rows / 100).max(1);
+        let (sheet1, sheet2) = synthetic_pair(rows, changes);
+        group.throughput(Throughput::Elements(rows as u64));
+
+        let modes = [
+            ("similarity", WorksheetDiffer::new()),
+            ("key", WorksheetDiffer::new().with_key_columns(vec![0])),
+            ("myers", WorksheetDiffer::new().with_align_mode(AlignMode::Myers)),
+            ("patience", WorksheetDiffer::new().with_align_mode(AlignMode::Patience)),
+        ];
+
+        for (name, differ) in &modes {
+            group.bench_with_input(BenchmarkId::new(*name, rows), &rows, |b, _| {
+                b.iter(|| differ.compare(black_box(&sheet1), black_box(&sheet2)))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_unrelated_rows(c: &mut Criterion) {
+    // Worst case for the modified-row search: almost nothing matches exactly
+    let mut group = c.benchmark_group("compare_unrelated");
+    group.sample_size(10);
+
+    for &rows in &[1_000usize, 5_000] {
+        let (sheet1, _) = synthetic_pair(rows, 1);
+        let mut rng = Lcg(7);
+        let sheet2: Worksheet = (0..rows).map(|id| synthetic_row(id, &mut rng)).collect();
+        let differ = WorksheetDiffer::new();
+
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::new("similarity", rows), &rows, |b, _| {
+            b.iter(|| differ.compare(black_box(&sheet1), black_box(&sheet2)))
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_low_cardinality(c: &mut Criterion) {
+    // Blank cells, booleans, categories and (with a tolerance) all numbers share
+    // posting lists covering most rows, so only the unique name narrows the search
+    let mut group = c.benchmark_group("compare_low_cardinality");
+    group.sample_size(10);
+
+    for &rows in &[1_000usize, 10_000] {
+        let (sheet1, sheet2) = low_cardinality_pair(rows);
+        let modes = [
+            ("similarity", WorksheetDiffer::new()),
+            ("tolerance", WorksheetDiffer::new().with_tolerance(Tolerance::new(0.5, 0.0))),
+        ];
+
+        group.throughput(Throughput::Elements(rows as u64));
+        for (name, differ) in &modes {
+            group.bench_with_input(BenchmarkId::new(*name, rows), &rows, |b, _| {
+                b.iter(|| differ.compare(black_box(&sheet1), black_box(&sheet2)))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_compare, bench_unrelated_rows, bench_low_cardinality);
+criterion_main!(benches);
@@ -0,0 +1,353 @@
+//! Column alignment by header name for worksheets with inserted, removed or reordered columns.
+
+use serde::{Deserialize, Serialize};
+
+use crate::columns::column_letter;
+use crate::reader::{CellValue, Row};
+
+/// Minimum header similarity (0.0 to 1.0) for two differently named columns to be paired as a rename
+pub const DEFAULT_RENAME_THRESHOLD: f64 = 0.6;
+
+/// Types of differences between columns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnChange {
+    /// Column has the same header at the same relative position
+    Unchanged,
+    /// Column only exists in the second worksheet
+    Added,
+    /// Column only exists in the first worksheet
+    Removed,
+    /// Column exists in both worksheets under a different header
+    Renamed,
+    /// Column exists in both worksheets but its position relative to the other columns changed
+    Moved,
+}
+
+impl ColumnChange {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColumnChange::Unchanged => "unchanged",
+            ColumnChange::Added => "added",
+            ColumnChange::Removed => "removed",
+            ColumnChange::Renamed => "renamed",
+            ColumnChange::Moved => "moved",
+        }
+    }
+}
+
+/// Represents the diff information for a single column of the aligned output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnDiff {
+    /// Type of difference
+    pub change: ColumnChange,
+    /// Index of the column in the first worksheet (None for Added columns)
+    pub old_index: Option<usize>,
+    /// Index of the column in the second worksheet (None for Removed columns)
+    pub new_index: Option<usize>,
+    /// Header in the first worksheet
+    pub old_header: Option<String>,
+    /// Header in the second worksheet
+    pub new_header: Option<String>,
+}
+
+impl ColumnDiff {
+    /// Header to display for the column (the new header if the column still exists)
+    pub fn header(&self) -> &str {
+        self.new_header
+            .as_deref()
+            .or(self.old_header.as_deref())
+            .unwrap_or_default()
+    }
+
+    /// Whether the header text changed (also true for moved columns that were renamed)
+    pub fn is_renamed(&self) -> bool {
+        matches!((&self.old_header, &self.new_header), (Some(old), Some(new)) if old != new)
+    }
+
+    /// Short description of the change, e.g. "moved from C" or "renamed from 'Qty'"
+    pub fn describe(&self) -> String {
+        match self.change {
+            ColumnChange::Unchanged => String::new(),
+            ColumnChange::Added => "added".to_string(),
+            ColumnChange::Removed => "removed".to_string(),
+            ColumnChange::Renamed => format!("renamed from '{}'", self.old_header.as_deref().unwrap_or_default()),
+            ColumnChange::Moved => {
+                let from = self.old_index.map(column_letter).unwrap_or_default();
+                if self.is_renamed() {
+                    format!(
+                        "moved from {}, renamed from '{}'",
+                        from,
+                        self.old_header.as_deref().unwrap_or_default()
+                    )
+                } else {
+                    format!("moved from {}", from)
+                }
+            }
+        }
+    }
+}
+
+/// Mapping of the columns of two worksheets onto one combined column layout
+///
+/// The layout follows the column order of the second worksheet, with removed
+/// columns placed after the column that preceded them in the first worksheet.
+#[derive(Debug, Clone, Default)]
+pub struct ColumnAlignment {
+    /// One entry per output column, in output order
+    pub columns: Vec<ColumnDiff>,
+}
+
+impl ColumnAlignment {
+    /// Align the columns of two worksheets on their header rows
+    ///
+    /// Columns are paired by exact header text first, then by header text
+    /// ignoring case, spaces and punctuation, and finally by header similarity
+    /// (for renamed columns) if it reaches `rename_threshold`.
+    pub fn from_headers(header1: &[CellValue], header2: &[CellValue], rename_threshold: f64) -> Self {
+        let names1: Vec<String> = header1.iter().map(|c| c.to_string().trim().to_string()).collect();
+        let names2: Vec<String> = header2.iter().map(|c| c.to_string().trim().to_string()).collect();
+
+        let mut pairs: Vec<Option<usize>> = vec![None; names1.len()];
+        let mut used2 = vec![false; names2.len()];
+
+        // Exact header text, then a canonical form of it
+        let passes: [fn(&str) -> String; 2] = [|s| s.to_string(), canonical_header];
+        for canonical in passes {
+            for (idx1, name1) in names1.iter().enumerate() {
+                if pairs[idx1].is_some() || name1.is_empty() {
+                    continue;
+                }
+                let key = canonical(name1);
+                if let Some(idx2) = (0..names2.len())
+                    .find(|&idx2| !used2[idx2] && !names2[idx2].is_empty() && canonical(&names2[idx2]) == key)
+                {
+                    pairs[idx1] = Some(idx2);
+                    used2[idx2] = true;
+                }
+            }
+        }
+
+        // Renamed headers: pair the most similar remaining headers first
+        let mut candidates = Vec::new();
+        for (idx1, name1) in names1.iter().enumerate() {
+            if pairs[idx1].is_some() || name1.is_empty() {
+                continue;
+            }
+            for (idx2, name2) in names2.iter().enumerate() {
+                if used2[idx2] || name2.is_empty() {
+                    continue;
+                }
+                let score = header_similarity(name1, name2);
+                if score >= rename_threshold {
+                    candidates.push((score, idx1, idx2));
+                }
+            }
+        }
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+        for (_, idx1, idx2) in candidates {
+            if pairs[idx1].is_none() && !used2[idx2] {
+                pairs[idx1] = Some(idx2);
+                used2[idx2] = true;
+            }
+        }
+
+        // Columns without a header are paired by their order among the unheaded columns
+        let unheaded1: Vec<usize> = (0..names1.len())
+            .filter(|&i| pairs[i].is_none() && names1[i].is_empty())
+            .collect();
+        let unheaded2: Vec<usize> = (0..names2.len())
+            .filter(|&j| !used2[j] && names2[j].is_empty())
+            .collect();
+        for (&idx1, &idx2) in unheaded1.iter().zip(unheaded2.iter()) {
+            pairs[idx1] = Some(idx2);
+            used2[idx2] = true;
+        }
+
+        let in_order = longest_increasing(&pairs);
+
+        // Layout: all new columns in order, removed columns after their old predecessor
+        let mut columns: Vec<ColumnDiff> = (0..names2.len())
+            .map(|idx2| ColumnDiff {
+                change: ColumnChange::Added,
+                old_index: None,
+                new_index: Some(idx2),
+                old_header: None,
+                new_header: Some(names2[idx2].clone()),
+            })
+            .collect();
+
+        for (idx1, pair) in pairs.iter().enumerate() {
+            if let Some(idx2) = *pair {
+                let column = &mut columns[idx2];
+                column.old_index = Some(idx1);
+                column.old_header = Some(names1[idx1].clone());
+                column.change = if !in_order[idx1] {
+                    ColumnChange::Moved
+                } else if names1[idx1] != names2[idx2] {
+                    ColumnChange::Renamed
+                } else {
+                    ColumnChange::Unchanged
+                };
+            }
+        }
+
+        for idx1 in (0..names1.len()).rev() {
+            if pairs[idx1].is_some() {
+                continue;
+            }
+            let insert_at = (0..idx1)
+                .rev()
+                .find_map(|prev| pairs[prev])
+                .and_then(|prev2| columns.iter().position(|c| c.new_index == Some(prev2)))
+                .map(|pos| pos + 1)
+                .unwrap_or(0);
+            columns.insert(
+                insert_at,
+                ColumnDiff {
+                    change: ColumnChange::Removed,
+                    old_index: Some(idx1),
+                    new_index: None,
+                    old_header: Some(names1[idx1].clone()),
+                    new_header: None,
+                },
+            );
+        }
+
+        ColumnAlignment { columns }
+    }
+
+    /// Whether any column was added, removed, renamed or moved
+    pub fn has_changes(&self) -> bool {
+        self.columns.iter().any(|c| c.change != ColumnChange::Unchanged)
+    }
+
+    /// Rearrange a row of the first worksheet into the combined layout
+    pub fn project_old(&self, row: &Row) -> Row {
+        self.project(row, |column| column.old_index)
+    }
+
+    /// Rearrange a row of the second worksheet into the combined layout
+    pub fn project_new(&self, row: &Row) -> Row {
+        self.project(row, |column| column.new_index)
+    }
+
+    /// Position in the combined layout of a column of the first worksheet
+    pub fn old_to_layout(&self, old_index: usize) -> Option<usize> {
+        self.columns.iter().position(|c| c.old_index == Some(old_index))
+    }
+
+    fn project(&self, row: &Row, source: impl Fn(&ColumnDiff) -> Option<usize>) -> Row {
+        self.columns
+            .iter()
+            .map(|column| {
+                source(column)
+                    .and_then(|idx| row.get(idx))
+                    .cloned()
+                    .unwrap_or(CellValue::Empty)
+            })
+            .collect()
+    }
+}
+
+/// Header text reduced to lowercase letters and digits
+fn canonical_header(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Similarity of two headers (1.0 = equal)
+///
+/// Based on the edit distance of their canonical forms, with a fixed score for
+/// headers that extend each other by whole words ("Name" / "Full Name") or
+/// abbreviate each other ("Qty" / "Quantity").
+fn header_similarity(a: &str, b: &str) -> f64 {
+    let words = |s: &str| -> Vec<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(|w| w.to_lowercase())
+            .collect()
+    };
+    let (words_a, words_b) = (words(a), words(b));
+    let extends = |short: &[String], long: &[String]| {
+        !short.is_empty() && short.iter().all(|w| long.contains(w))
+    };
+    if extends(&words_a, &words_b) || extends(&words_b, &words_a) {
+        return 0.75;
+    }
+
+    let a: Vec<char> = canonical_header(a).chars().collect();
+    let b: Vec<char> = canonical_header(b).chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 0.0;
+    }
+    if is_abbreviation(&a, &b) || is_abbreviation(&b, &a) {
+        return 0.7;
+    }
+
+    // Levenshtein distance with a single rolling row
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(row[j + 1])
+            };
+            previous = current;
+        }
+    }
+
+    1.0 - row[b.len()] as f64 / longest as f64
+}
+
+/// Whether `short` abbreviates `long`: same first letter and all letters appear in order
+fn is_abbreviation(short: &[char], long: &[char]) -> bool {
+    if short.len() < 2 || short.len() >= long.len() || short[0] != long[0] {
+        return false;
+    }
+    let mut remaining = long.iter();
+    short.iter().all(|c| remaining.any(|l| l == c))
+}
+
+/// Mark the pairs that keep their relative order (longest increasing run of new indices)
+///
+/// `pairs[i]` is the new index paired with old index `i`, if any. Pairs
+/// outside of this subsequence are the ones that moved; this is used for both
+/// columns and rows.
+pub(crate) fn longest_increasing(pairs: &[Option<usize>]) -> Vec<bool> {
+    let items: Vec<(usize, usize)> = pairs
+        .iter()
+        .enumerate()
+        .filter_map(|(idx1, pair)| pair.map(|idx2| (idx1, idx2)))
+        .collect();
+
+    // Patience sorting with predecessor links
+    let mut tails: Vec<usize> = Vec::new();
+    let mut previous: Vec<Option<usize>> = vec![None; items.len()];
+    for (pos, &(_, idx2)) in items.iter().enumerate() {
+        let slot = tails.partition_point(|&t| items[t].1 < idx2);
+        if slot > 0 {
+            previous[pos] = Some(tails[slot - 1]);
+        }
+        if slot == tails.len() {
+            tails.push(pos);
+        } else {
+            tails[slot] = pos;
+        }
+    }
+
+    let mut in_order = vec![false; pairs.len()];
+    let mut current = tails.last().copied();
+    while let Some(pos) = current {
+        in_order[items[pos].0] = true;
+        current = previous[pos];
+    }
+    in_order
+}
@@ -0,0 +1,136 @@
+//! Column references used to select worksheet columns by letter, index or header name.
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::reader::CellValue;
+
+/// Reference to a worksheet column as given by the user
+///
+/// A column can be referenced by its 1-based number (`3`), by its Excel
+/// letter (`C`) or by the text of its header cell (`Price`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnSpec {
+    /// Zero-based column index
+    Index(usize),
+    /// Column letter or header name, resolved against the header row
+    Name(String),
+}
+
+impl ColumnSpec {
+    /// Resolve the column reference to a zero-based column index
+    ///
+    /// Header names take precedence over column letters, so a header called
+    /// "ID" is found even though "ID" is also a valid column letter. Letters
+    /// refer to the columns of the file and numbers count from the first
+    /// column of the sheet.
+    ///
+    /// # Arguments
+    /// * `header` - The header row used to look up column names
+    /// * `first_col` - Zero-based column of the file where the sheet starts (see [`Worksheet::origin`])
+    ///
+    /// [`Worksheet::origin`]: crate::reader::Worksheet::origin
+    pub fn resolve(&self, header: &[CellValue], first_col: usize) -> Result<usize> {
+        match self {
+            ColumnSpec::Index(idx) => Ok(*idx),
+            ColumnSpec::Name(name) => {
+                let name = name.trim();
+                let by_name = header
+                    .iter()
+                    .position(|cell| cell.to_string().trim() == name)
+                    .or_else(|| {
+                        header
+                            .iter()
+                            .position(|cell| cell.to_string().trim().eq_ignore_ascii_case(name))
+                    });
+                if let Some(col_idx) = by_name {
+                    return Ok(col_idx);
+                }
+                let col = column_index(name).with_context(|| format!("Column '{}' not found in header row", name))?;
+                col.checked_sub(first_col).with_context(|| {
+                    format!(
+                        "Column '{}' is left of the first column of the sheet ({})",
+                        name,
+                        column_letter(first_col)
+                    )
+                })
+            }
+        }
+    }
+
+    /// Resolve a list of column references to zero-based column indices
+    pub fn resolve_all(specs: &[ColumnSpec], header: &[CellValue], first_col: usize) -> Result<Vec<usize>> {
+        specs.iter().map(|spec| spec.resolve(header, first_col)).collect()
+    }
+}
+
+impl FromStr for ColumnSpec {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if s.is_empty() {
+            anyhow::bail!("Column reference must not be empty");
+        }
+        if s.chars().all(|c| c.is_ascii_digit()) {
+            let number: usize = s.parse().context("Invalid column number")?;
+            if number == 0 {
+                anyhow::bail!("Column numbers start at 1");
+            }
+            return Ok(ColumnSpec::Index(number - 1));
+        }
+        Ok(ColumnSpec::Name(s.to_string()))
+    }
+}
+
+impl fmt::Display for ColumnSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnSpec::Index(idx) => write!(f, "{}", idx + 1),
+            ColumnSpec::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+/// Convert a zero-based column index to an Excel column letter (0 → "A", 27 → "AB")
+pub fn column_letter(index: usize) -> String {
+    let mut letters = Vec::new();
+    let mut n = index + 1;
+    while n > 0 {
+        let rem = (n - 1) % 26;
+        letters.push(b'A' + rem as u8);
+        n = (n - 1) / 26;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+/// Convert an Excel column letter to a zero-based column index ("A" → 0, "AB" → 27)
+///
+/// Returns None if the text is not a valid column letter within Excel's
+/// 16384 column limit.
+pub fn column_index(letters: &str) -> Option<usize> {
+    if letters.is_empty() || letters.len() > 3 || !letters.chars().all(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+    let index = letters
+        .to_ascii_uppercase()
+        .bytes()
+        .fold(0usize, |acc, b| acc * 26 + (b - b'A' + 1) as usize)
+        - 1;
+    (index < 16384).then_some(index)
+}
+
+/// A1-style reference of a zero-based (row, column) cell position ((2, 1) → "B3")
+pub fn cell_address(row: usize, col: usize) -> String {
+    format!("{}{}", column_letter(col), row + 1)
+}
+
+/// Zero-based (row, column) of an A1-style cell reference ("B3" → (2, 1))
+pub fn cell_position(reference: &str) -> Option<(usize, usize)> {
+    let split = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = reference.split_at(split);
+    let row: usize = digits.parse().ok()?;
+    Some((row.checked_sub(1)?, column_index(letters)?))
+}
@@ -0,0 +1,256 @@
+//! CSV and TSV file reader implementation using the csv crate.
+
+use anyhow::{Context, Result};
+use encoding_rs::{Encoding, WINDOWS_1252};
+use std::path::Path;
+
+use crate::datetime::parse_iso_datetime;
+use crate::reader::{has_extension, CellValue, FileReader, Row, Worksheet, EXCEL_ERRORS};
+
+/// Delimiters considered when detecting the delimiter of a file
+const DELIMITER_CANDIDATES: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+/// Number of lines inspected when detecting the delimiter and quote character
+const SNIFF_LINES: usize = 20;
+
+/// Significant digits Excel keeps of a number
+const MAX_SIGNIFICANT_DIGITS: usize = 15;
+
+/// Reader for delimited text files (.csv, .tsv, .txt)
+///
+/// A delimited file is treated as a workbook with a single sheet named after
+/// the file. The text encoding (UTF-8, UTF-16 with BOM or Windows-1252), the
+/// delimiter and the quote character are detected from the file contents, and
+/// cell values are converted to integers, floats, booleans and dates where
+/// they look like one.
+pub struct CsvReader {
+    delimiter: Option<u8>,
+}
+
+impl CsvReader {
+    pub fn new() -> Self {
+        CsvReader { delimiter: None }
+    }
+
+    /// Use a fixed delimiter instead of detecting it
+    pub fn with_delimiter(delimiter: u8) -> Self {
+        CsvReader {
+            delimiter: Some(delimiter),
+        }
+    }
+
+    /// Read and decode the file contents to text
+    fn read_text(&self, file_path: &str) -> Result<String> {
+        let bytes = std::fs::read(file_path)
+            .with_context(|| format!("Failed to open file: {}", file_path))?;
+
+        let text = if let Some((encoding, bom_length)) = Encoding::for_bom(&bytes) {
+            let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_length..]);
+            text.into_owned()
+        } else {
+            match String::from_utf8(bytes) {
+                Ok(text) => text,
+                // Not UTF-8: assume the usual encoding of spreadsheet exports on Windows
+                Err(e) => WINDOWS_1252.decode(e.as_bytes()).0.into_owned(),
+            }
+        };
+
+        Ok(text)
+    }
+
+    /// Pick the delimiter from the file extension or the contents
+    fn detect_delimiter(&self, file_path: &str, text: &str) -> u8 {
+        if let Some(delimiter) = self.delimiter {
+            return delimiter;
+        }
+        if has_extension(file_path, &["tsv", "tab"]) {
+            return b'\t';
+        }
+
+        let lines: Vec<&str> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .take(SNIFF_LINES)
+            .collect();
+
+        // Prefer the delimiter that splits every line into the same number of fields,
+        // then the one producing the most fields
+        DELIMITER_CANDIDATES
+            .iter()
+            .map(|&delimiter| {
+                let counts: Vec<usize> = lines
+                    .iter()
+                    .map(|line| count_unquoted(line, delimiter as char))
+                    .collect();
+                let min = counts.iter().copied().min().unwrap_or(0);
+                let consistent = min > 0 && counts.iter().all(|&c| c == min);
+                (delimiter, consistent, min, counts.iter().sum::<usize>())
+            })
+            .max_by_key(|&(_, consistent, min, total)| (consistent, min, total))
+            .filter(|&(_, _, _, total)| total > 0)
+            .map(|(delimiter, _, _, _)| delimiter)
+            .unwrap_or(b',')
+    }
+}
+
+impl Default for CsvReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileReader for CsvReader {
+    fn read(&self, file_path: &str, sheet_name: Option<&str>) -> Result<Worksheet> {
+        if !self.supports(file_path) {
+            anyhow::bail!("File {} is not a .csv or .tsv file", file_path);
+        }
+
+        if let Some(name) = sheet_name {
+            let sheet = sheet_name_for(file_path);
+            if name != sheet {
+                anyhow::bail!(
+                    "Sheet '{}' not found, {} only contains sheet '{}'",
+                    name,
+                    file_path,
+                    sheet
+                );
+            }
+        }
+
+        let text = self.read_text(file_path)?;
+        let delimiter = self.detect_delimiter(file_path, &text);
+        let quote = detect_quote(&text, delimiter);
+
+        let mut reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(delimiter)
+            .quote(quote)
+            .from_reader(text.as_bytes());
+
+        let mut worksheet = Worksheet::new();
+        for record in reader.records() {
+            let record = record.with_context(|| format!("Failed to parse {}", file_path))?;
+            let row: Row = record.iter().map(infer_cell_value).collect();
+            worksheet.push(row);
+        }
+
+        // Pad rows to the same width, like the used range of a spreadsheet
+        let width = worksheet.iter().map(|row| row.len()).max().unwrap_or(0);
+        for row in worksheet.iter_mut() {
+            row.resize(width, CellValue::Empty);
+        }
+
+        Ok(worksheet)
+    }
+
+    fn get_sheet_names(&self, file_path: &str) -> Result<Vec<String>> {
+        if !self.supports(file_path) {
+            anyhow::bail!("File {} is not a .csv or .tsv file", file_path);
+        }
+
+        Ok(vec![sheet_name_for(file_path)])
+    }
+
+    fn supports(&self, file_path: &str) -> bool {
+        has_extension(file_path, &["csv", "tsv", "tab", "txt"])
+    }
+}
+
+/// Convert a text field to the most specific cell value it represents
+///
+/// Numbers with leading zeros (such as "007") stay text, as they are usually
+/// codes rather than quantities. So do whole numbers with more than 15
+/// digits, such as account or card numbers, which Excel could not store
+/// exactly either. Excel error values (such as "#REF!") become errors.
+pub fn infer_cell_value(field: &str) -> CellValue {
+    let trimmed = field.trim();
+    if trimmed.is_empty() {
+        return CellValue::Empty;
+    }
+
+    let unsigned = trimmed.strip_prefix(['-', '+']).unwrap_or(trimmed);
+    let leading_zero = unsigned.len() > 1 && unsigned.starts_with('0') && !unsigned.starts_with("0.");
+    let long_digits = unsigned.len() > MAX_SIGNIFICANT_DIGITS && unsigned.bytes().all(|b| b.is_ascii_digit());
+    let numeric = !unsigned.is_empty()
+        && unsigned.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && unsigned
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | 'e' | 'E' | '-' | '+'));
+
+    if numeric && !leading_zero && !long_digits {
+        if let Ok(i) = trimmed.parse::<i64>() {
+            return CellValue::Int(i);
+        }
+        if let Ok(f) = trimmed.parse::<f64>() {
+            if f.is_finite() {
+                return CellValue::Float(f);
+            }
+        }
+    }
+
+    if trimmed.eq_ignore_ascii_case("true") {
+        return CellValue::Bool(true);
+    }
+    if trimmed.eq_ignore_ascii_case("false") {
+        return CellValue::Bool(false);
+    }
+
+    if let Some(serial) = parse_iso_datetime(trimmed) {
+        return CellValue::DateTime(serial);
+    }
+
+    // Exports of Excel workbooks write error values as their text
+    if EXCEL_ERRORS.contains(&trimmed) {
+        return CellValue::Error(trimmed.to_string());
+    }
+
+    CellValue::String(field.to_string())
+}
+
+/// Name of the single sheet of a delimited file (the file name without extension)
+fn sheet_name_for(file_path: &str) -> String {
+    Path::new(file_path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Sheet1")
+        .to_string()
+}
+
+/// Count occurrences of a delimiter outside of double-quoted sections
+fn count_unquoted(line: &str, delimiter: char) -> usize {
+    let mut in_quotes = false;
+    let mut count = 0;
+    for c in line.chars() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if c == delimiter && !in_quotes {
+            count += 1;
+        }
+    }
+    count
+}
+
+/// Use single quotes if fields are quoted with them and never with double quotes
+fn detect_quote(text: &str, delimiter: u8) -> u8 {
+    let delimiter = delimiter as char;
+    let (mut double, mut single) = (0, 0);
+    for line in text.lines().take(SNIFF_LINES) {
+        for field in line.split(delimiter) {
+            let field = field.trim_start();
+            if field.starts_with('"') {
+                double += 1;
+            } else {
+                let field = field.trim_end();
+                if field.len() > 1 && field.starts_with('\'') && field.ends_with('\'') {
+                    single += 1;
+                }
+            }
+        }
+    }
+    if single > 0 && double == 0 {
+        b'\''
+    } else {
+        b'"'
+    }
+}
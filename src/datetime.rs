@@ -0,0 +1,302 @@
+//! Conversion of calendar dates and times to Excel serial date values.
+
+use anyhow::Result;
+use std::fmt;
+use std::str::FromStr;
+
+/// Convert a date and time to an Excel serial date (days since 1899-12-30)
+///
+/// Follows Excel's 1900 date system, including its fictitious 1900-02-29, so
+/// serial values match what Excel stores for the same date.
+///
+/// Returns None if the date or time is out of range.
+pub fn excel_serial(
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: f64,
+) -> Option<f64> {
+    // Excel's fictitious 1900-02-29 is serial day 60
+    let leap_bug = (year, month, day) == (1900, 2, 29);
+    if !(1..=12).contains(&month)
+        || day == 0
+        || (day > days_in_month(year, month) && !leap_bug)
+        || hour > 23
+        || minute > 59
+        || !(0.0..60.0).contains(&second)
+    {
+        return None;
+    }
+
+    // Days since 1899-12-30; Excel counts 1900-02-29, so earlier dates shift by one
+    let mut days = days_from_civil(year, month, day) - days_from_civil(1899, 12, 30);
+    if leap_bug {
+        days = 60;
+    } else if days < 61 {
+        days -= 1;
+    }
+    if days < 1 {
+        return None;
+    }
+
+    let seconds = (hour * 3600 + minute * 60) as f64 + second;
+    Some(days as f64 + seconds / 86_400.0)
+}
+
+/// Format an Excel serial date as `YYYY-MM-DD`, `YYYY-MM-DD HH:MM:SS` or `HH:MM:SS`
+///
+/// The time is left out for whole days and the date for values below 1 (times
+/// without a date). Returns None for negative or non-finite values.
+pub fn format_excel_serial(serial: f64) -> Option<String> {
+    if !serial.is_finite() || serial < 0.0 {
+        return None;
+    }
+
+    let mut days = serial.floor() as i64;
+    let mut seconds = ((serial - days as f64) * 86_400.0).round() as i64;
+    if seconds == 86_400 {
+        days += 1;
+        seconds = 0;
+    }
+    let time = format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if days == 0 {
+        return Some(time);
+    }
+
+    // Excel's fictitious 1900-02-29 has no counterpart in the calendar
+    let date = if days == 60 {
+        "1900-02-29".to_string()
+    } else {
+        let offset = if days < 60 { 1 } else { 0 };
+        let (year, month, day) = civil_from_days(days + offset + days_from_civil(1899, 12, 30));
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    };
+
+    if seconds == 0 {
+        Some(date)
+    } else {
+        Some(format!("{} {}", date, time))
+    }
+}
+
+/// Parse an ISO 8601 date or date-time into an Excel serial date
+///
+/// Accepts `YYYY-MM-DD`, optionally followed by a space or `T` and a time
+/// `HH:MM`, `HH:MM:SS` or `HH:MM:SS.fff`, and an optional trailing `Z`.
+pub fn parse_iso_datetime(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let s = s.strip_suffix('Z').unwrap_or(s);
+    let (date, time) = match s.find(['T', ' ']) {
+        Some(pos) => (&s[..pos], Some(&s[pos + 1..])),
+        None => (s, None),
+    };
+
+    let mut parts = date.split('-');
+    let year = fixed_digits(parts.next()?, 4)?;
+    let month = fixed_digits(parts.next()?, 2)?;
+    let day = fixed_digits(parts.next()?, 2)?;
+    if parts.next().is_some() {
+        return None;
+    }
+
+    let (hour, minute, second) = match time {
+        None => (0, 0, 0.0),
+        Some(time) => parse_time(time)?,
+    };
+
+    excel_serial(year as i64, month, day, hour, minute, second)
+}
+
+/// Date format for parsing dates stored as text, such as `%d/%m/%Y`
+///
+/// Supported fields are `%Y` (4-digit year), `%y` (2-digit year, 00-29 in
+/// the 2000s like Excel), `%m` and `%d` (1 or 2 digits), `%H`, `%M` and `%S`
+/// (2 digits) and `%%` for a literal percent sign. Other characters must
+/// match exactly.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DateFormat {
+    pattern: String,
+    items: Vec<FormatItem>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FormatItem {
+    Literal(char),
+    Year,
+    ShortYear,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+}
+
+impl DateFormat {
+    /// Parse a text in this format into an Excel serial date
+    ///
+    /// Returns None if the text does not match the format or is not a valid date.
+    pub fn parse(&self, s: &str) -> Option<f64> {
+        let mut rest = s.trim();
+        let (mut year, mut month, mut day) = (None, None, None);
+        let (mut hour, mut minute, mut second) = (0, 0, 0);
+
+        for item in &self.items {
+            let (min_digits, max_digits) = match item {
+                FormatItem::Literal(c) => {
+                    rest = rest.strip_prefix(*c)?;
+                    continue;
+                }
+                FormatItem::Year => (4, 4),
+                FormatItem::Month | FormatItem::Day => (1, 2),
+                FormatItem::ShortYear | FormatItem::Hour | FormatItem::Minute | FormatItem::Second => (2, 2),
+            };
+            let length = rest
+                .bytes()
+                .take(max_digits)
+                .take_while(u8::is_ascii_digit)
+                .count();
+            if length < min_digits {
+                return None;
+            }
+            let value: u32 = rest[..length].parse().ok()?;
+            rest = &rest[length..];
+
+            match item {
+                FormatItem::Year => year = Some(value as i64),
+                FormatItem::ShortYear => year = Some(if value < 30 { 2000 } else { 1900 } + value as i64),
+                FormatItem::Month => month = Some(value),
+                FormatItem::Day => day = Some(value),
+                FormatItem::Hour => hour = value,
+                FormatItem::Minute => minute = value,
+                FormatItem::Second => second = value,
+                FormatItem::Literal(_) => {}
+            }
+        }
+        if !rest.is_empty() {
+            return None;
+        }
+
+        excel_serial(year?, month?, day?, hour, minute, second as f64)
+    }
+}
+
+impl FromStr for DateFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut items = Vec::new();
+        let mut chars = s.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                items.push(FormatItem::Literal(c));
+                continue;
+            }
+            items.push(match chars.next() {
+                Some('Y') => FormatItem::Year,
+                Some('y') => FormatItem::ShortYear,
+                Some('m') => FormatItem::Month,
+                Some('d') => FormatItem::Day,
+                Some('H') => FormatItem::Hour,
+                Some('M') => FormatItem::Minute,
+                Some('S') => FormatItem::Second,
+                Some('%') => FormatItem::Literal('%'),
+                Some(other) => anyhow::bail!(
+                    "Unknown field '%{}' in date format '{}' (expected %Y, %y, %m, %d, %H, %M or %S)",
+                    other,
+                    s
+                ),
+                None => anyhow::bail!("Date format '{}' ends with a lone '%'", s),
+            });
+        }
+
+        let has = |wanted: &[FormatItem]| items.iter().any(|item| wanted.contains(item));
+        if !has(&[FormatItem::Year, FormatItem::ShortYear]) || !has(&[FormatItem::Month]) || !has(&[FormatItem::Day]) {
+            anyhow::bail!("Date format '{}' needs a year (%Y or %y), a month (%m) and a day (%d)", s);
+        }
+
+        Ok(DateFormat {
+            pattern: s.to_string(),
+            items,
+        })
+    }
+}
+
+impl fmt::Display for DateFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern)
+    }
+}
+
+/// Parse `HH:MM`, `HH:MM:SS` or `HH:MM:SS.fff`
+fn parse_time(s: &str) -> Option<(u32, u32, f64)> {
+    let mut parts = s.split(':');
+    let hour = fixed_digits(parts.next()?, 2)?;
+    let minute = fixed_digits(parts.next()?, 2)?;
+    let second = match parts.next() {
+        None => 0.0,
+        Some(sec) => {
+            let (whole, fraction) = sec.split_once('.').unwrap_or((sec, ""));
+            let whole = fixed_digits(whole, 2)? as f64;
+            if !fraction.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            if fraction.is_empty() {
+                whole
+            } else {
+                whole + format!("0.{}", fraction).parse::<f64>().ok()?
+            }
+        }
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((hour, minute, second))
+}
+
+/// Parse a number of exactly `len` ASCII digits
+fn fixed_digits(s: &str, len: usize) -> Option<u32> {
+    if s.len() != len || !s.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Date in the proleptic Gregorian calendar for a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
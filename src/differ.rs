@@ -1,13 +1,34 @@
 //! Diff engine for comparing worksheets.
 
-use std::collections::{HashMap, HashSet};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices, Algorithm, DiffTag};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::column_diff::{longest_increasing, ColumnAlignment, ColumnChange, ColumnDiff, DEFAULT_RENAME_THRESHOLD};
+use crate::matcher::SimilarityIndex;
+use crate::csv_reader::infer_cell_value;
+use crate::datetime::DateFormat;
+use crate::header::HeaderRow;
+use crate::ignore::RowFilter;
+use crate::normalize::{Normalizer, TextNormalization};
 use crate::reader::{CellValue, Row, Worksheet};
+use crate::stream::{RowDiffs, SheetRow};
+use crate::tolerance::Tolerance;
+use crate::workbook::SheetOrigins;
 
 /// Types of differences between rows
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum DiffType {
     Identical,
     Modified,
+    /// Row exists in both worksheets, but out of order (see [`WorksheetDiffer::with_move_detection`])
+    ///
+    /// A moved row whose cells also changed lists them in [`RowDiff::modified_cells`].
+    Moved,
     Removed,
     Added,
 }
@@ -17,16 +38,121 @@ impl DiffType {
         match self {
             DiffType::Identical => "identical",
             DiffType::Modified => "modified",
+            DiffType::Moved => "moved",
             DiffType::Removed => "removed",
             DiffType::Added => "added",
         }
     }
 }
 
+/// Strategy used to pair up the rows of two worksheets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AlignMode {
+    /// Match rows by content regardless of position; added rows are listed last
+    #[default]
+    Similarity,
+    /// Order-preserving alignment using the Myers diff algorithm
+    Myers,
+    /// Order-preserving alignment using the patience diff algorithm
+    Patience,
+}
+
+impl AlignMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlignMode::Similarity => "similarity",
+            AlignMode::Myers => "myers",
+            AlignMode::Patience => "patience",
+        }
+    }
+}
+
+impl FromStr for AlignMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "similarity" => Ok(AlignMode::Similarity),
+            "myers" => Ok(AlignMode::Myers),
+            "patience" => Ok(AlignMode::Patience),
+            _ => anyhow::bail!(
+                "Unknown alignment mode '{}' (expected similarity, myers or patience)",
+                s
+            ),
+        }
+    }
+}
+
+/// What is compared for cells that contain a formula
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompareMode {
+    /// Compare the calculated values only
+    #[default]
+    Values,
+    /// Compare the formula text; a value change caused by changed inputs is ignored
+    Formulas,
+    /// A cell differs if either its value or its formula changed
+    Both,
+}
+
+impl CompareMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompareMode::Values => "values",
+            CompareMode::Formulas => "formulas",
+            CompareMode::Both => "both",
+        }
+    }
+}
+
+impl FromStr for CompareMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "values" => Ok(CompareMode::Values),
+            "formulas" => Ok(CompareMode::Formulas),
+            "both" => Ok(CompareMode::Both),
+            _ => anyhow::bail!("Unknown compare mode '{}' (expected values, formulas or both)", s),
+        }
+    }
+}
+
+/// Zero-based (row, column) of a cell in a worksheet
+pub type CellPosition = (usize, usize);
+
+/// Change of the formula of a modified cell
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormulaChange {
+    /// Column of the cell in the row data
+    pub column: usize,
+    /// Formula in the first worksheet, without the leading `=` (None for a constant)
+    pub old: Option<String>,
+    /// Formula in the second worksheet, without the leading `=` (None for a constant)
+    pub new: Option<String>,
+}
+
+impl FormulaChange {
+    /// Old formula as shown in Excel (e.g. `=SUM(A1:A3)`), or None for a constant
+    pub fn old_formula(&self) -> Option<String> {
+        self.old.as_ref().map(|formula| format!("={}", formula))
+    }
+
+    /// New formula as shown in Excel (e.g. `=SUM(A1:A4)`), or None for a constant
+    pub fn new_formula(&self) -> Option<String> {
+        self.new.as_ref().map(|formula| format!("={}", formula))
+    }
+}
+
 /// Represents the diff information for a single row
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RowDiff {
-    /// Index of the row in the result
+    /// Position of the row in the result, counting from 0 in the order rows are reported
+    ///
+    /// The positions of the row in the compared worksheets are [`old_row`] and [`new_row`].
+    ///
+    /// [`old_row`]: RowDiff::old_row
+    /// [`new_row`]: RowDiff::new_row
     pub row_index: usize,
     /// Type of difference
     pub diff_type: DiffType,
@@ -36,6 +162,43 @@ pub struct RowDiff {
     pub modified_cells: Vec<usize>,
     /// The original row data (old values, for Modified type)
     pub original_row_data: Option<Row>,
+    /// Index of the row in the first worksheet (None for Added rows)
+    pub old_row: Option<usize>,
+    /// Index of the row in the second worksheet (None for Removed rows)
+    pub new_row: Option<usize>,
+    /// A1 address of the first cell of the row in the first file (None for Added rows)
+    ///
+    /// Unlike [`old_row`], it accounts for where the compared cells start in
+    /// the file (see [`SheetOrigins`]).
+    ///
+    /// [`old_row`]: RowDiff::old_row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_address: Option<String>,
+    /// A1 address of the first cell of the row in the second file (None for Removed rows)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_address: Option<String>,
+    /// Modified cells whose formula changed (only filled when formulas are compared)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub formula_changes: Vec<FormulaChange>,
+    /// Column indices of cells whose value is equal but whose type changed (only with type coercion)
+    ///
+    /// Rows with type changes keep both the old and the new values, also when
+    /// they are Identical.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub type_changes: Vec<usize>,
+    /// Column indices of cells that are shown but were left out of the comparison
+    ///
+    /// Lists the ignored columns, or every cell of a row that was ignored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignored_cells: Vec<usize>,
+    /// Whether the row was left out of the comparison (see [`WorksheetDiffer::with_row_filter`])
+    ///
+    /// Ignored rows keep their diff type, but are not changes.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub ignored: bool,
+    /// Whether this row pairs the header rows of the worksheets (see [`WorksheetDiffer::with_header_row`])
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub header: bool,
 }
 
 impl RowDiff {
@@ -52,31 +215,506 @@ impl RowDiff {
             row_data,
             modified_cells,
             original_row_data,
+            old_row: None,
+            new_row: None,
+            old_address: None,
+            new_address: None,
+            formula_changes: Vec::new(),
+            type_changes: Vec::new(),
+            ignored_cells: Vec::new(),
+            ignored: false,
+            header: false,
         }
     }
+
+    /// Set the positions of the row in the first and second worksheet
+    pub fn with_positions(mut self, old_row: Option<usize>, new_row: Option<usize>) -> Self {
+        self.old_row = old_row;
+        self.new_row = new_row;
+        self
+    }
+
+    /// Positions (row, column) of a cell of this row in the first and the second worksheet
+    ///
+    /// # Arguments
+    /// * `columns` - Column diffs matching the column layout of the row data (empty if columns were not aligned)
+    /// * `col_idx` - Column of the cell in the row data
+    pub fn cell_positions(
+        &self,
+        columns: &[ColumnDiff],
+        col_idx: usize,
+    ) -> (Option<CellPosition>, Option<CellPosition>) {
+        let column = columns.get(col_idx);
+        let old = self.old_row.zip(column.map_or(Some(col_idx), |c| c.old_index));
+        let new = self.new_row.zip(column.map_or(Some(col_idx), |c| c.new_index));
+        (old, new)
+    }
+
+    /// Whether the row data shows the cell of the first worksheet at a column
+    ///
+    /// Cells come from the second worksheet, except in removed rows and removed columns.
+    pub fn shows_old_cell(&self, columns: &[ColumnDiff], col_idx: usize) -> bool {
+        self.diff_type == DiffType::Removed
+            || columns
+                .get(col_idx)
+                .is_some_and(|column| column.change == ColumnChange::Removed)
+    }
+
+    /// Whether a cell was left out of the comparison
+    pub fn is_ignored(&self, col_idx: usize) -> bool {
+        self.ignored_cells.contains(&col_idx)
+    }
+
+    /// Formula change of a modified cell, if its formula changed
+    pub fn formula_change(&self, col_idx: usize) -> Option<&FormulaChange> {
+        self.formula_changes.iter().find(|change| change.column == col_idx)
+    }
+
+    /// Difference between the new and the old number of a modified numeric cell
+    pub fn numeric_delta(&self, col_idx: usize) -> Option<f64> {
+        if !self.modified_cells.contains(&col_idx) {
+            return None;
+        }
+        let old = self.original_row_data.as_ref()?.get(col_idx)?.as_number()?;
+        let new = self.row_data.get(col_idx)?.as_number()?;
+        Some(((new - old) * 1e10).round() / 1e10)
+    }
+
+    /// Signed text of the numeric delta of a modified cell (e.g. "+2" or "-0.5")
+    pub fn delta_text(&self, col_idx: usize) -> Option<String> {
+        self.numeric_delta(col_idx).map(|delta| {
+            if delta > 0.0 {
+                format!("+{}", delta)
+            } else {
+                delta.to_string()
+            }
+        })
+    }
+
+    /// Whether the row has any reported change, including type-only changes of Identical rows
+    ///
+    /// Ignored rows have no changes.
+    pub fn has_changes(&self) -> bool {
+        !self.ignored && (self.diff_type != DiffType::Identical || !self.type_changes.is_empty())
+    }
+
+    /// Old and new value of a cell whose only change is its type
+    pub fn type_change(&self, col_idx: usize) -> Option<(&CellValue, &CellValue)> {
+        if !self.type_changes.contains(&col_idx) {
+            return None;
+        }
+        let old = self.original_row_data.as_ref()?.get(col_idx)?;
+        Some((old, self.row_data.get(col_idx)?))
+    }
+
+    /// Number of cells that became an error value in this row
+    ///
+    /// Counts all error cells of Added rows and the modified cells of Modified
+    /// rows whose new value is an error while the old value was not. Ignored
+    /// rows have no new errors.
+    pub fn new_errors(&self) -> usize {
+        if self.ignored {
+            return 0;
+        }
+        match self.diff_type {
+            DiffType::Added => self.row_data.iter().filter(|cell| cell.is_error()).count(),
+            DiffType::Modified | DiffType::Moved => self
+                .modified_cells
+                .iter()
+                .filter(|&&col| {
+                    let old_error = self
+                        .original_row_data
+                        .as_ref()
+                        .and_then(|row| row.get(col))
+                        .is_some_and(CellValue::is_error);
+                    self.row_data.get(col).is_some_and(CellValue::is_error) && !old_error
+                })
+                .count(),
+            DiffType::Identical | DiffType::Removed => 0,
+        }
+    }
+
+    /// Formula change of a modified cell whose formula changed but whose value did not
+    pub fn formula_only_change(&self, col_idx: usize) -> Option<&FormulaChange> {
+        let change = self.formula_change(col_idx)?;
+        let old = self.original_row_data.as_ref()?.get(col_idx).unwrap_or(&CellValue::Empty);
+        let new = self.row_data.get(col_idx).unwrap_or(&CellValue::Empty);
+        (old.normalize() == new.normalize()).then_some(change)
+    }
+}
+
+/// Number of rows per diff type, and of cells that became an error or changed type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiffSummary {
+    pub identical: usize,
+    pub modified: usize,
+    /// Rows that exist in both worksheets but out of order, also when their cells changed
+    #[serde(default)]
+    pub moved: usize,
+    pub removed: usize,
+    pub added: usize,
+    /// Cells holding an error value (such as #REF!) in the second file that did not in the first
+    #[serde(default)]
+    pub new_errors: usize,
+    /// Cells whose value is equal but whose type changed (e.g. text "42" and number 42)
+    #[serde(default)]
+    pub type_changes: usize,
+    /// Rows left out of the comparison, not counted in the other row counts
+    #[serde(default)]
+    pub ignored: usize,
+}
+
+impl DiffSummary {
+    /// Count the diff types of a list of row diffs
+    ///
+    /// The header row names the columns and is not counted.
+    pub fn from_diffs(diffs: &[RowDiff]) -> Self {
+        let mut summary = DiffSummary::default();
+        for diff in diffs.iter().filter(|diff| !diff.header) {
+            if diff.ignored {
+                summary.ignored += 1;
+                continue;
+            }
+            match diff.diff_type {
+                DiffType::Identical => summary.identical += 1,
+                DiffType::Modified => summary.modified += 1,
+                DiffType::Moved => summary.moved += 1,
+                DiffType::Removed => summary.removed += 1,
+                DiffType::Added => summary.added += 1,
+            }
+            summary.new_errors += diff.new_errors();
+            summary.type_changes += diff.type_changes.len();
+        }
+        summary
+    }
+
+    /// Add the counts of another summary, e.g. to total the sheets of a workbook
+    pub fn add(&mut self, other: &DiffSummary) {
+        self.identical += other.identical;
+        self.modified += other.modified;
+        self.moved += other.moved;
+        self.removed += other.removed;
+        self.added += other.added;
+        self.new_errors += other.new_errors;
+        self.type_changes += other.type_changes;
+        self.ignored += other.ignored;
+    }
+
+    /// Number of rows that are not identical
+    pub fn changed(&self) -> usize {
+        self.modified + self.moved + self.removed + self.added
+    }
 }
 
+/// Default share of equal cells for two rows to be considered a modification
+pub const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.5;
+
 /// Engine for comparing two worksheets
+#[derive(Debug, Clone)]
 pub struct WorksheetDiffer {
-    ignore_whitespace: bool,
+    normalizer: Normalizer,
+    /// Text normalization of single columns, replacing the general one
+    column_normalizers: HashMap<usize, Normalizer>,
+    key_columns: Vec<usize>,
+    align_mode: AlignMode,
+    similarity_threshold: f64,
+    align_columns: bool,
+    header_row: HeaderRow,
+    detect_moves: bool,
+    /// Columns that are shown but do not take part in the comparison
+    excluded_columns: HashSet<usize>,
+    /// Columns the user chose to ignore, shown greyed out
+    ignored_columns: HashSet<usize>,
+    /// Conditions selecting rows that are not compared
+    row_filters: Vec<RowFilter>,
+    compare_mode: CompareMode,
+    tolerance: Tolerance,
+    /// Tolerances of single columns, replacing the general tolerance
+    column_tolerances: HashMap<usize, Tolerance>,
+    coerce_types: bool,
+    /// Formats of dates stored as text, tried when types are coerced
+    date_formats: Vec<DateFormat>,
 }
 
 impl WorksheetDiffer {
     pub fn new() -> Self {
         WorksheetDiffer {
-            ignore_whitespace: false,
+            normalizer: Normalizer::new(),
+            column_normalizers: HashMap::new(),
+            key_columns: Vec::new(),
+            align_mode: AlignMode::default(),
+            similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
+            align_columns: false,
+            header_row: HeaderRow::default(),
+            detect_moves: false,
+            excluded_columns: HashSet::new(),
+            ignored_columns: HashSet::new(),
+            row_filters: Vec::new(),
+            compare_mode: CompareMode::default(),
+            tolerance: Tolerance::default(),
+            column_tolerances: HashMap::new(),
+            coerce_types: false,
+            date_formats: Vec::new(),
         }
     }
 
     /// Create a new differ with options
     pub fn with_options(ignore_whitespace: bool) -> Self {
-        WorksheetDiffer { ignore_whitespace }
+        let mut normalizer = Normalizer::new();
+        if ignore_whitespace {
+            normalizer = normalizer.with_step(TextNormalization::Whitespace);
+        }
+        Self::new().with_normalizer(normalizer)
+    }
+
+    /// Normalize string cells with the given pipeline before comparing them
+    ///
+    /// The values shown in the diff are not changed.
+    pub fn with_normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Use a different text normalization for the strings of one column
+    ///
+    /// # Arguments
+    /// * `col_idx` - Zero-based index of the column
+    /// * `normalizer` - Pipeline replacing the general one for this column
+    pub fn with_column_normalizer(mut self, col_idx: usize, normalizer: Normalizer) -> Self {
+        self.column_normalizers.insert(col_idx, normalizer);
+        self
+    }
+
+    /// Text normalization of the strings of a column
+    pub fn normalizer_for(&self, col_idx: usize) -> &Normalizer {
+        self.column_normalizers.get(&col_idx).unwrap_or(&self.normalizer)
+    }
+
+    /// Match rows on the given key columns instead of on row similarity
+    ///
+    /// Rows with the same key values are paired up and reported as Identical
+    /// or Modified; rows whose key only exists on one side are Removed or Added.
+    ///
+    /// # Arguments
+    /// * `key_columns` - Zero-based indices of the columns forming the primary key
+    pub fn with_key_columns(mut self, key_columns: Vec<usize>) -> Self {
+        self.key_columns = key_columns;
+        self
+    }
+
+    /// Select how rows of the two worksheets are aligned
+    ///
+    /// The sequence modes (Myers, patience) keep the order of both worksheets,
+    /// so added rows appear where they were inserted instead of at the end.
+    pub fn with_align_mode(mut self, align_mode: AlignMode) -> Self {
+        self.align_mode = align_mode;
+        self
+    }
+
+    /// Report rows that are out of order as [`DiffType::Moved`] (disabled by default)
+    ///
+    /// Of the rows paired between both worksheets, the largest set that is in
+    /// the same order in both keeps its diff type; the other rows are reported
+    /// as moved, also when their cells changed. Rows shifted by added or
+    /// removed rows are not moved. Without move detection, the order of the
+    /// rows is ignored.
+    pub fn with_move_detection(mut self, detect_moves: bool) -> Self {
+        self.detect_moves = detect_moves;
+        self
+    }
+
+    /// Set the share of equal cells required to report two rows as a modification
+    ///
+    /// Rows below the threshold are reported as Removed and Added instead.
+    /// Defaults to 0.5 (at least half of the cells are equal).
+    pub fn with_similarity_threshold(mut self, threshold: f64) -> Self {
+        self.similarity_threshold = threshold;
+        self
+    }
+
+    /// Align columns by their header instead of by position
+    ///
+    /// The header row of each worksheet is used (see [`with_header_row`]), or
+    /// its first non-empty row if it has none. Columns
+    /// are paired by header name (with a similarity fallback for renamed
+    /// headers), so inserted, removed or reordered columns do not make every
+    /// row look modified. The row data of the diffs then follows the combined
+    /// column layout described by [`compare_columns`].
+    ///
+    /// [`compare_columns`]: WorksheetDiffer::compare_columns
+    /// [`with_header_row`]: WorksheetDiffer::with_header_row
+    pub fn with_column_alignment(mut self, align_columns: bool) -> Self {
+        self.align_columns = align_columns;
+        self
+    }
+
+    /// Set which row of each worksheet is its header row
+    ///
+    /// The header rows are compared with each other and reported first among
+    /// the data rows, marked as [`RowDiff::header`], so a header is never
+    /// paired with a data row. Rows above the header, such as a title block,
+    /// are compared by position. Defaults to [`HeaderRow::None`], comparing
+    /// every row as data.
+    pub fn with_header_row(mut self, header_row: HeaderRow) -> Self {
+        self.header_row = header_row;
+        self
+    }
+
+    /// Leave columns out of the comparison, e.g. timestamps that change on every export
+    ///
+    /// Ignored cells do not make rows differ and do not count towards the
+    /// similarity of rows, but are still shown (greyed out) in the diff.
+    ///
+    /// # Arguments
+    /// * `columns` - Zero-based indices of the columns to ignore
+    pub fn with_ignored_columns(mut self, columns: Vec<usize>) -> Self {
+        self.ignored_columns.extend(columns);
+        self
+    }
+
+    /// Leave the rows selected by a filter out of the comparison
+    ///
+    /// A row is ignored if it is selected in either worksheet. Ignored rows
+    /// are only paired with an equal row or a row with the same key, never
+    /// by similarity, and are marked as [`RowDiff::ignored`] with all their
+    /// cells listed in [`RowDiff::ignored_cells`]. Paired ignored rows are
+    /// reported as Identical; an ignored row without a counterpart stays
+    /// Removed or Added. Ignored rows are counted apart in [`DiffSummary`].
+    /// With column alignment, filters see the rows in the combined column
+    /// layout.
+    pub fn with_row_filter(mut self, filter: RowFilter) -> Self {
+        self.row_filters.push(filter);
+        self
+    }
+
+    /// Select what is compared for cells that contain a formula
+    ///
+    /// Only applies to [`compare_worksheets`], with worksheets that were read
+    /// with their formulas.
+    ///
+    /// [`compare_worksheets`]: WorksheetDiffer::compare_worksheets
+    pub fn with_compare_mode(mut self, compare_mode: CompareMode) -> Self {
+        self.compare_mode = compare_mode;
+        self
+    }
+
+    /// Treat numbers that differ by at most the tolerance as equal
+    ///
+    /// Applies to integer and float cells; `Int(5)` and `Float(5.0)` are
+    /// always equal.
+    pub fn with_tolerance(mut self, tolerance: Tolerance) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Use a different tolerance for the numbers of one column
+    ///
+    /// # Arguments
+    /// * `col_idx` - Zero-based index of the column
+    /// * `tolerance` - Tolerance replacing the general one for this column
+    pub fn with_column_tolerance(mut self, col_idx: usize, tolerance: Tolerance) -> Self {
+        self.column_tolerances.insert(col_idx, tolerance);
+        self
+    }
+
+    /// Tolerance for the numbers of a column
+    pub fn tolerance_for(&self, col_idx: usize) -> Tolerance {
+        self.column_tolerances
+            .get(&col_idx)
+            .copied()
+            .unwrap_or(self.tolerance)
+    }
+
+    /// Treat text cells as equal to the number, boolean or date they represent
+    ///
+    /// With coercion, `"42"` equals `42`, `"TRUE"` equals `true` and
+    /// `"2024-03-01"` equals that date. Such cells are not reported as
+    /// modified but listed in [`RowDiff::type_changes`].
+    pub fn with_type_coercion(mut self, coerce_types: bool) -> Self {
+        self.coerce_types = coerce_types;
+        self
+    }
+
+    /// Formats of dates stored as text, tried after ISO 8601 when types are coerced
+    pub fn with_date_formats(mut self, date_formats: Vec<DateFormat>) -> Self {
+        self.date_formats = date_formats;
+        self
+    }
+
+    /// Compare the columns of two worksheets
+    ///
+    /// Returns one entry per column of the combined layout used in the row
+    /// diffs, or an empty list if column alignment is disabled.
+    pub fn compare_columns(&self, sheet1: &[Row], sheet2: &[Row]) -> Vec<ColumnDiff> {
+        self.column_alignment(sheet1, sheet2)
+            .map(|alignment| alignment.columns)
+            .unwrap_or_default()
     }
 
     /// Compare two worksheets and generate diff information
-    pub fn compare(&self, sheet1: &Worksheet, sheet2: &Worksheet) -> Vec<RowDiff> {
-        let mut result = Vec::new();
+    ///
+    /// Row addresses refer to worksheets starting at A1; use
+    /// [`compare_worksheets`] or [`SheetDiff::with_origins`] for the addresses
+    /// of worksheets that start elsewhere.
+    ///
+    /// [`compare_worksheets`]: WorksheetDiffer::compare_worksheets
+    /// [`SheetDiff::with_origins`]: crate::workbook::SheetDiff::with_origins
+    pub fn compare(&self, sheet1: &[Row], sheet2: &[Row]) -> Vec<RowDiff> {
+        let mut diffs = match self.column_alignment(sheet1, sheet2) {
+            Some(alignment) if alignment.has_changes() => self.compare_aligned(&alignment, sheet1, sheet2),
+            _ => {
+                let mut diffs = self.compare_sections(sheet1, sheet2);
+                if self.detect_moves {
+                    mark_moved(&mut diffs);
+                }
+                self.mark_ignored(&mut diffs);
+                diffs
+            }
+        };
 
+        // Matching strategies number rows differently, so number them by their position in the result
+        for (row_index, diff) in diffs.iter_mut().enumerate() {
+            diff.row_index = row_index;
+        }
+        SheetOrigins::default().locate(&mut diffs);
+        diffs
+    }
+
+    /// Compare two streams of sorted rows, yielding the row diffs as they are found
+    ///
+    /// Unlike [`compare`], neither worksheet is loaded: rows are paired with a
+    /// sorted merge, so only the current row of each stream (and the rows whose
+    /// keys only differ from it in case) is kept in memory.
+    /// Both streams must be sorted in ascending order on the key columns, or
+    /// on all compared cells without key columns, as Excel sorts them: numbers
+    /// and dates, then text ignoring case (after normalization), booleans,
+    /// errors and blank cells. Rows with equal keys are paired as Identical or
+    /// Modified, other rows are Removed or Added. Without key columns only
+    /// equal rows can be paired, so a changed row is reported as removed and
+    /// added. Moved rows and column alignment are not detected.
+    ///
+    /// The header row is looked up among the first rows of each stream (see
+    /// [`HeaderRow`]), which are compared by position as in [`compare`]. A row
+    /// out of order yields an error and ends the stream. Row addresses refer
+    /// to rows starting at A1; use [`RowDiffs::with_origins`] for rows that
+    /// start elsewhere, such as those of a [`RowStream`].
+    ///
+    /// # Arguments
+    /// * `rows1` - Rows of the old worksheet with their zero-based row number
+    /// * `rows2` - Rows of the new worksheet with their zero-based row number
+    ///
+    /// [`compare`]: WorksheetDiffer::compare
+    /// [`RowStream`]: crate::stream::RowStream
+    pub fn compare_iter<I1, I2>(&self, rows1: I1, rows2: I2) -> RowDiffs<'_, I1::IntoIter, I2::IntoIter>
+    where
+        I1: IntoIterator<Item = Result<SheetRow>>,
+        I2: IntoIterator<Item = Result<SheetRow>>,
+    {
+        RowDiffs::new(self, rows1.into_iter(), rows2.into_iter())
+    }
+
+    /// Compare the rows above the header, the header rows and the data rows of two worksheets
+    fn compare_sections(&self, sheet1: &[Row], sheet2: &[Row]) -> Vec<RowDiff> {
         // Normalize rows to handle different column counts
         let max_cols = sheet1
             .iter()
@@ -85,6 +723,66 @@ impl WorksheetDiffer {
             .unwrap_or(0)
             .max(sheet2.iter().map(|r| r.len()).max().unwrap_or(0));
 
+        let header1 = self.header_row.locate(sheet1);
+        let header2 = self.header_row.locate(sheet2);
+        if header1.is_none() && header2.is_none() {
+            return self.compare_rows(sheet1, sheet2, max_cols);
+        }
+
+        // Rows above the header (such as a title block) are paired by position, then the header rows
+        let mut result = Vec::new();
+        let (above1, above2) = (header1.unwrap_or(0), header2.unwrap_or(0));
+        for idx in 0..above1.max(above2) {
+            let idx1 = (idx < above1).then_some(idx);
+            let idx2 = (idx < above2).then_some(idx);
+            result.extend(self.positional_row(result.len(), idx1, sheet1, idx2, sheet2, max_cols));
+        }
+        if let Some(header) = self.positional_row(result.len(), header1, sheet1, header2, sheet2, max_cols) {
+            result.push(RowDiff { header: true, ..header });
+        }
+
+        // A sheet without a header row only has data rows
+        let data1 = header1.map_or(0, |row| row + 1);
+        let data2 = header2.map_or(0, |row| row + 1);
+        let data_diffs = self.compare_rows(&sheet1[data1..], &sheet2[data2..], max_cols);
+        result.extend(data_diffs.into_iter().map(|diff| RowDiff {
+            old_row: diff.old_row.map(|row| row + data1),
+            new_row: diff.new_row.map(|row| row + data2),
+            ..diff
+        }));
+        result
+    }
+
+    /// Build the diff of the rows at fixed positions of both worksheets, if either exists
+    fn positional_row(
+        &self,
+        row_index: usize,
+        idx1: Option<usize>,
+        sheet1: &[Row],
+        idx2: Option<usize>,
+        sheet2: &[Row],
+        max_cols: usize,
+    ) -> Option<RowDiff> {
+        let row1 = idx1.map(|idx| self.normalize_row(&sheet1[idx], max_cols));
+        let row2 = idx2.map(|idx| self.normalize_row(&sheet2[idx], max_cols));
+        let diff = match (row1, row2) {
+            (Some(row1), Some(row2)) => {
+                let diff = self.paired_row(0, std::slice::from_ref(&row1), 0, std::slice::from_ref(&row2));
+                RowDiff { row_index, ..diff }
+            }
+            (Some(row1), None) => RowDiff::new(row_index, DiffType::Removed, row1, vec![], None),
+            (None, Some(row2)) => RowDiff::new(row_index, DiffType::Added, row2, vec![], None),
+            (None, None) => return None,
+        };
+        Some(diff.with_positions(idx1, idx2))
+    }
+
+    /// Compare the rows of two worksheets sharing the same column layout
+    ///
+    /// Rows are padded with empty cells to `max_cols` columns.
+    fn compare_rows(&self, sheet1: &[Row], sheet2: &[Row], max_cols: usize) -> Vec<RowDiff> {
+        let mut result = Vec::new();
+
         let sheet1_normalized: Vec<Row> = sheet1
             .iter()
             .map(|row| self.normalize_row(row, max_cols))
@@ -94,71 +792,526 @@ impl WorksheetDiffer {
             .map(|row| self.normalize_row(row, max_cols))
             .collect();
 
-        // Create mapping of rows for comparison
-        let _sheet1_map: HashMap<Vec<u8>, usize> = sheet1_normalized
+        match self.align_mode {
+            AlignMode::Myers => {
+                return self.compare_sequence(&sheet1_normalized, &sheet2_normalized, Algorithm::Myers)
+            }
+            AlignMode::Patience => {
+                return self.compare_sequence(&sheet1_normalized, &sheet2_normalized, Algorithm::Patience)
+            }
+            AlignMode::Similarity => {}
+        }
+
+        if !self.key_columns.is_empty() {
+            return self.compare_by_key(&sheet1_normalized, &sheet2_normalized);
+        }
+
+        // Create mapping of rows for comparison; duplicate rows are kept as a
+        // queue so every copy in sheet1 consumes its own copy in sheet2
+        let mut sheet2_map: HashMap<Vec<u8>, VecDeque<usize>> = HashMap::new();
+        for (idx, row) in sheet2_normalized.iter().enumerate() {
+            sheet2_map
+                .entry(self.row_to_key(row))
+                .or_default()
+                .push_back(idx);
+        }
+
+        let mut processed_sheet2 = HashSet::new();
+
+        // First pass: find identical rows
+        let mut identical: Vec<Option<usize>> = vec![None; sheet1_normalized.len()];
+        for (idx1, row1) in sheet1_normalized.iter().enumerate() {
+            if let Some(idx2) = sheet2_map
+                .get_mut(&self.row_to_key(row1))
+                .and_then(|indices| indices.pop_front())
+            {
+                identical[idx1] = Some(idx2);
+                processed_sheet2.insert(idx2);
+            }
+        }
+
+        // Second pass: look up a modified version of each remaining row among the unmatched rows of sheet2
+        let mut index = SimilarityIndex::new(
+            self,
+            &sheet2_normalized,
+            (0..sheet2_normalized.len()).filter(|idx| !processed_sheet2.contains(idx)),
+        );
+
+        for (idx1, row1) in sheet1_normalized.iter().enumerate() {
+            if let Some(idx2) = identical[idx1] {
+                // Row exists in both sheets (identical)
+                result.push(self.identical_row(idx1, &sheet1_normalized, idx2, &sheet2_normalized));
+            } else if let Some((match_idx, _)) = index.best_match(row1, self.similarity_threshold) {
+                // Found a modified version (or a row that is equal within the numeric tolerance)
+                result.push(self.paired_row(idx1, &sheet1_normalized, match_idx, &sheet2_normalized));
+                index.remove(match_idx);
+                processed_sheet2.insert(match_idx);
+            } else {
+                // Row removed in sheet2
+                result.push(
+                    RowDiff::new(idx1, DiffType::Removed, row1.clone(), vec![], None)
+                        .with_positions(Some(idx1), None),
+                );
+            }
+        }
+
+        // Third pass: find added rows (in sheet2 but not in sheet1)
+        for (idx2, row2) in sheet2_normalized.iter().enumerate() {
+            if !processed_sheet2.contains(&idx2) {
+                result.push(
+                    RowDiff::new(result.len(), DiffType::Added, row2.clone(), vec![], None)
+                        .with_positions(None, Some(idx2)),
+                );
+            }
+        }
+
+        result
+    }
+
+    /// Compare two worksheets, taking the formulas of their cells into account
+    ///
+    /// Same as [`compare`], but formula cells are compared according to the
+    /// compare mode, and modified cells whose formula changed are listed in
+    /// [`RowDiff::formula_changes`]. The row data still holds the calculated
+    /// values, and row addresses account for the origin of each worksheet.
+    ///
+    /// [`compare`]: WorksheetDiffer::compare
+    pub fn compare_worksheets(&self, sheet1: &Worksheet, sheet2: &Worksheet) -> Vec<RowDiff> {
+        let origins = SheetOrigins::new(sheet1.origin, sheet2.origin);
+        if self.compare_mode == CompareMode::Values || (sheet1.formulas.is_empty() && sheet2.formulas.is_empty()) {
+            let mut diffs = self.compare(sheet1, sheet2);
+            origins.locate(&mut diffs);
+            return diffs;
+        }
+
+        // Compare formula cells by their formula text (and value), then show the values again
+        let rows1 = self.comparison_rows(sheet1);
+        let rows2 = self.comparison_rows(sheet2);
+        let columns = self.compare_columns(&rows1, &rows2);
+        let mut diffs = self.compare(&rows1, &rows2);
+
+        let value_at = |sheet: &Worksheet, (row, col): (usize, usize)| {
+            sheet.get(row).and_then(|r| r.get(col)).cloned().unwrap_or(CellValue::Empty)
+        };
+        for diff in &mut diffs {
+            for col_idx in 0..diff.row_data.len() {
+                let (old_pos, new_pos) = diff.cell_positions(&columns, col_idx);
+                let (sheet, shown_pos) = if diff.shows_old_cell(&columns, col_idx) {
+                    (sheet1, old_pos)
+                } else {
+                    (sheet2, new_pos)
+                };
+                if let Some(pos) = shown_pos.filter(|&(row, col)| sheet.formula(row, col).is_some()) {
+                    diff.row_data[col_idx] = value_at(sheet, pos);
+                }
+                if let (Some(original), Some(pos)) = (diff.original_row_data.as_mut(), old_pos) {
+                    if sheet1.formula(pos.0, pos.1).is_some() && col_idx < original.len() {
+                        original[col_idx] = value_at(sheet1, pos);
+                    }
+                }
+            }
+
+            diff.formula_changes = diff
+                .modified_cells
+                .iter()
+                .filter_map(|&col_idx| {
+                    let (old_pos, new_pos) = diff.cell_positions(&columns, col_idx);
+                    let old = old_pos.and_then(|(row, col)| sheet1.formula(row, col));
+                    let new = new_pos.and_then(|(row, col)| sheet2.formula(row, col));
+                    (old != new).then(|| FormulaChange {
+                        column: col_idx,
+                        old: old.map(str::to_string),
+                        new: new.map(str::to_string),
+                    })
+                })
+                .collect();
+        }
+
+        origins.locate(&mut diffs);
+        diffs
+    }
+
+    /// Rows of a worksheet as compared in the compare mode, with formula cells replaced by their formula
+    fn comparison_rows(&self, sheet: &Worksheet) -> Vec<Row> {
+        sheet
             .iter()
             .enumerate()
-            .map(|(idx, row)| (self.row_to_key(row), idx))
+            .map(|(row_idx, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(col_idx, value)| match sheet.formula(row_idx, col_idx) {
+                        Some(formula) if self.compare_mode == CompareMode::Both => {
+                            let value = self.normalized(col_idx, value);
+                            CellValue::String(format!("={}\u{0}{:?}", formula, value))
+                        }
+                        Some(formula) => CellValue::String(format!("={}", formula)),
+                        None => value.clone(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Compare two worksheets after rearranging their rows into the combined column layout
+    fn compare_aligned(&self, alignment: &ColumnAlignment, sheet1: &[Row], sheet2: &[Row]) -> Vec<RowDiff> {
+        let sheet1: Vec<Row> = sheet1.iter().map(|row| alignment.project_old(row)).collect();
+        let sheet2: Vec<Row> = sheet2.iter().map(|row| alignment.project_new(row)).collect();
+
+        // Added and removed columns have no counterpart, so they are shown but not compared
+        let mut scoped = self.clone();
+        scoped.align_columns = false;
+        scoped.excluded_columns = self
+            .excluded_columns
+            .iter()
+            .filter_map(|&col| alignment.old_to_layout(col))
+            .chain(alignment.columns.iter().enumerate().filter_map(|(idx, column)| {
+                matches!(column.change, ColumnChange::Added | ColumnChange::Removed).then_some(idx)
+            }))
             .collect();
-        let sheet2_map: HashMap<Vec<u8>, usize> = sheet2_normalized
+        scoped.ignored_columns = self
+            .ignored_columns
             .iter()
-            .enumerate()
-            .map(|(idx, row)| (self.row_to_key(row), idx))
+            .filter_map(|&col| alignment.old_to_layout(col))
+            .collect();
+        scoped.row_filters = self
+            .row_filters
+            .iter()
+            .filter_map(|filter| match filter {
+                RowFilter::Pattern { column, pattern } => {
+                    Some(RowFilter::pattern(alignment.old_to_layout(*column)?, pattern.clone()))
+                }
+                RowFilter::Predicate(_) => Some(filter.clone()),
+            })
+            .collect();
+        scoped.key_columns = self
+            .key_columns
+            .iter()
+            .filter_map(|&col| alignment.old_to_layout(col))
+            .collect();
+        scoped.column_normalizers = self
+            .column_normalizers
+            .iter()
+            .filter_map(|(&col, normalizer)| Some((alignment.old_to_layout(col)?, normalizer.clone())))
+            .collect();
+        scoped.column_tolerances = self
+            .column_tolerances
+            .iter()
+            .filter_map(|(&col, &tolerance)| Some((alignment.old_to_layout(col)?, tolerance)))
             .collect();
 
-        let mut processed_sheet1 = HashSet::new();
-        let mut processed_sheet2 = HashSet::new();
+        let mut diffs = scoped.compare(&sheet1, &sheet2);
 
-        // First pass: find identical and modified rows
-        for (idx1, row1) in sheet1_normalized.iter().enumerate() {
-            let key1 = self.row_to_key(row1);
+        // Show the values of added and removed columns from the side where they exist
+        for diff in &mut diffs {
+            for (col_idx, column) in alignment.columns.iter().enumerate() {
+                let source = match column.change {
+                    ColumnChange::Added => diff.new_row.map(|idx| &sheet2[idx]),
+                    ColumnChange::Removed => diff.old_row.map(|idx| &sheet1[idx]),
+                    _ => None,
+                };
+                if let (Some(row), Some(cell)) = (source, diff.row_data.get_mut(col_idx)) {
+                    *cell = row[col_idx].clone();
+                }
+            }
+        }
 
-            if let Some(&idx2) = sheet2_map.get(&key1) {
-                // Row exists in both sheets (identical)
-                result.push(RowDiff::new(idx1, DiffType::Identical, row1.clone(), vec![], None));
-                processed_sheet1.insert(idx1);
-                processed_sheet2.insert(idx2);
+        diffs
+    }
+
+    /// Align the columns of both worksheets on their header rows, if enabled
+    fn column_alignment<'a>(&self, sheet1: &'a [Row], sheet2: &'a [Row]) -> Option<ColumnAlignment> {
+        if !self.align_columns {
+            return None;
+        }
+        let header = |sheet: &'a [Row]| match self.header_row.locate(sheet) {
+            Some(row) => sheet.get(row),
+            None => first_non_empty_row(sheet),
+        };
+        Some(ColumnAlignment::from_headers(header(sheet1)?, header(sheet2)?, DEFAULT_RENAME_THRESHOLD))
+    }
+
+    /// Whether a column takes part in the comparison
+    pub(crate) fn is_compared(&self, col_idx: usize) -> bool {
+        !self.excluded_columns.contains(&col_idx) && !self.ignored_columns.contains(&col_idx)
+    }
+
+    /// Whether a row is selected by one of the row filters
+    pub(crate) fn is_ignored_row(&self, row: &Row) -> bool {
+        self.row_filters.iter().any(|filter| filter.matches(row))
+    }
+
+    /// Mark ignored rows, reporting pairs involving one as Identical, and list the ignored cells of each row
+    pub(crate) fn mark_ignored(&self, diffs: &mut [RowDiff]) {
+        if self.ignored_columns.is_empty() && self.row_filters.is_empty() {
+            return;
+        }
+        let mut ignored_columns: Vec<usize> = self.ignored_columns.iter().copied().collect();
+        ignored_columns.sort_unstable();
+
+        for diff in diffs {
+            // The header row names the columns, so it is never ignored as a row
+            let ignored_row = !diff.header
+                && (self.is_ignored_row(&diff.row_data)
+                    || diff.original_row_data.as_ref().is_some_and(|row| self.is_ignored_row(row)));
+            if ignored_row {
+                diff.ignored = true;
+                if diff.old_row.is_some() && diff.new_row.is_some() {
+                    diff.diff_type = DiffType::Identical;
+                }
+                diff.modified_cells.clear();
+                diff.original_row_data = None;
+                diff.type_changes.clear();
+                diff.ignored_cells = (0..diff.row_data.len()).collect();
             } else {
-                // Check if this row has a modified version in sheet2
-                if let Some((match_idx, modified_cells)) =
-                    self.find_modified_row(row1, &sheet2_normalized, &processed_sheet2)
-                {
-                    // Found a modified version
-                    result.push(RowDiff::new(
-                        idx1,
-                        DiffType::Modified,
-                        sheet2_normalized[match_idx].clone(),
-                        modified_cells,
-                        Some(row1.clone()),
-                    ));
-                    processed_sheet1.insert(idx1);
-                    processed_sheet2.insert(match_idx);
-                } else {
-                    // Row removed in sheet2
-                    result.push(RowDiff::new(idx1, DiffType::Removed, row1.clone(), vec![], None));
-                    processed_sheet1.insert(idx1);
+                diff.ignored_cells = ignored_columns
+                    .iter()
+                    .copied()
+                    .filter(|&col_idx| col_idx < diff.row_data.len())
+                    .collect();
+            }
+        }
+    }
+
+    /// Compare normalized worksheets by pairing rows with equal key values
+    fn compare_by_key(&self, sheet1: &[Row], sheet2: &[Row]) -> Vec<RowDiff> {
+        let mut result = Vec::new();
+
+        // Rows sharing a key are consumed in order, so duplicate keys pair up one by one
+        let mut sheet2_keys: HashMap<Vec<u8>, VecDeque<usize>> = HashMap::new();
+        for (idx, row) in sheet2.iter().enumerate() {
+            sheet2_keys
+                .entry(self.key_of(row))
+                .or_default()
+                .push_back(idx);
+        }
+
+        let mut processed_sheet2 = HashSet::new();
+
+        for (idx1, row1) in sheet1.iter().enumerate() {
+            let matched = sheet2_keys
+                .get_mut(&self.key_of(row1))
+                .and_then(|indices| indices.pop_front());
+
+            match matched {
+                Some(idx2) => {
+                    result.push(self.paired_row(idx1, sheet1, idx2, sheet2));
+                    processed_sheet2.insert(idx2);
+                }
+                None => {
+                    result.push(
+                        RowDiff::new(idx1, DiffType::Removed, row1.clone(), vec![], None)
+                            .with_positions(Some(idx1), None),
+                    );
                 }
             }
         }
 
-        // Second pass: find added rows (in sheet2 but not in sheet1)
-        for (idx2, row2) in sheet2_normalized.iter().enumerate() {
+        for (idx2, row2) in sheet2.iter().enumerate() {
             if !processed_sheet2.contains(&idx2) {
-                result.push(RowDiff::new(
-                    result.len(),
-                    DiffType::Added,
-                    row2.clone(),
-                    vec![],
-                    None,
-                ));
+                result.push(
+                    RowDiff::new(result.len(), DiffType::Added, row2.clone(), vec![], None)
+                        .with_positions(None, Some(idx2)),
+                );
             }
         }
 
         result
     }
 
+    /// Compare normalized worksheets with an order-preserving sequence diff
+    ///
+    /// Rows are aligned on their keys (the key columns if set, the whole row
+    /// otherwise). Within each changed hunk, removed rows are paired with
+    /// similar added rows as modifications, and added rows are interleaved at
+    /// the position where they were inserted.
+    fn compare_sequence(&self, sheet1: &[Row], sheet2: &[Row], algorithm: Algorithm) -> Vec<RowDiff> {
+        let keys1: Vec<Vec<u8>> = sheet1.iter().map(|row| self.match_key(row)).collect();
+        let keys2: Vec<Vec<u8>> = sheet2.iter().map(|row| self.match_key(row)).collect();
+
+        let mut result = Vec::new();
+
+        for op in capture_diff_slices(algorithm, &keys1, &keys2) {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            match tag {
+                DiffTag::Equal => {
+                    for (idx1, idx2) in old_range.zip(new_range) {
+                        let diff = self.paired_row(idx1, sheet1, idx2, sheet2);
+                        result.push(RowDiff { row_index: result.len(), ..diff });
+                    }
+                }
+                DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                    self.align_hunk(&mut result, sheet1, old_range, sheet2, new_range);
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Emit the rows of a changed hunk in document order
+    fn align_hunk(
+        &self,
+        result: &mut Vec<RowDiff>,
+        sheet1: &[Row],
+        old_range: Range<usize>,
+        sheet2: &[Row],
+        new_range: Range<usize>,
+    ) {
+        let mut index = SimilarityIndex::new(self, sheet2, new_range.clone());
+        let mut processed_hunk2 = HashSet::new();
+        let mut pending_added = new_range.clone().peekable();
+
+        for idx1 in old_range {
+            let row1 = &sheet1[idx1];
+
+            // Rows with different keys are different records, so only pair by similarity without keys
+            let modified = if self.key_columns.is_empty() {
+                index.best_match(row1, self.similarity_threshold)
+            } else {
+                None
+            };
+
+            match modified {
+                Some((idx2, _)) => {
+                    index.remove(idx2);
+                    processed_hunk2.insert(idx2);
+
+                    // Added rows inserted before the modified row come first
+                    while let Some(&added) = pending_added.peek() {
+                        if added >= idx2 {
+                            break;
+                        }
+                        pending_added.next();
+                        if !processed_hunk2.contains(&added) {
+                            self.push_added(result, added, sheet2);
+                        }
+                    }
+
+                    let diff = self.paired_row(idx1, sheet1, idx2, sheet2);
+                    result.push(RowDiff { row_index: result.len(), ..diff });
+                }
+                None => {
+                    result.push(
+                        RowDiff::new(result.len(), DiffType::Removed, row1.clone(), vec![], None)
+                            .with_positions(Some(idx1), None),
+                    );
+                }
+            }
+        }
+
+        for added in pending_added {
+            if !processed_hunk2.contains(&added) {
+                self.push_added(result, added, sheet2);
+            }
+        }
+    }
+
+    /// Append an Added row for the given sheet2 row
+    fn push_added(&self, result: &mut Vec<RowDiff>, idx2: usize, sheet2: &[Row]) {
+        result.push(
+            RowDiff::new(result.len(), DiffType::Added, sheet2[idx2].clone(), vec![], None)
+                .with_positions(None, Some(idx2)),
+        );
+    }
+
+    /// Build the diff for two rows that were matched up (Identical or Modified)
+    pub(crate) fn paired_row(&self, idx1: usize, sheet1: &[Row], idx2: usize, sheet2: &[Row]) -> RowDiff {
+        let row1 = &sheet1[idx1];
+        let row2 = &sheet2[idx2];
+        let modified_cells = self.modified_columns(row1, row2);
+        if modified_cells.is_empty() {
+            return self.identical_row(idx1, sheet1, idx2, sheet2);
+        }
+        let type_changes = self.type_changed_columns(row1, row2, &modified_cells);
+        RowDiff {
+            type_changes,
+            ..RowDiff::new(idx1, DiffType::Modified, row2.clone(), modified_cells, Some(row1.clone()))
+        }
+        .with_positions(Some(idx1), Some(idx2))
+    }
+
+    /// Build the diff for two rows whose cells are all equal
+    fn identical_row(&self, idx1: usize, sheet1: &[Row], idx2: usize, sheet2: &[Row]) -> RowDiff {
+        let row1 = &sheet1[idx1];
+        let row2 = &sheet2[idx2];
+        let type_changes = self.type_changed_columns(row1, row2, &[]);
+        let diff = if type_changes.is_empty() {
+            RowDiff::new(idx1, DiffType::Identical, row1.clone(), vec![], None)
+        } else {
+            // Keep both rows, so the old and the new type can be shown
+            RowDiff::new(idx1, DiffType::Identical, row2.clone(), vec![], Some(row1.clone()))
+        };
+        RowDiff { type_changes, ..diff }.with_positions(Some(idx1), Some(idx2))
+    }
+
+    /// List the column indices of equal cells whose type changed between two rows
+    ///
+    /// Only text can be coerced to another type, so a type change is an equal
+    /// pair of a text cell and a cell of another type.
+    fn type_changed_columns(&self, row1: &Row, row2: &Row, modified_cells: &[usize]) -> Vec<usize> {
+        if !self.coerce_types {
+            return Vec::new();
+        }
+        let is_text = |value: &CellValue| matches!(value, CellValue::String(_));
+        row1.iter()
+            .zip(row2.iter())
+            .enumerate()
+            .filter(|(col_idx, (v1, v2))| {
+                self.is_compared(*col_idx) && !modified_cells.contains(col_idx) && is_text(v1) != is_text(v2)
+            })
+            .map(|(col_idx, _)| col_idx)
+            .collect()
+    }
+
+    /// Key used to align rows: the key columns if set, the whole row otherwise
+    fn match_key(&self, row: &Row) -> Vec<u8> {
+        if self.key_columns.is_empty() {
+            self.row_to_key(row)
+        } else {
+            self.key_of(row)
+        }
+    }
+
+    /// Header row setting of the differ
+    pub(crate) fn header_row(&self) -> HeaderRow {
+        self.header_row
+    }
+
+    /// Normalized cells a row is sorted on in a streaming comparison
+    ///
+    /// These are the key columns if set, all cells otherwise, with the cells
+    /// of columns that are not compared left empty.
+    pub(crate) fn sort_key(&self, row: &Row) -> Row {
+        if self.key_columns.is_empty() {
+            return row
+                .iter()
+                .enumerate()
+                .map(|(col_idx, value)| {
+                    if self.is_compared(col_idx) {
+                        self.normalized(col_idx, value)
+                    } else {
+                        CellValue::Empty
+                    }
+                })
+                .collect();
+        }
+        self.key_columns
+            .iter()
+            .map(|&col| self.normalized(col, row.get(col).unwrap_or(&CellValue::Empty)))
+            .collect()
+    }
+
+    /// Build the lookup key of a row from its key columns
+    fn key_of(&self, row: &Row) -> Vec<u8> {
+        self.cells_to_key(
+            self.key_columns
+                .iter()
+                .map(|&col| (col, row.get(col).unwrap_or(&CellValue::Empty))),
+        )
+    }
+
     /// Normalize a row to a target length by padding with Empty
-    fn normalize_row(&self, row: &Row, target_length: usize) -> Row {
+    pub(crate) fn normalize_row(&self, row: &Row, target_length: usize) -> Row {
         if row.len() >= target_length {
             row[..target_length].to_vec()
         } else {
@@ -170,64 +1323,112 @@ impl WorksheetDiffer {
 
     /// Convert a row to a hashable key for comparison
     fn row_to_key(&self, row: &Row) -> Vec<u8> {
+        self.cells_to_key(
+            row.iter()
+                .enumerate()
+                .filter(|(col_idx, _)| self.is_compared(*col_idx)),
+        )
+    }
+
+    /// Convert a sequence of cells (with their column index) to a hashable key
+    fn cells_to_key<'a>(&self, cells: impl Iterator<Item = (usize, &'a CellValue)>) -> Vec<u8> {
         // Create a simple hash by concatenating normalized string representations
         let mut key = Vec::new();
-        for value in row {
-            let normalized = value.normalize_with_options(self.ignore_whitespace);
-            let s = normalized.to_string();
-            key.extend_from_slice(s.as_bytes());
+        for (col_idx, value) in cells {
+            key.extend_from_slice(&self.cell_key(col_idx, value));
             key.push(0); // separator
         }
         key
     }
 
-    /// Find a row that matches the target row with some modifications
+    /// Convert a single cell to a hashable key
     ///
-    /// Returns (row_index, list of modified cell indices) or None
-    fn find_modified_row(
-        &self,
-        target_row: &Row,
-        sheet: &[Row],
-        processed: &HashSet<usize>,
-    ) -> Option<(usize, Vec<usize>)> {
-        // Simple heuristic: if more than 50% of cells match, consider it a modified row
-        let mut best_match: Option<usize> = None;
-        let mut best_score = 0.0;
-        let mut best_modified = Vec::new();
-
-        for (idx, row) in sheet.iter().enumerate() {
-            if processed.contains(&idx) {
-                continue;
-            }
+    /// Cells that compare equal without a numeric tolerance always have equal
+    /// keys. The key starts with the type of the normalized value, so text
+    /// "42" and the number 42 only share a key when type coercion converts
+    /// the text.
+    pub(crate) fn cell_key(&self, col_idx: usize, value: &CellValue) -> Vec<u8> {
+        let value = self.normalized(col_idx, value);
+        let tag = match value {
+            // Integers and floats are compared as numbers, so 5 and 5.0 share a key
+            CellValue::Int(_) | CellValue::Float(_) => b'n',
+            CellValue::String(_) => b's',
+            CellValue::Bool(_) => b'b',
+            CellValue::DateTime(_) => b'd',
+            CellValue::Error(_) => b'e',
+            CellValue::Empty => b'_',
+        };
+        let mut key = vec![tag];
+        key.extend_from_slice(value.to_string().as_bytes());
+        key
+    }
 
-            let mut matches = 0;
-            let mut modified = Vec::new();
+    /// Normalize a cell of a column for comparison
+    ///
+    /// With type coercion, text representing another type is converted to it;
+    /// other text gets the text normalization of the column.
+    pub(crate) fn normalized(&self, col_idx: usize, value: &CellValue) -> CellValue {
+        match value {
+            CellValue::String(s) => match self.coerce_types.then(|| self.coerce(s)) {
+                Some(CellValue::String(_)) | None => self.normalized_text(col_idx, s),
+                Some(coerced) => coerced.normalize(),
+            },
+            _ => value.normalize(),
+        }
+    }
 
-            for (col_idx, (v1, v2)) in target_row.iter().zip(row.iter()).enumerate() {
-                if v1.normalize_with_options(self.ignore_whitespace)
-                    == v2.normalize_with_options(self.ignore_whitespace)
-                {
-                    matches += 1;
-                } else {
-                    modified.push(col_idx);
-                }
-            }
+    /// Apply the text normalization of a column to the text of a string cell
+    fn normalized_text(&self, col_idx: usize, text: &str) -> CellValue {
+        let normalizer = self.normalizer_for(col_idx);
+        if normalizer.is_empty() {
+            CellValue::String(text.to_string())
+        } else {
+            CellValue::String(normalizer.apply(text))
+        }
+    }
 
-            let score = if !target_row.is_empty() {
-                matches as f64 / target_row.len() as f64
-            } else {
-                0.0
-            };
+    /// Key of a cell in the similarity index of a column
+    ///
+    /// Numbers in a column with a tolerance can be equal without having equal
+    /// keys, so they all share one key there and are compared exactly later.
+    pub(crate) fn index_key(&self, col_idx: usize, value: &CellValue) -> Vec<u8> {
+        if value.as_number().is_some() && !self.tolerance_for(col_idx).is_zero() {
+            return b"\x01number".to_vec();
+        }
+        self.cell_key(col_idx, value)
+    }
 
-            // Require at least 50% match to consider it a modification
-            if score > best_score && score >= 0.5 {
-                best_score = score;
-                best_match = Some(idx);
-                best_modified = modified;
-            }
+    /// Convert text to the number, boolean, error or date it represents, if any
+    fn coerce(&self, text: &str) -> CellValue {
+        match infer_cell_value(text) {
+            CellValue::String(_) => self
+                .date_formats
+                .iter()
+                .find_map(|format| format.parse(text))
+                .map(CellValue::DateTime)
+                .unwrap_or_else(|| CellValue::String(text.to_string())),
+            value => value,
+        }
+    }
+
+    /// Whether two cells of a column are equal after normalization and within the numeric tolerance
+    pub(crate) fn cells_equal(&self, col_idx: usize, v1: &CellValue, v2: &CellValue) -> bool {
+        let v1 = self.normalized(col_idx, v1);
+        let v2 = self.normalized(col_idx, v2);
+        match (v1.as_number(), v2.as_number()) {
+            (Some(n1), Some(n2)) => n1 == n2 || self.tolerance_for(col_idx).allows(n1, n2),
+            _ => v1 == v2,
         }
+    }
 
-        best_match.map(|idx| (idx, best_modified))
+    /// List the column indices whose values differ between two rows
+    pub(crate) fn modified_columns(&self, row1: &Row, row2: &Row) -> Vec<usize> {
+        row1.iter()
+            .zip(row2.iter())
+            .enumerate()
+            .filter(|(col_idx, (v1, v2))| self.is_compared(*col_idx) && !self.cells_equal(*col_idx, v1, v2))
+            .map(|(col_idx, _)| col_idx)
+            .collect()
     }
 }
 
@@ -236,3 +1437,33 @@ impl Default for WorksheetDiffer {
         Self::new()
     }
 }
+
+/// First row containing at least one non-empty cell
+fn first_non_empty_row(sheet: &[Row]) -> Option<&Row> {
+    sheet
+        .iter()
+        .find(|row| row.iter().any(|cell| *cell != CellValue::Empty))
+}
+
+/// Report the paired rows that are out of order as Moved
+///
+/// The rows in order are the longest sequence of pairs whose rows increase in
+/// both worksheets (see [`longest_increasing`]). Header rows keep their type.
+fn mark_moved(diffs: &mut [RowDiff]) {
+    // Paired rows in the order of the first worksheet: (old row, new row, diff index)
+    let mut pairs: Vec<(usize, usize, usize)> = diffs
+        .iter()
+        .enumerate()
+        .filter(|(_, diff)| !diff.header)
+        .filter_map(|(idx, diff)| Some((diff.old_row?, diff.new_row?, idx)))
+        .collect();
+    pairs.sort_unstable();
+
+    let new_rows: Vec<Option<usize>> = pairs.iter().map(|&(_, new_row, _)| Some(new_row)).collect();
+    let in_order = longest_increasing(&new_rows);
+    for (&(_, _, idx), in_order) in pairs.iter().zip(in_order) {
+        if !in_order {
+            diffs[idx].diff_type = DiffType::Moved;
+        }
+    }
+}
@@ -1,35 +1,143 @@
 //! Excel file reader implementation using calamine.
 
 use anyhow::{Context, Result};
-use calamine::{open_workbook, Reader, Xlsx, Data};
-use std::path::Path;
+use calamine::{open_workbook, Data, Ods, Range, Reader, Sheets, Xls, Xlsb, Xlsx};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek};
 
-use crate::reader::{CellValue, FileReader, Row, Worksheet};
+use crate::datetime::parse_iso_datetime;
+use crate::range::{CellRange, RangeSelection};
+use crate::reader::{has_extension, CellValue, FileReader, Row, Worksheet};
+use crate::styles::read_xlsx_styles;
 
-/// Reader for Excel (.xlsx) files
-pub struct ExcelReader;
+/// File extensions of the workbook formats, used when a file cannot be inspected
+const WORKBOOK_EXTENSIONS: [&str; 7] = ["xlsx", "xlsm", "xlam", "xls", "xla", "xlsb", "ods"];
 
-impl ExcelReader {
-    pub fn new() -> Self {
-        ExcelReader
+/// Signature of OLE compound documents (legacy .xls workbooks)
+const OLE_SIGNATURE: [u8; 8] = [0xD0, 0xCF, 0x11, 0xE0, 0xA1, 0xB1, 0x1A, 0xE1];
+
+/// Signature of zip archives (.xlsx, .xlsm, .xlsb and .ods workbooks)
+const ZIP_SIGNATURE: [u8; 4] = [b'P', b'K', 0x03, 0x04];
+
+/// Contents of the mimetype part of OpenDocument spreadsheets
+const ODS_MIMETYPE: &str = "application/vnd.oasis.opendocument.spreadsheet";
+
+/// Workbook file formats that can be read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkbookFormat {
+    /// Legacy binary Excel workbook (.xls)
+    Xls,
+    /// Office Open XML workbook (.xlsx, .xlsm)
+    Xlsx,
+    /// Binary Excel workbook (.xlsb)
+    Xlsb,
+    /// OpenDocument spreadsheet (.ods)
+    Ods,
+}
+
+impl WorkbookFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WorkbookFormat::Xls => "xls",
+            WorkbookFormat::Xlsx => "xlsx",
+            WorkbookFormat::Xlsb => "xlsb",
+            WorkbookFormat::Ods => "ods",
+        }
+    }
+
+    /// Detect the format of a workbook from its contents
+    ///
+    /// The file extension is ignored, so mislabeled files (such as an .xls
+    /// file that is really an .xlsx workbook) are still recognized.
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to the file
+    pub fn detect(file_path: &str) -> Result<Self> {
+        let mut file = File::open(file_path)
+            .with_context(|| format!("Failed to open file: {}", file_path))?;
+        let mut signature = [0u8; 8];
+        let length = file.read(&mut signature)?;
+        let signature = &signature[..length];
+
+        if signature == OLE_SIGNATURE {
+            return Ok(WorkbookFormat::Xls);
+        }
+        if !signature.starts_with(&ZIP_SIGNATURE) {
+            anyhow::bail!("{} is not a spreadsheet workbook", file_path);
+        }
+
+        // Zip based formats are told apart by the parts they contain
+        let mut archive = zip::ZipArchive::new(BufReader::new(file))
+            .with_context(|| format!("{} is not a valid zip archive", file_path))?;
+        if archive.index_for_name("xl/workbook.bin").is_some() {
+            Ok(WorkbookFormat::Xlsb)
+        } else if archive.index_for_name("xl/workbook.xml").is_some() {
+            Ok(WorkbookFormat::Xlsx)
+        } else if is_ods_archive(&mut archive) {
+            Ok(WorkbookFormat::Ods)
+        } else {
+            anyhow::bail!("{} is a zip archive but not a spreadsheet workbook", file_path)
+        }
     }
 }
 
-impl Default for ExcelReader {
-    fn default() -> Self {
-        Self::new()
+/// Whether a zip archive is an OpenDocument spreadsheet (and not e.g. a text document)
+fn is_ods_archive<R: Read + Seek>(archive: &mut zip::ZipArchive<R>) -> bool {
+    let mut mimetype = String::new();
+    match archive.by_name("mimetype") {
+        Ok(mut entry) => entry.read_to_string(&mut mimetype).is_ok() && mimetype.trim() == ODS_MIMETYPE,
+        Err(_) => false,
     }
 }
 
-impl FileReader for ExcelReader {
-    fn read(&self, file_path: &str, sheet_name: Option<&str>) -> Result<Worksheet> {
-        if !self.supports(file_path) {
-            anyhow::bail!("File {} is not a valid .xlsx file", file_path);
-        }
+/// Reader for Excel (.xlsx, .xlsm, .xlsb, .xls) and OpenDocument (.ods) files
+pub struct ExcelReader {
+    read_formulas: bool,
+}
+
+impl ExcelReader {
+    pub fn new() -> Self {
+        ExcelReader { read_formulas: false }
+    }
 
-        let mut workbook: Xlsx<_> = open_workbook(file_path)
-            .with_context(|| format!("Failed to open workbook: {}", file_path))?;
+    /// Also read the formulas of the cells into [`Worksheet::formulas`]
+    ///
+    /// Cell values are always the values calculated when the workbook was last
+    /// saved; reading the formulas takes a second pass over the sheet.
+    pub fn with_formulas(mut self, read_formulas: bool) -> Self {
+        self.read_formulas = read_formulas;
+        self
+    }
+
+    /// Open a workbook with the calamine reader for its detected format
+    fn open(&self, file_path: &str) -> Result<Sheets<BufReader<File>>> {
+        self.open_as(file_path, WorkbookFormat::detect(file_path)?)
+    }
+
+    /// Open a workbook with the calamine reader for the given format
+    fn open_as(&self, file_path: &str, format: WorkbookFormat) -> Result<Sheets<BufReader<File>>> {
+        let context = || format!("Failed to open {} workbook: {}", format.as_str(), file_path);
+
+        let workbook = match format {
+            WorkbookFormat::Xls => Sheets::Xls(open_workbook::<Xls<_>, _>(file_path).with_context(context)?),
+            WorkbookFormat::Xlsx => Sheets::Xlsx(open_workbook::<Xlsx<_>, _>(file_path).with_context(context)?),
+            WorkbookFormat::Xlsb => Sheets::Xlsb(open_workbook::<Xlsb<_>, _>(file_path).with_context(context)?),
+            WorkbookFormat::Ods => Sheets::Ods(open_workbook::<Ods<_>, _>(file_path).with_context(context)?),
+        };
+
+        Ok(workbook)
+    }
 
+    /// Read a sheet of an open workbook, or only a region of it
+    fn read_sheet(
+        &self,
+        workbook: &mut Sheets<BufReader<File>>,
+        format: WorkbookFormat,
+        file_path: &str,
+        sheet_name: Option<&str>,
+        region: Option<&CellRange>,
+    ) -> Result<Worksheet> {
         // Determine which sheet to read
         let sheet_to_read = if let Some(name) = sheet_name {
             name.to_string()
@@ -43,12 +151,19 @@ impl FileReader for ExcelReader {
         };
 
         // Read the worksheet
-        let range = workbook
+        let mut range = workbook
             .worksheet_range(&sheet_to_read)
             .with_context(|| format!("Failed to read sheet: {}", sheet_to_read))?;
 
+        // The used range starts at the first non-empty cell of the sheet, not at A1
+        let mut origin = range.start().map_or((0, 0), |(row, col)| (row as usize, col as usize));
+        if let Some(region) = region {
+            range = crop_to_region(&range, region);
+            origin = region.start();
+        }
+
         // Convert range to our Worksheet type
-        let mut worksheet = Worksheet::new();
+        let mut worksheet = Worksheet::new().with_origin(origin);
 
         for row in range.rows() {
             let converted_row: Row = row
@@ -59,34 +174,158 @@ impl FileReader for ExcelReader {
                     Data::String(s) => CellValue::String(s.clone()),
                     Data::Bool(b) => CellValue::Bool(*b),
                     Data::Empty => CellValue::Empty,
-                    Data::Error(_) => CellValue::Empty,
+                    Data::Error(e) => CellValue::Error(e.to_string()),
                     Data::DateTime(dt) => CellValue::DateTime(dt.as_f64()), // Store as DateTime to preserve formatting
-                    Data::DateTimeIso(s) => CellValue::String(s.clone()),
-                    Data::DurationIso(s) => CellValue::String(s.clone())
+                    // OpenDocument stores dates as ISO 8601 text
+                    Data::DateTimeIso(s) => parse_iso_datetime(s)
+                        .map(CellValue::DateTime)
+                        .unwrap_or_else(|| CellValue::String(s.clone())),
+                    Data::DurationIso(s) => CellValue::String(s.clone()),
                 })
                 .collect();
             worksheet.push(converted_row);
         }
 
+        if let (true, Some((first_row, first_col))) = (self.read_formulas, range.start()) {
+            let formula_range = workbook
+                .worksheet_formula(&sheet_to_read)
+                .with_context(|| format!("Failed to read formulas of sheet: {}", sheet_to_read))?;
+
+            // Both ranges start at their first non-empty cell, so positions are shifted to the values
+            let mut formulas = HashMap::new();
+            if let Some((row_offset, col_offset)) = formula_range.start() {
+                for (row, col, formula) in formula_range.cells() {
+                    let row = (row_offset + row as u32).checked_sub(first_row);
+                    let col = (col_offset + col as u32).checked_sub(first_col);
+                    if let (Some(row), Some(col), false) = (row, col, formula.is_empty()) {
+                        formulas.insert((row as usize, col as usize), formula.clone());
+                    }
+                }
+            }
+            worksheet = worksheet.with_formulas(formulas);
+        }
+
+        // Styles are only available for Office Open XML workbooks. They are
+        // cosmetic, so a workbook whose styles cannot be parsed is still compared.
+        if let (WorkbookFormat::Xlsx, Some((row, col))) = (format, range.start()) {
+            if let Ok(styles) = read_xlsx_styles(file_path, &sheet_to_read, (row as usize, col as usize)) {
+                worksheet = worksheet.with_styles(styles);
+            }
+        }
+
         Ok(worksheet)
     }
+}
 
-    fn get_sheet_names(&self, file_path: &str) -> Result<Vec<String>> {
-        if !self.supports(file_path) {
-            anyhow::bail!("File {} is not a valid .xlsx file", file_path);
+/// Cut a region from the used range of a sheet
+///
+/// The region is taken by absolute position, so the result starts exactly at
+/// its first cell even if that cell is empty.
+fn crop_to_region(range: &Range<Data>, region: &CellRange) -> Range<Data> {
+    let last_cell = range
+        .end()
+        .and_then(|(row, col)| region.end_within((row as usize, col as usize)));
+    match last_cell {
+        Some((last_row, last_col)) => range.range(
+            (region.first_row as u32, region.first_col as u32),
+            (last_row as u32, last_col as u32),
+        ),
+        None => Range::empty(),
+    }
+}
+
+/// Resolve a range selection to the sheet and the cell range to read
+///
+/// Defined names are looked up first, then the tables of .xlsx workbooks. A
+/// table is read with its header row.
+fn resolve_selection(
+    workbook: &mut Sheets<BufReader<File>>,
+    sheet_name: Option<&str>,
+    selection: &RangeSelection,
+) -> Result<(Option<String>, CellRange)> {
+    let name = match selection {
+        RangeSelection::Cells(range) => return Ok((sheet_name.map(str::to_string), *range)),
+        RangeSelection::SheetCells(sheet, range) => {
+            let sheet = RangeSelection::sheet(sheet_name, Some(sheet))?;
+            return Ok((sheet.map(str::to_string), *range));
+        }
+        RangeSelection::Name(name) => name,
+    };
+
+    // Defined names refer to a range such as 'Q1 Data'!$A$5:$K$2000
+    let defined = workbook
+        .defined_names()
+        .iter()
+        .find(|(defined, _)| defined.eq_ignore_ascii_case(name))
+        .map(|(_, reference)| reference.trim_start_matches('=').to_string());
+    if let Some(reference) = defined {
+        return match reference.parse::<RangeSelection>() {
+            Ok(RangeSelection::Name(_)) | Err(_) => anyhow::bail!(
+                "Name '{}' does not refer to a single cell range ({})",
+                name,
+                reference
+            ),
+            Ok(selection) => resolve_selection(workbook, sheet_name, &selection),
+        };
+    }
+
+    if let Sheets::Xlsx(xlsx) = workbook {
+        xlsx.load_tables().context("Failed to read the tables of the workbook")?;
+        let table_name = xlsx
+            .table_names()
+            .into_iter()
+            .find(|table| table.eq_ignore_ascii_case(name))
+            .cloned();
+        if let Some(table_name) = table_name {
+            let table = xlsx
+                .table_by_name(&table_name)
+                .with_context(|| format!("Failed to read table '{}'", table_name))?;
+            let sheet = RangeSelection::sheet(sheet_name, Some(table.sheet_name()))?.map(str::to_string);
+            // The table data excludes the header row, which is the row above it
+            let ((first_row, first_col), (last_row, last_col)) = table
+                .data()
+                .start()
+                .zip(table.data().end())
+                .with_context(|| format!("Table '{}' has no cells", table_name))?;
+            let start = (first_row.saturating_sub(1) as usize, first_col as usize);
+            return Ok((sheet, CellRange::new(start, (last_row as usize, last_col as usize))));
         }
+    }
 
-        let workbook: Xlsx<_> = open_workbook(file_path)
-            .with_context(|| format!("Failed to open workbook: {}", file_path))?;
+    anyhow::bail!("No defined name or table '{}' found", name)
+}
+
+impl Default for ExcelReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileReader for ExcelReader {
+    fn read(&self, file_path: &str, sheet_name: Option<&str>) -> Result<Worksheet> {
+        let format = WorkbookFormat::detect(file_path)?;
+        let mut workbook = self.open_as(file_path, format)?;
+        self.read_sheet(&mut workbook, format, file_path, sheet_name, None)
+    }
+
+    fn read_range(&self, file_path: &str, sheet_name: Option<&str>, selection: &RangeSelection) -> Result<Worksheet> {
+        let format = WorkbookFormat::detect(file_path)?;
+        let mut workbook = self.open_as(file_path, format)?;
+        let (sheet, range) = resolve_selection(&mut workbook, sheet_name, selection)?;
+        self.read_sheet(&mut workbook, format, file_path, sheet.as_deref(), Some(&range))
+    }
+
+    fn get_sheet_names(&self, file_path: &str) -> Result<Vec<String>> {
+        let workbook = self.open(file_path)?;
 
         Ok(workbook.sheet_names().to_vec())
     }
 
     fn supports(&self, file_path: &str) -> bool {
-        Path::new(file_path)
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.eq_ignore_ascii_case("xlsx"))
-            .unwrap_or(false)
+        match File::open(file_path) {
+            // Existing files are recognized by their contents
+            Ok(_) => WorkbookFormat::detect(file_path).is_ok(),
+            Err(_) => has_extension(file_path, &WORKBOOK_EXTENSIONS),
+        }
     }
 }
@@ -0,0 +1,102 @@
+//! Location of the header row that names the columns of a sheet.
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::reader::{CellValue, Row};
+
+/// Number of rows searched when detecting the header row
+pub const HEADER_SEARCH_ROWS: usize = 20;
+
+/// Which row of a sheet is its header row
+///
+/// The header row is compared with the header row of the other sheet, never
+/// with a data row. Rows above it (such as a title block) are compared by
+/// position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HeaderRow {
+    /// The sheets have no header row; every row is compared as data
+    #[default]
+    None,
+    /// The header row is at this zero-based index
+    Row(usize),
+    /// Detect the header row from the cells of the first rows
+    Auto,
+}
+
+impl HeaderRow {
+    /// Zero-based index of the header row of a sheet, if it has one
+    pub fn locate(&self, rows: &[Row]) -> Option<usize> {
+        match self {
+            HeaderRow::None => None,
+            HeaderRow::Row(row) => (*row < rows.len()).then_some(*row),
+            HeaderRow::Auto => detect_header_row(rows),
+        }
+    }
+
+    /// Number of rows from the top of a sheet needed to locate its header row
+    pub fn search_rows(&self) -> usize {
+        match self {
+            HeaderRow::None => 0,
+            HeaderRow::Row(row) => row + 1,
+            HeaderRow::Auto => HEADER_SEARCH_ROWS,
+        }
+    }
+}
+
+impl FromStr for HeaderRow {
+    type Err = anyhow::Error;
+
+    /// Parse `auto`, `none` or a 1-based row number
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "auto" => Ok(HeaderRow::Auto),
+            "none" => Ok(HeaderRow::None),
+            number => match number.parse::<usize>() {
+                Ok(row) if row > 0 => Ok(HeaderRow::Row(row - 1)),
+                _ => anyhow::bail!("Invalid header row '{}' (expected auto, none or a row number from 1)", s),
+            },
+        }
+    }
+}
+
+impl fmt::Display for HeaderRow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderRow::None => write!(f, "none"),
+            HeaderRow::Row(row) => write!(f, "row {}", row + 1),
+            HeaderRow::Auto => write!(f, "auto"),
+        }
+    }
+}
+
+/// Find the header row among the first [`HEADER_SEARCH_ROWS`] rows
+///
+/// Candidates are rows whose cells are all distinct texts and that fill more
+/// than half the columns of the widest row. Of these, the row naming the most
+/// columns is the header row, the first one on a tie, so a title or a note
+/// above the table is skipped. Returns None if no row qualifies, e.g. for a
+/// sheet of numbers only.
+pub fn detect_header_row(rows: &[Row]) -> Option<usize> {
+    let rows = &rows[..rows.len().min(HEADER_SEARCH_ROWS)];
+    let filled = |row: &Row| row.iter().filter(|cell| **cell != CellValue::Empty).count();
+    let width = rows.iter().map(filled).max()?;
+
+    let names = |row: &Row| {
+        let mut names = HashSet::new();
+        let all_names = row.iter().filter(|cell| **cell != CellValue::Empty).all(|cell| match cell {
+            CellValue::String(text) => !text.trim().is_empty() && names.insert(text.trim()),
+            _ => false,
+        });
+        if all_names { names.len() } else { 0 }
+    };
+    rows.iter()
+        .enumerate()
+        .map(|(row_idx, row)| (row_idx, names(row)))
+        .filter(|&(_, count)| count * 2 > width)
+        // max_by_key returns the last maximum, so compare reversed indices
+        .max_by_key(|&(row_idx, count)| (count, std::cmp::Reverse(row_idx)))
+        .map(|(row_idx, _)| row_idx)
+}
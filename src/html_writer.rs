@@ -0,0 +1,415 @@
+//! Self-contained HTML report of diff results, for code review and tickets.
+
+use anyhow::{Context, Result};
+use std::fmt::Write as _;
+use std::fs;
+
+use crate::column_diff::{ColumnChange, ColumnDiff};
+use crate::columns::column_letter;
+use crate::differ::{DiffSummary, RowDiff};
+use crate::reader::CellValue;
+use crate::workbook::{SheetDiff, SheetOrigins, SheetStatus};
+
+/// Inline stylesheet; colors follow the Excel output
+const STYLE: &str = r#"
+body { font-family: -apple-system, "Segoe UI", Roboto, sans-serif; font-size: 14px; margin: 0 0 4em 0; color: #222; }
+header { padding: 1em 1.5em; background: #f6f8fa; border-bottom: 1px solid #d0d7de; }
+h1 { font-size: 1.3em; margin: 0 0 0.5em 0; }
+h2 { font-size: 1.1em; margin: 1.5em 1.5em 0.5em 1.5em; }
+.summary { display: flex; gap: 1.5em; flex-wrap: wrap; }
+.summary span { white-space: nowrap; }
+.summary .count { font-weight: bold; }
+.summary .errors { color: #d00000; }
+.sheets { border-collapse: collapse; margin-top: 1em; }
+.sheets td, .sheets th { padding: 0.2em 0.8em; border: 1px solid #d0d7de; text-align: right; }
+.sheets td:first-child, .sheets th:first-child, .sheets td:nth-child(2) { text-align: left; }
+.status-only { background: #fff3cd; }
+.columns { margin: 0 1.5em 0.5em 1.5em; color: #555; }
+.note { margin: 0 1.5em; color: #777; font-style: italic; }
+.wrap { margin: 0 1.5em; overflow-x: auto; }
+table.diff { border-collapse: collapse; white-space: pre; }
+table.diff th, table.diff td { border: 1px solid #d0d7de; padding: 0.15em 0.5em; vertical-align: top; }
+table.diff thead th { position: sticky; top: 0; background: #eaeef2; z-index: 1; }
+table.diff td.num { color: #888; text-align: right; background: #f6f8fa; font-size: 0.9em; }
+table.diff th.col-added { background: #c6efce; }
+table.diff th.col-removed { background: #d9d9d9; text-decoration: line-through; }
+table.diff th.col-renamed { background: #e4dfec; }
+table.diff th.col-moved { background: #ddebf7; }
+tr.moved td { background: #ddebf7; }
+tr.removed td { background: #ffff00; }
+tr.added td { background: #ffa500; }
+tr.ignored td:not(.num) { background: none; }
+td.modified { color: #d00000; }
+td.formula, td.formula ins, td.formula del { color: #7030a0; }
+td.type { color: #0070c0; }
+td.ignored { color: #a6a6a6; }
+del { color: #a00000; opacity: 0.75; }
+ins { color: #d00000; text-decoration: none; font-weight: bold; }
+.delta { color: #777; font-size: 0.9em; }
+tr.skipped td { color: #888; text-align: center; font-style: italic; background: #fafafa; }
+tr.current td { outline: 2px solid #0969da; outline-offset: -2px; }
+#nav { position: fixed; right: 1em; bottom: 1em; background: #fff; border: 1px solid #d0d7de; border-radius: 6px;
+       padding: 0.4em 0.6em; box-shadow: 0 2px 8px rgba(0,0,0,0.15); z-index: 2; }
+#nav button { font-size: 1em; margin: 0 0.2em; cursor: pointer; }
+"#;
+
+/// Inline script for the jump-to-next-change navigator (also on the n and p keys)
+const SCRIPT: &str = r#"
+(function () {
+  var changes = Array.prototype.slice.call(document.querySelectorAll("tr.change"));
+  var label = document.getElementById("nav-label");
+  var current = -1;
+  function show(index) {
+    if (changes.length === 0) { return; }
+    if (current >= 0) { changes[current].classList.remove("current"); }
+    current = (index + changes.length) % changes.length;
+    changes[current].classList.add("current");
+    changes[current].scrollIntoView({ block: "center" });
+    label.textContent = "Change " + (current + 1) + " of " + changes.length;
+  }
+  document.getElementById("nav-prev").onclick = function () { show(current - 1); };
+  document.getElementById("nav-next").onclick = function () { show(current + 1); };
+  document.addEventListener("keydown", function (e) {
+    if (e.target.tagName === "INPUT" || e.ctrlKey || e.metaKey || e.altKey) { return; }
+    if (e.key === "n" || e.key === "j") { show(current + 1); }
+    if (e.key === "p" || e.key === "k") { show(current - 1); }
+  });
+  label.textContent = changes.length === 0 ? "No changes" : changes.length + " changes";
+})();
+"#;
+
+/// Writer for diff results as a single HTML file with inline CSS and JavaScript
+///
+/// The report starts with the row counts per diff type, followed by a table
+/// of the compared rows: moved rows in light blue, removed rows in yellow,
+/// added rows in orange and modified cells in red with the old value struck
+/// through. The header row
+/// stays visible while scrolling, and a navigator jumps between changes.
+#[derive(Debug, Clone, Default)]
+pub struct HtmlDiffWriter {
+    title: Option<String>,
+}
+
+impl HtmlDiffWriter {
+    pub fn new() -> Self {
+        HtmlDiffWriter { title: None }
+    }
+
+    /// Set the report title (e.g. the names of the compared files)
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Write diff results to an HTML file
+    ///
+    /// # Arguments
+    /// * `diffs` - List of RowDiff objects
+    /// * `columns` - Column diffs matching the column layout of the row data
+    /// * `output_path` - Path to write the output file
+    /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
+    pub fn write(
+        &self,
+        diffs: &[RowDiff],
+        columns: &[ColumnDiff],
+        output_path: &str,
+        diff_only: bool,
+    ) -> Result<()> {
+        let mut body = String::new();
+        write_summary(&mut body, &DiffSummary::from_diffs(diffs));
+        body.push_str("</header>\n");
+        write_table(&mut body, diffs, columns, &SheetOrigins::default(), diff_only);
+
+        self.save(&body, output_path)
+    }
+
+    /// Write the diff of one sheet to an HTML file, numbering rows as in the compared files
+    ///
+    /// Same as [`write`], for a sheet diff of a cell range (see [`SheetDiff::with_origins`]).
+    ///
+    /// # Arguments
+    /// * `sheet` - Diff results of the sheet
+    /// * `output_path` - Path to write the output file
+    /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
+    ///
+    /// [`write`]: HtmlDiffWriter::write
+    pub fn write_sheet_diff(&self, sheet: &SheetDiff, output_path: &str, diff_only: bool) -> Result<()> {
+        let mut body = String::new();
+        write_summary(&mut body, &sheet.summary());
+        body.push_str("</header>\n");
+        write_table(&mut body, &sheet.diffs, &sheet.columns, &sheet.origins, diff_only);
+
+        self.save(&body, output_path)
+    }
+
+    /// Write the diff results of a whole workbook comparison to an HTML file
+    ///
+    /// The report lists every sheet with its status and row counts, linking to
+    /// one table per sheet.
+    ///
+    /// # Arguments
+    /// * `sheets` - Diff results per sheet
+    /// * `output_path` - Path to write the output file
+    /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
+    pub fn write_workbook(&self, sheets: &[SheetDiff], output_path: &str, diff_only: bool) -> Result<()> {
+        let mut total = DiffSummary::default();
+        for sheet in sheets {
+            total.add(&sheet.summary());
+        }
+
+        let mut body = String::new();
+        write_summary(&mut body, &total);
+        body.push_str("<table class=\"sheets\">\n<tr><th>Sheet</th><th>Status</th><th>Identical</th><th>Modified</th><th>Moved</th><th>Removed</th><th>Added</th><th>New errors</th><th>Type changes</th><th>Ignored</th></tr>\n");
+        for (idx, sheet) in sheets.iter().enumerate() {
+            let summary = sheet.summary();
+            let class = if sheet.status == SheetStatus::Compared { "" } else { " class=\"status-only\"" };
+            let _ = writeln!(
+                body,
+                "<tr{}><td><a href=\"#sheet-{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                class,
+                idx + 1,
+                escape(&sheet.name),
+                sheet.status.as_str(),
+                summary.identical,
+                summary.modified,
+                summary.moved,
+                summary.removed,
+                summary.added,
+                summary.new_errors,
+                summary.type_changes,
+                summary.ignored
+            );
+        }
+        body.push_str("</table>\n</header>\n");
+
+        for (idx, sheet) in sheets.iter().enumerate() {
+            let _ = writeln!(body, "<h2 id=\"sheet-{}\">{}</h2>", idx + 1, escape(&sheet.name));
+            if sheet.status != SheetStatus::Compared {
+                let _ = writeln!(body, "<p class=\"note\">Sheet {}.</p>", sheet.status.as_str());
+            }
+            write_table(&mut body, &sheet.diffs, &sheet.columns, &sheet.origins, diff_only);
+        }
+
+        self.save(&body, output_path)
+    }
+
+    /// Wrap the report body in a complete HTML document and write it
+    fn save(&self, body: &str, output_path: &str) -> Result<()> {
+        let title = self.title.as_deref().unwrap_or("Excel diff");
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+        let _ = writeln!(html, "<title>{}</title>", escape(title));
+        let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+        let _ = writeln!(html, "<header>\n<h1>{}</h1>", escape(title));
+        html.push_str(body);
+        html.push_str("<div id=\"nav\"><button id=\"nav-prev\" title=\"Previous change (p)\">&#9650;</button>");
+        html.push_str("<span id=\"nav-label\"></span>");
+        html.push_str("<button id=\"nav-next\" title=\"Next change (n)\">&#9660;</button></div>\n");
+        let _ = writeln!(html, "<script>{}</script>\n</body>\n</html>", SCRIPT);
+
+        fs::write(output_path, html).with_context(|| format!("Failed to create file: {}", output_path))
+    }
+}
+
+/// Write the row counts per diff type
+fn write_summary(html: &mut String, summary: &DiffSummary) {
+    let _ = writeln!(
+        html,
+        "<div class=\"summary\"><span><span class=\"count\">{}</span> identical</span><span><span class=\"count\">{}</span> modified</span>{}<span><span class=\"count\">{}</span> removed</span><span><span class=\"count\">{}</span> added</span>{}{}{}</div>",
+        summary.identical,
+        summary.modified,
+        if summary.moved > 0 {
+            format!("<span><span class=\"count\">{}</span> moved</span>", summary.moved)
+        } else {
+            String::new()
+        },
+        summary.removed,
+        summary.added,
+        if summary.new_errors > 0 {
+            format!("<span class=\"errors\"><span class=\"count\">{}</span> new errors</span>", summary.new_errors)
+        } else {
+            String::new()
+        },
+        if summary.type_changes > 0 {
+            format!("<span><span class=\"count\">{}</span> type changes</span>", summary.type_changes)
+        } else {
+            String::new()
+        },
+        if summary.ignored > 0 {
+            format!("<span><span class=\"count\">{}</span> ignored</span>", summary.ignored)
+        } else {
+            String::new()
+        }
+    );
+}
+
+/// Write the table of one sheet
+///
+/// The header row is used as the table header, or the column letters if the
+/// sheets have none. Identical rows are collapsed into a single line when
+/// `diff_only` is set.
+fn write_table(
+    html: &mut String,
+    diffs: &[RowDiff],
+    columns: &[ColumnDiff],
+    origins: &SheetOrigins,
+    diff_only: bool,
+) {
+    let changed: Vec<&ColumnDiff> = columns
+        .iter()
+        .filter(|c| c.change != ColumnChange::Unchanged)
+        .collect();
+    if !changed.is_empty() {
+        let descriptions: Vec<String> = changed
+            .iter()
+            .map(|c| format!("<b>{}</b> {}", escape(c.header()), escape(&c.describe())))
+            .collect();
+        let _ = writeln!(html, "<p class=\"columns\">Columns: {}</p>", descriptions.join(", "));
+    }
+
+    if diffs.is_empty() {
+        html.push_str("<p class=\"note\">No rows.</p>\n");
+        return;
+    }
+
+    let width = diffs.iter().map(|d| d.row_data.len()).max().unwrap_or(0);
+    let header = diffs.iter().find(|diff| diff.header);
+    html.push_str("<div class=\"wrap\">\n<table class=\"diff\">\n<thead>\n");
+    match header {
+        Some(header) => {
+            let header_change = if header.has_changes() { " change" } else { "" };
+            let _ = write!(html, "<tr class=\"{}{}\"><th>Row (old/new)</th>", header.diff_type.as_str(), header_change);
+        }
+        None => html.push_str("<tr><th>Row (old/new)</th>"),
+    }
+    for col_idx in 0..width {
+        let column = columns.get(col_idx);
+        let class = match column.map(|c| c.change) {
+            Some(ColumnChange::Added) => " class=\"col-added\"",
+            Some(ColumnChange::Removed) => " class=\"col-removed\"",
+            Some(ColumnChange::Renamed) => " class=\"col-renamed\"",
+            Some(ColumnChange::Moved) => " class=\"col-moved\"",
+            _ => "",
+        };
+        let title = column
+            .filter(|c| c.change != ColumnChange::Unchanged)
+            .map(|c| format!(" title=\"{}\"", escape(&c.describe())))
+            .unwrap_or_default();
+        let text = match header {
+            Some(header) => cell_html(header, col_idx),
+            None => column_letter(origins.new.1 + col_idx),
+        };
+        let _ = write!(html, "<th{}{}>{}</th>", class, title, text);
+    }
+    html.push_str("</tr>\n</thead>\n<tbody>\n");
+
+    let mut skipped = 0;
+    for diff in diffs.iter().filter(|diff| !diff.header) {
+        if diff_only && !diff.has_changes() {
+            skipped += 1;
+            continue;
+        }
+        write_skipped(html, &mut skipped, width);
+
+        let change = if diff.has_changes() { " change" } else { "" };
+        let ignored = if diff.ignored { " ignored" } else { "" };
+        let _ = write!(
+            html,
+            "<tr class=\"{}{}{}\"><td class=\"num\" title=\"{}\">{}</td>",
+            diff.diff_type.as_str(),
+            change,
+            ignored,
+            escape(&source_cells(diff)),
+            escape(&origins.row_label(diff))
+        );
+        for col_idx in 0..width {
+            let class = if diff.formula_only_change(col_idx).is_some() {
+                " class=\"formula\"".to_string()
+            } else if diff.modified_cells.contains(&col_idx) {
+                " class=\"modified\"".to_string()
+            } else if let Some((old, new)) = diff.type_change(col_idx) {
+                format!(" class=\"type\" title=\"{} → {}\"", old.type_name(), new.type_name())
+            } else if diff.is_ignored(col_idx) {
+                " class=\"ignored\" title=\"Ignored\"".to_string()
+            } else {
+                String::new()
+            };
+            let _ = write!(html, "<td{}>{}</td>", class, cell_html(diff, col_idx));
+        }
+        html.push_str("</tr>\n");
+    }
+    write_skipped(html, &mut skipped, width);
+    html.push_str("</tbody>\n</table>\n</div>\n");
+}
+
+/// Write a placeholder line for a run of unchanged rows that were left out
+fn write_skipped(html: &mut String, skipped: &mut usize, width: usize) {
+    if *skipped > 0 {
+        let plural = if *skipped == 1 { "" } else { "s" };
+        let _ = writeln!(
+            html,
+            "<tr class=\"skipped\"><td colspan=\"{}\">{} unchanged row{}</td></tr>",
+            width + 1,
+            skipped,
+            plural
+        );
+        *skipped = 0;
+    }
+}
+
+/// Tooltip of the row number cell: the addresses of the row in both files
+fn source_cells(diff: &RowDiff) -> String {
+    match (&diff.old_address, &diff.new_address) {
+        (Some(old), Some(new)) => format!("Old: {}, new: {}", old, new),
+        (Some(old), None) => format!("Old: {}", old),
+        (None, Some(new)) => format!("New: {}", new),
+        (None, None) => String::new(),
+    }
+}
+
+/// Cell content, with the old value (or formula) struck through for modified cells
+fn cell_html(diff: &RowDiff, col_idx: usize) -> String {
+    let new = diff.row_data.get(col_idx).map(CellValue::formatted).unwrap_or_default();
+    if !diff.modified_cells.contains(&col_idx) {
+        return escape(&new);
+    }
+
+    let old = diff
+        .original_row_data
+        .as_ref()
+        .and_then(|row| row.get(col_idx))
+        .map(CellValue::formatted)
+        .unwrap_or_default();
+    // When only the formula changed, the formulas are shown instead of the equal values
+    let (old, new) = match diff.formula_only_change(col_idx) {
+        Some(change) => (change.old_formula().unwrap_or(old), change.new_formula().unwrap_or(new)),
+        None => (old, new),
+    };
+    let mut html = String::new();
+    if !old.is_empty() {
+        let _ = write!(html, "<del>{}</del>", escape(&old));
+    }
+    if !new.is_empty() {
+        let _ = write!(html, "<ins>{}</ins>", escape(&new));
+    }
+    if let Some(delta) = diff.delta_text(col_idx) {
+        let _ = write!(html, " <span class=\"delta\">({})</span>", escape(&delta));
+    }
+    html
+}
+
+/// Escape text for use in HTML content and attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,159 @@
+//! Columns and rows that are shown in the diff but left out of the comparison.
+
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::columns::ColumnSpec;
+use crate::reader::{CellValue, Row};
+
+/// Selection of columns to ignore, as given by the user
+///
+/// Besides a single column reference (`LastModified`, `C` or `3`), a regular
+/// expression between slashes (`/^tmp_/`) selects every column whose header
+/// matches it.
+#[derive(Debug, Clone)]
+pub enum ColumnSelector {
+    /// A single column
+    Column(ColumnSpec),
+    /// All columns whose header text matches the expression
+    Pattern(Regex),
+}
+
+impl ColumnSelector {
+    /// Resolve the selection to zero-based column indices
+    ///
+    /// A pattern matching no header selects no columns, while a single column
+    /// that cannot be found is an error.
+    ///
+    /// # Arguments
+    /// * `header` - The header row used to look up column names
+    /// * `first_col` - Zero-based column of the file where the sheet starts
+    pub fn resolve(&self, header: &[CellValue], first_col: usize) -> Result<Vec<usize>> {
+        match self {
+            ColumnSelector::Column(spec) => Ok(vec![spec.resolve(header, first_col)?]),
+            ColumnSelector::Pattern(pattern) => Ok(header
+                .iter()
+                .enumerate()
+                .filter(|(_, cell)| pattern.is_match(cell.to_string().trim()))
+                .map(|(col_idx, _)| col_idx)
+                .collect()),
+        }
+    }
+}
+
+impl FromStr for ColumnSelector {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let trimmed = s.trim();
+        match trimmed.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+            Some(pattern) => Ok(ColumnSelector::Pattern(
+                Regex::new(pattern).with_context(|| format!("Invalid column pattern '{}'", trimmed))?,
+            )),
+            None => Ok(ColumnSelector::Column(trimmed.parse()?)),
+        }
+    }
+}
+
+impl fmt::Display for ColumnSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnSelector::Column(spec) => write!(f, "{}", spec),
+            ColumnSelector::Pattern(pattern) => write!(f, "/{}/", pattern),
+        }
+    }
+}
+
+/// Function deciding from its cells whether a row is ignored
+pub type RowPredicate = Arc<dyn Fn(&[CellValue]) -> bool + Send + Sync>;
+
+/// Condition selecting the rows that are left out of the comparison
+#[derive(Clone)]
+pub enum RowFilter {
+    /// Rows whose cell in the column matches the expression
+    Pattern {
+        /// Zero-based index of the column
+        column: usize,
+        /// Expression matched against the text of the cell
+        pattern: Regex,
+    },
+    /// Rows for which the function returns true
+    Predicate(RowPredicate),
+}
+
+impl RowFilter {
+    /// Ignore the rows whose cell in a column matches a regular expression
+    pub fn pattern(column: usize, pattern: Regex) -> Self {
+        RowFilter::Pattern { column, pattern }
+    }
+
+    /// Ignore the rows for which a function returns true
+    pub fn predicate(predicate: impl Fn(&[CellValue]) -> bool + Send + Sync + 'static) -> Self {
+        RowFilter::Predicate(Arc::new(predicate))
+    }
+
+    /// Whether a row is selected by the filter
+    pub fn matches(&self, row: &Row) -> bool {
+        match self {
+            RowFilter::Pattern { column, pattern } => row
+                .get(*column)
+                .is_some_and(|cell| pattern.is_match(&cell.to_string())),
+            RowFilter::Predicate(predicate) => predicate(row),
+        }
+    }
+}
+
+impl fmt::Debug for RowFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RowFilter::Pattern { column, pattern } => f
+                .debug_struct("Pattern")
+                .field("column", column)
+                .field("pattern", &pattern.as_str())
+                .finish(),
+            RowFilter::Predicate(_) => f.write_str("Predicate(..)"),
+        }
+    }
+}
+
+/// Rows to ignore, as given by the user (`Status=^(draft|test)$`)
+#[derive(Debug, Clone)]
+pub struct RowPattern {
+    /// The column whose cells are matched
+    pub column: ColumnSpec,
+    /// Expression matched against the text of the cells
+    pub pattern: Regex,
+}
+
+impl RowPattern {
+    /// Resolve the column against the header row into a row filter
+    ///
+    /// `first_col` is the zero-based column of the file where the sheet starts.
+    pub fn resolve(&self, header: &[CellValue], first_col: usize) -> Result<RowFilter> {
+        Ok(RowFilter::pattern(self.column.resolve(header, first_col)?, self.pattern.clone()))
+    }
+}
+
+impl FromStr for RowPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // Split on the first '=', as the expression may contain one itself
+        let (column, pattern) = s
+            .split_once('=')
+            .with_context(|| format!("Invalid row pattern '{}' (expected COLUMN=REGEX)", s))?;
+        Ok(RowPattern {
+            column: column.parse()?,
+            pattern: Regex::new(pattern).with_context(|| format!("Invalid row pattern '{}'", pattern))?,
+        })
+    }
+}
+
+impl fmt::Display for RowPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ~ /{}/", self.column, self.pattern)
+    }
+}
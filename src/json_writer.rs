@@ -0,0 +1,256 @@
+//! JSON output of diff results for scripts, CI pipelines and dashboards.
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::column_diff::ColumnDiff;
+use crate::columns::column_letter;
+use crate::differ::{DiffSummary, DiffType, RowDiff};
+use crate::reader::{CellValue, Row};
+use crate::workbook::{SheetDiff, SheetOrigins, SheetStatus};
+
+/// Version of the JSON document layout
+///
+/// Incremented whenever a field is removed or changes meaning, or a field gets
+/// a new value such as a diff type; new fields may be added without a version
+/// change.
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// Writer for diff results as a JSON document
+pub struct JsonDiffWriter;
+
+/// Document for a single-sheet comparison
+#[derive(Serialize)]
+struct SheetDocument<'a> {
+    schema_version: u32,
+    summary: DiffSummary,
+    #[serde(flatten)]
+    sheet: SheetBody<'a>,
+}
+
+/// Document for a whole-workbook comparison
+#[derive(Serialize)]
+struct WorkbookDocument<'a> {
+    schema_version: u32,
+    summary: DiffSummary,
+    sheets: Vec<SheetEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct SheetEntry<'a> {
+    name: &'a str,
+    status: SheetStatus,
+    summary: DiffSummary,
+    #[serde(flatten)]
+    sheet: SheetBody<'a>,
+}
+
+/// Header, column changes and rows of one sheet
+#[derive(Serialize)]
+struct SheetBody<'a> {
+    header: Vec<String>,
+    columns: &'a [ColumnDiff],
+    rows: Vec<RowEntry<'a>>,
+}
+
+#[derive(Serialize)]
+struct RowEntry<'a> {
+    #[serde(rename = "type")]
+    diff_type: DiffType,
+    old_row: Option<usize>,
+    new_row: Option<usize>,
+    old_address: Option<&'a str>,
+    new_address: Option<&'a str>,
+    values: &'a Row,
+    changes: Vec<CellChange<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    type_changes: Vec<CellChange<'a>>,
+    #[serde(skip_serializing_if = "<[usize]>::is_empty")]
+    ignored: &'a [usize],
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    ignored_row: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    header: bool,
+}
+
+#[derive(Serialize)]
+struct CellChange<'a> {
+    column: usize,
+    letter: String,
+    header: Option<String>,
+    old: &'a CellValue,
+    new: &'a CellValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delta: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old_formula: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    new_formula: Option<String>,
+}
+
+impl JsonDiffWriter {
+    pub fn new() -> Self {
+        JsonDiffWriter
+    }
+
+    /// Write diff results to a JSON file
+    ///
+    /// The document contains the schema version, the row counts per diff type,
+    /// the header row, the column changes and one entry per row with its diff
+    /// type, its 0-based row and the address of its first cell in each
+    /// worksheet, its values and, for modified rows, the old and new value (and
+    /// formula, if it changed) of every changed cell.
+    ///
+    /// # Arguments
+    /// * `diffs` - List of RowDiff objects
+    /// * `columns` - Column diffs matching the column layout of the row data
+    /// * `output_path` - Path to write the output file
+    /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
+    pub fn write(
+        &self,
+        diffs: &[RowDiff],
+        columns: &[ColumnDiff],
+        output_path: &str,
+        diff_only: bool,
+    ) -> Result<()> {
+        let document = SheetDocument {
+            schema_version: JSON_SCHEMA_VERSION,
+            summary: DiffSummary::from_diffs(diffs),
+            sheet: sheet_body(diffs, columns, &SheetOrigins::default(), diff_only),
+        };
+
+        save(&document, output_path)
+    }
+
+    /// Write the diff of one sheet to a JSON file
+    ///
+    /// Same as [`write`], for a sheet diff of a cell range (see
+    /// [`SheetDiff::with_origins`]): rows and column letters refer to the cells
+    /// of the compared files, while `column` stays the position in `values`.
+    ///
+    /// # Arguments
+    /// * `sheet` - Diff results of the sheet
+    /// * `output_path` - Path to write the output file
+    /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
+    ///
+    /// [`write`]: JsonDiffWriter::write
+    pub fn write_sheet_diff(&self, sheet: &SheetDiff, output_path: &str, diff_only: bool) -> Result<()> {
+        let document = SheetDocument {
+            schema_version: JSON_SCHEMA_VERSION,
+            summary: sheet.summary(),
+            sheet: sheet_body(&sheet.diffs, &sheet.columns, &sheet.origins, diff_only),
+        };
+
+        save(&document, output_path)
+    }
+
+    /// Write the diff results of a whole workbook comparison to a JSON file
+    ///
+    /// Same layout as [`write`], with the rows nested per sheet together with
+    /// the sheet name, its status and its own row counts.
+    ///
+    /// # Arguments
+    /// * `sheets` - Diff results per sheet
+    /// * `output_path` - Path to write the output file
+    /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
+    ///
+    /// [`write`]: JsonDiffWriter::write
+    pub fn write_workbook(&self, sheets: &[SheetDiff], output_path: &str, diff_only: bool) -> Result<()> {
+        let mut summary = DiffSummary::default();
+        let entries = sheets
+            .iter()
+            .map(|sheet| {
+                let sheet_summary = sheet.summary();
+                summary.add(&sheet_summary);
+                SheetEntry {
+                    name: &sheet.name,
+                    status: sheet.status,
+                    summary: sheet_summary,
+                    sheet: sheet_body(&sheet.diffs, &sheet.columns, &sheet.origins, diff_only),
+                }
+            })
+            .collect();
+
+        let document = WorkbookDocument {
+            schema_version: JSON_SCHEMA_VERSION,
+            summary,
+            sheets: entries,
+        };
+
+        save(&document, output_path)
+    }
+}
+
+impl Default for JsonDiffWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Build the header, column and row entries of one sheet
+fn sheet_body<'a>(
+    diffs: &'a [RowDiff],
+    columns: &'a [ColumnDiff],
+    origins: &SheetOrigins,
+    diff_only: bool,
+) -> SheetBody<'a> {
+    let header: Vec<String> = diffs
+        .iter()
+        .find(|diff| diff.header)
+        .map(|row| row.row_data.iter().map(|value| value.to_string()).collect())
+        .unwrap_or_default();
+
+    let rows = diffs
+        .iter()
+        .filter(|diff| !diff_only || diff.has_changes())
+        .map(|diff| {
+            let cell_change = |&col_idx: &usize| {
+                let formula = diff.formula_change(col_idx);
+                CellChange {
+                    column: col_idx,
+                    letter: column_letter(origins.column(diff, columns, col_idx)),
+                    header: header.get(col_idx).filter(|name| !name.is_empty()).cloned(),
+                    old: diff
+                        .original_row_data
+                        .as_ref()
+                        .and_then(|row| row.get(col_idx))
+                        .unwrap_or(&CellValue::Empty),
+                    new: diff.row_data.get(col_idx).unwrap_or(&CellValue::Empty),
+                    delta: diff.numeric_delta(col_idx),
+                    old_formula: formula.and_then(|change| change.old_formula()),
+                    new_formula: formula.and_then(|change| change.new_formula()),
+                }
+            };
+
+            let (old_row, new_row) = origins.rows(diff);
+            RowEntry {
+                diff_type: diff.diff_type,
+                old_row,
+                new_row,
+                old_address: diff.old_address.as_deref(),
+                new_address: diff.new_address.as_deref(),
+                values: &diff.row_data,
+                changes: diff.modified_cells.iter().map(cell_change).collect(),
+                type_changes: diff.type_changes.iter().map(cell_change).collect(),
+                ignored: &diff.ignored_cells,
+                ignored_row: diff.ignored,
+                header: diff.header,
+            }
+        })
+        .collect();
+
+    SheetBody { header, columns, rows }
+}
+
+/// Serialize a document to a file
+fn save<T: Serialize>(document: &T, output_path: &str) -> Result<()> {
+    let file = File::create(output_path)
+        .with_context(|| format!("Failed to create file: {}", output_path))?;
+    let mut writer = BufWriter::new(file);
+    serde_json::to_writer_pretty(&mut writer, document)?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
@@ -7,11 +7,45 @@
 //! - Added rows (orange background)
 
 pub mod reader;
+pub mod columns;
+pub mod range;
+pub mod header;
+pub mod column_diff;
 pub mod excel_reader;
+pub mod csv_reader;
+pub mod datetime;
+pub mod styles;
+pub mod registry;
+pub mod normalize;
+pub mod tolerance;
+pub mod ignore;
 pub mod differ;
+pub mod stream;
+mod matcher;
 pub mod writer;
+pub mod json_writer;
+pub mod text_writer;
+pub mod html_writer;
+pub mod output;
+pub mod workbook;
 
 pub use reader::FileReader;
+pub use columns::ColumnSpec;
+pub use range::{Anchor, CellRange, RangeSelection};
+pub use header::HeaderRow;
+pub use column_diff::{ColumnAlignment, ColumnChange, ColumnDiff};
 pub use excel_reader::ExcelReader;
-pub use differ::{DiffType, RowDiff, WorksheetDiffer};
-pub use writer::ExcelDiffWriter;
+pub use csv_reader::CsvReader;
+pub use registry::ReaderRegistry;
+pub use styles::{CellStyle, NumberFormat, SheetStyles};
+pub use normalize::{ColumnNormalization, Normalizer, TextNormalization};
+pub use tolerance::{ColumnTolerance, Tolerance};
+pub use ignore::{ColumnSelector, RowFilter, RowPattern};
+pub use differ::{AlignMode, CompareMode, DiffSummary, DiffType, FormulaChange, RowDiff, WorksheetDiffer};
+pub use stream::{RowDiffs, RowStream, SheetRow, StreamingWorkbook};
+pub use writer::{ExcelDiffWriter, ModifiedStyle};
+pub use json_writer::JsonDiffWriter;
+pub use text_writer::{TextDiffWriter, TextLayout};
+pub use html_writer::HtmlDiffWriter;
+pub use output::OutputFormat;
+pub use workbook::{SheetDiff, SheetOrigins, SheetStatus};
@@ -2,9 +2,23 @@
 
 use anyhow::{Context, Result};
 use clap::Parser;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
 use std::process;
 
-use exceldiff::{DiffType, ExcelDiffWriter, ExcelReader, FileReader, WorksheetDiffer};
+use exceldiff::columns::column_letter;
+use exceldiff::datetime::DateFormat;
+use exceldiff::differ::DEFAULT_SIMILARITY_THRESHOLD;
+use exceldiff::reader::{CellValue, Row, Worksheet};
+use exceldiff::text_writer::DEFAULT_CONTEXT_ROWS;
+use exceldiff::workbook::{pair_sheets, SheetOrigins};
+use exceldiff::{
+    AlignMode, Anchor, ColumnChange, ColumnDiff, ColumnNormalization, ColumnSelector, ColumnSpec, ColumnTolerance,
+    CompareMode, CsvReader, DiffSummary, ExcelDiffWriter, ExcelReader, FileReader, HeaderRow, HtmlDiffWriter,
+    JsonDiffWriter, ModifiedStyle, Normalizer, OutputFormat, RangeSelection, ReaderRegistry, RowPattern, SheetDiff,
+    SheetRow, SheetStatus, StreamingWorkbook, TextDiffWriter, TextLayout, TextNormalization, Tolerance,
+    WorksheetDiffer,
+};
 
 /// Get the version string (set by build.rs)
 const VERSION: &str = env!("EXCELDIFF_VERSION");
@@ -15,17 +29,45 @@ const VERSION: &str = env!("EXCELDIFF_VERSION");
 #[command(version = VERSION)]
 #[command(about = "Compare two Excel worksheets and highlight differences", long_about = None)]
 struct Cli {
-    /// Path to the first Excel file (baseline)
+    /// Path to the first file (baseline): .xlsx, .csv or .tsv
     #[arg(value_name = "FILE1")]
     file1: String,
 
-    /// Path to the second Excel file (comparison)
+    /// Path to the second file (comparison): .xlsx, .csv or .tsv
     #[arg(value_name = "FILE2")]
     file2: String,
 
-    /// Output file path
-    #[arg(short, long, default_value = "diff_output.xlsx")]
-    output: String,
+    /// Output file path (default: diff_output.<format>, or the terminal for --format text)
+    #[arg(short, long)]
+    output: Option<String>,
+
+    /// Output format: xlsx (color-coded workbook), json, text or html (default: from the output extension, else xlsx)
+    #[arg(long, value_name = "FORMAT")]
+    format: Option<OutputFormat>,
+
+    /// Modified cells in xlsx output: arrow ("old → new" text) or note (new typed value, old value in a cell note)
+    #[arg(long, value_name = "STYLE", default_value = "arrow")]
+    modified_style: ModifiedStyle,
+
+    /// Xlsx output: also copy fonts, fill colors and column widths of the source cells (number formats are always kept)
+    #[arg(long)]
+    copy_styles: bool,
+
+    /// Xlsx output: leave out the first column with the row numbers of each row in the compared files
+    #[arg(long)]
+    no_row_numbers: bool,
+
+    /// Text output: unified (one line per row) or side-by-side (old and new rows next to each other)
+    #[arg(long, value_name = "LAYOUT", default_value = "unified")]
+    layout: TextLayout,
+
+    /// Text output: number of identical rows shown around each change
+    #[arg(long, value_name = "ROWS", default_value_t = DEFAULT_CONTEXT_ROWS)]
+    context: usize,
+
+    /// Text output: do not use colors (also disabled by the NO_COLOR environment variable)
+    #[arg(long)]
+    no_color: bool,
 
     /// Sheet name in first file (default: first sheet)
     #[arg(long)]
@@ -35,6 +77,26 @@ struct Cli {
     #[arg(long)]
     sheet2: Option<String>,
 
+    /// Compare only a region of the first file: cell range (A5:K2000, A:K, 'Sheet 1'!A5:K2000), defined name or table name
+    #[arg(long, value_name = "RANGE")]
+    range1: Option<RangeSelection>,
+
+    /// Compare only a region of the second file: cell range, defined name or table name
+    #[arg(long, value_name = "RANGE")]
+    range2: Option<RangeSelection>,
+
+    /// How columns of both files are lined up: absolute (same column letter; default) or relative (from the first cell of each sheet; default with --range1/--range2)
+    #[arg(long, value_name = "MODE")]
+    anchor: Option<Anchor>,
+
+    /// Compare all sheets, pairing them by name (one output tab per sheet plus a summary tab)
+    #[arg(long, conflicts_with_all = ["sheet1", "sheet2", "range1", "range2"])]
+    all_sheets: bool,
+
+    /// Compare very large .xlsx sheets row by row without loading them (xlsx output only); both sheets must be sorted ascending on the --key columns (or on all columns without --key) as Excel sorts: numbers and dates, text ignoring case, booleans, errors, blanks
+    #[arg(long, conflicts_with_all = ["all_sheets", "range1", "range2", "anchor", "align", "align_columns", "compare", "copy_styles"])]
+    stream: bool,
+
     /// Only output rows with differences (exclude identical rows)
     #[arg(long)]
     diff_only: bool,
@@ -43,9 +105,158 @@ struct Cli {
     #[arg(long)]
     no_header: bool,
 
+    /// Header row, compared with the other header instead of with data rows: none (default), auto (detect) or a row number (1 = first compared row)
+    #[arg(long, value_name = "ROW", default_value = "none")]
+    header_row: HeaderRow,
+
     /// Ignore whitespace differences (trim and collapse whitespace in string values)
     #[arg(long)]
     ignore_whitespace: bool,
+
+    /// Ignore differences in letter case (Unicode case folding)
+    #[arg(long)]
+    ignore_case: bool,
+
+    /// Normalize text before comparing: invisible, nfkc, punctuation, case, whitespace (comma separated)
+    #[arg(long, value_name = "STEPS", value_delimiter = ',')]
+    normalize: Vec<TextNormalization>,
+
+    /// Text normalization of single columns, replacing the general one (e.g. Name=case+nfkc,Code=none)
+    #[arg(long, value_name = "COLUMN=STEPS", value_delimiter = ',')]
+    column_normalize: Vec<ColumnNormalization>,
+
+    /// Match rows on these key columns (letter, 1-based number or header name; comma separated)
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    key: Vec<ColumnSpec>,
+
+    /// Columns shown but not compared: letter, 1-based number, header name or /regex/ on the header (comma separated)
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',')]
+    ignore_columns: Vec<ColumnSelector>,
+
+    /// Rows shown but not compared: those whose cell in COLUMN matches the regular expression (repeatable)
+    #[arg(long, value_name = "COLUMN=REGEX")]
+    ignore_rows: Vec<RowPattern>,
+
+    /// Row alignment: similarity (match by content), myers or patience (keep row order)
+    #[arg(long, value_name = "MODE", default_value = "similarity")]
+    align: AlignMode,
+
+    /// Align columns by header name, so inserted, removed, renamed or reordered columns are detected
+    #[arg(long)]
+    align_columns: bool,
+
+    /// Ignore the order of rows: rows that are out of order are reported as identical or modified instead of moved
+    #[arg(long)]
+    ignore_order: bool,
+
+    /// Cells with formulas: compare values, formulas (ignore value changes caused by inputs) or both
+    #[arg(long, value_name = "MODE", default_value = "values")]
+    compare: CompareMode,
+
+    /// Treat text as equal to the number, boolean or date it represents, reporting such cells as type changes
+    #[arg(long)]
+    coerce_types: bool,
+
+    /// Format of dates stored as text, tried after ISO dates when coercing types (e.g. %d/%m/%Y; repeatable)
+    #[arg(long, value_name = "FORMAT", requires = "coerce_types")]
+    date_format: Vec<DateFormat>,
+
+    /// Treat numbers as equal if they differ by at most this amount (e.g. 0.01)
+    #[arg(long, value_name = "AMOUNT", default_value_t = 0.0)]
+    tolerance: f64,
+
+    /// Treat numbers as equal if they differ by at most this share of the larger one (e.g. 0.001 for 0.1%)
+    #[arg(long, value_name = "RATIO", default_value_t = 0.0)]
+    relative_tolerance: f64,
+
+    /// Tolerance of single columns, replacing the general one (e.g. Price=0.01,Rate=0.5%; comma separated)
+    #[arg(long, value_name = "COLUMN=TOLERANCE", value_delimiter = ',')]
+    column_tolerance: Vec<ColumnTolerance>,
+
+    /// Minimum share of equal cells (0.0 to 1.0) for two rows to be reported as modified
+    #[arg(long, value_name = "RATIO", default_value_t = DEFAULT_SIMILARITY_THRESHOLD)]
+    min_similarity: f64,
+}
+
+impl Cli {
+    /// Output format: the --format option, else inferred from the output path
+    fn output_format(&self) -> OutputFormat {
+        self.format
+            .or_else(|| self.output.as_deref().and_then(OutputFormat::from_path))
+            .unwrap_or_default()
+    }
+
+    /// How cells are lined up: a range is compared from its first cell, whole sheets by address
+    fn anchor(&self) -> Anchor {
+        let ranges = self.range1.is_some() || self.range2.is_some();
+        self.anchor
+            .unwrap_or(if ranges { Anchor::Relative } else { Anchor::Absolute })
+    }
+
+    /// Output path: the --output option, else the default name for the format
+    fn output_path(&self) -> String {
+        self.output
+            .clone()
+            .unwrap_or_else(|| self.output_format().default_output())
+    }
+
+    /// Whether the diff is printed as text to the terminal instead of written to a file
+    fn prints_text(&self) -> bool {
+        self.output_format() == OutputFormat::Text && self.output.is_none()
+    }
+
+    /// Create the text writer for the text output options
+    ///
+    /// Colors are only used when printing to a terminal, unless disabled by
+    /// --no-color or the NO_COLOR environment variable.
+    fn text_writer(&self) -> TextDiffWriter {
+        let no_color_env = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let color = self.prints_text() && !self.no_color && !no_color_env && io::stdout().is_terminal();
+        TextDiffWriter::new()
+            .with_layout(self.layout)
+            .with_context(self.context)
+            .with_color(color)
+    }
+
+    /// Create the Excel writer for the xlsx output options
+    fn excel_writer(&self) -> ExcelDiffWriter {
+        ExcelDiffWriter::new()
+            .with_modified_style(self.modified_style)
+            .with_copy_styles(self.copy_styles)
+            .with_row_numbers(!self.no_row_numbers)
+    }
+
+    /// Create the registry of file readers, reading formulas if they are compared
+    fn registry(&self) -> ReaderRegistry {
+        if self.compare == CompareMode::Values {
+            return ReaderRegistry::default();
+        }
+        ReaderRegistry::new()
+            .with_reader(Box::new(ExcelReader::new().with_formulas(true)))
+            .with_reader(Box::new(CsvReader::new()))
+    }
+
+    /// Text normalization of all columns without a column normalization
+    fn normalizer(&self) -> Normalizer {
+        let mut normalizer: Normalizer = self.normalize.iter().copied().collect();
+        if self.ignore_whitespace {
+            normalizer = normalizer.with_step(TextNormalization::Whitespace);
+        }
+        if self.ignore_case {
+            normalizer = normalizer.with_step(TextNormalization::Case);
+        }
+        normalizer
+    }
+
+    /// Numeric tolerance of all columns without a column tolerance
+    fn tolerance(&self) -> Tolerance {
+        Tolerance::new(self.tolerance, self.relative_tolerance)
+    }
+
+    /// Create the HTML writer, titled with the compared files
+    fn html_writer(&self) -> HtmlDiffWriter {
+        HtmlDiffWriter::new().with_title(format!("{} vs {}", self.file1, self.file2))
+    }
 }
 
 fn main() {
@@ -58,20 +269,32 @@ fn main() {
 fn run() -> Result<()> {
     let cli = Cli::parse();
 
-    let reader = ExcelReader::new();
+    // Choose a reader per file, so different formats can be compared
+    let registry = cli.registry();
+    let reader1 = registry
+        .reader_for(&cli.file1)
+        .with_context(|| format!("{} is not a supported spreadsheet (.xlsx, .xlsm, .xlsb, .xls, .ods) or text (.csv, .tsv) file", cli.file1))?;
+    let reader2 = registry
+        .reader_for(&cli.file2)
+        .with_context(|| format!("{} is not a supported spreadsheet (.xlsx, .xlsm, .xlsb, .xls, .ods) or text (.csv, .tsv) file", cli.file2))?;
 
-    // Validate file formats
-    if !reader.supports(&cli.file1) {
-        anyhow::bail!("{} is not a .xlsx file", cli.file1);
+    if !(0.0..=1.0).contains(&cli.min_similarity) {
+        anyhow::bail!("--min-similarity must be between 0.0 and 1.0");
+    }
+    if !(cli.tolerance >= 0.0 && cli.relative_tolerance >= 0.0) {
+        anyhow::bail!("--tolerance and --relative-tolerance must not be negative");
     }
 
-    if !reader.supports(&cli.file2) {
-        anyhow::bail!("{} is not a .xlsx file", cli.file2);
+    if cli.stream {
+        return run_stream(&cli);
+    }
+    if cli.all_sheets {
+        return run_all_sheets(&cli, reader1, reader2);
     }
 
     // Show available sheets if needed
-    if cli.sheet1.is_none() {
-        let sheets = reader
+    if cli.sheet1.is_none() && !names_sheet(cli.range1.as_ref()) {
+        let sheets = reader1
             .get_sheet_names(&cli.file1)
             .with_context(|| format!("Failed to read sheet names from {}", cli.file1))?;
         if let Some(first_sheet) = sheets.first() {
@@ -79,8 +302,8 @@ fn run() -> Result<()> {
         }
     }
 
-    if cli.sheet2.is_none() {
-        let sheets = reader
+    if cli.sheet2.is_none() && !names_sheet(cli.range2.as_ref()) {
+        let sheets = reader2
             .get_sheet_names(&cli.file2)
             .with_context(|| format!("Failed to read sheet names from {}", cli.file2))?;
         if let Some(first_sheet) = sheets.first() {
@@ -89,65 +312,497 @@ fn run() -> Result<()> {
     }
 
     // Read worksheets
-    println!("\nReading {}...", cli.file1);
-    let data1 = reader
-        .read(&cli.file1, cli.sheet1.as_deref())
-        .with_context(|| format!("Failed to read {}", cli.file1))?;
-    println!("  Loaded {} rows", data1.len());
-
-    println!("Reading {}...", cli.file2);
-    let data2 = reader
-        .read(&cli.file2, cli.sheet2.as_deref())
-        .with_context(|| format!("Failed to read {}", cli.file2))?;
-    println!("  Loaded {} rows", data2.len());
+    println!();
+    let data1 = read_worksheet(&cli, reader1, &cli.file1, cli.sheet1.as_deref(), cli.range1.as_ref())?;
+    let data2 = read_worksheet(&cli, reader2, &cli.file2, cli.sheet2.as_deref(), cli.range2.as_ref())?;
+    let (data1, data2) = cli.anchor().align(data1, data2);
 
     // Perform diff
     println!("\nComparing worksheets...");
-    if cli.ignore_whitespace {
-        println!("  Ignoring whitespace differences");
-    }
-    let differ = WorksheetDiffer::with_options(cli.ignore_whitespace);
-    let diffs = differ.compare(&data1, &data2);
+    print_options(&cli);
+    let differ = build_differ(&cli, &data1, data1.origin.1)?;
+    let columns = differ.compare_columns(&data1, &data2);
+    let diffs = differ.compare_worksheets(&data1, &data2);
 
-    // Count diff types
-    let mut stats = std::collections::HashMap::new();
-    stats.insert(DiffType::Identical, 0);
-    stats.insert(DiffType::Modified, 0);
-    stats.insert(DiffType::Removed, 0);
-    stats.insert(DiffType::Added, 0);
+    // Output refers to the cells of both files, also when only a range was compared
+    let sheet = SheetDiff::new(String::new(), SheetStatus::Compared, diffs)
+        .with_columns(columns)
+        .with_origins(data1.origin, data2.origin)
+        .with_styles(data1.styles, data2.styles);
 
-    for diff in &diffs {
-        *stats.entry(diff.diff_type).or_insert(0) += 1;
-    }
+    print_column_changes(&sheet.columns);
+    print_summary(&sheet.summary());
+    print_changed_columns(&sheet);
 
-    println!("\nDiff Summary:");
-    println!("  Identical rows: {}", stats[&DiffType::Identical]);
-    println!("  Modified rows:  {}", stats[&DiffType::Modified]);
-    println!("  Removed rows:   {}", stats[&DiffType::Removed]);
-    println!("  Added rows:     {}", stats[&DiffType::Added]);
+    // Text without an output file goes to the terminal
+    if cli.prints_text() {
+        println!();
+        let stdout = io::stdout();
+        return cli
+            .text_writer()
+            .write_sheet_diff(&mut stdout.lock(), &sheet, cli.diff_only);
+    }
 
     // Write output
-    println!("\nWriting diff to {}...", cli.output);
-    let writer = ExcelDiffWriter::new();
-    let include_header = cli.diff_only && !cli.no_header;
-    writer
-        .write(&diffs, &cli.output, cli.diff_only, include_header)
-        .with_context(|| format!("Failed to write output to {}", cli.output))?;
+    let output = cli.output_path();
+    println!("\nWriting diff to {}...", output);
+    let include_header = cli.diff_only && !cli.no_header && sheet.diffs.iter().any(|d| d.header);
+    let output_rows = if cli.diff_only {
+        // The header row is written once, also when it changed
+        sheet.diffs.iter().filter(|d| d.has_changes() && !(include_header && d.header)).count()
+    } else {
+        sheet.diffs.len()
+    };
+    match cli.output_format() {
+        OutputFormat::Xlsx => cli
+            .excel_writer()
+            .write_sheet_diff(&sheet, &output, cli.diff_only, include_header),
+        OutputFormat::Json => JsonDiffWriter::new().write_sheet_diff(&sheet, &output, cli.diff_only),
+        OutputFormat::Text => write_text_file(&output, |out| {
+            cli.text_writer().write_sheet_diff(out, &sheet, cli.diff_only)
+        }),
+        OutputFormat::Html => cli.html_writer().write_sheet_diff(&sheet, &output, cli.diff_only),
+    }
+    .with_context(|| format!("Failed to write output to {}", output))?;
 
     if cli.diff_only {
-        let output_rows = diffs
-            .iter()
-            .filter(|d| d.diff_type != DiffType::Identical)
-            .count();
         let total_rows = if include_header {
             output_rows + 1
         } else {
             output_rows
         };
-        println!("\nDone! Diff written to {} ({} rows)", cli.output, total_rows);
+        println!("\nDone! Diff written to {} ({} rows)", output, total_rows);
     } else {
-        println!("\nDone! Diff written to {}", cli.output);
+        println!("\nDone! Diff written to {}", output);
+    }
+
+    Ok(())
+}
+
+/// Read the worksheet to compare from a file: a whole sheet, or only the selected range
+fn read_worksheet(
+    cli: &Cli,
+    reader: &dyn FileReader,
+    file_path: &str,
+    sheet_name: Option<&str>,
+    range: Option<&RangeSelection>,
+) -> Result<Worksheet> {
+    let worksheet = match range {
+        Some(range) => {
+            println!("Reading {} of {}...", range, file_path);
+            reader.read_range(file_path, sheet_name, range)
+        }
+        None => {
+            println!("Reading {}...", file_path);
+            reader.read(file_path, sheet_name)
+        }
+    }
+    .with_context(|| format!("Failed to read {}", file_path))?;
+    match cli.header_row.locate(&worksheet) {
+        Some(header) => println!(
+            "  Loaded {} rows (header on row {})",
+            worksheet.len(),
+            worksheet.origin.0 + header + 1
+        ),
+        None => println!("  Loaded {} rows (no header row)", worksheet.len()),
+    }
+    Ok(worksheet)
+}
+
+/// Compare two sorted sheets as a stream of rows, writing the diff as the rows are compared
+fn run_stream(cli: &Cli) -> Result<()> {
+    if cli.output_format() != OutputFormat::Xlsx {
+        anyhow::bail!("--stream writes xlsx output only");
+    }
+    let mut workbook1 = StreamingWorkbook::open(&cli.file1)?;
+    let mut workbook2 = StreamingWorkbook::open(&cli.file2)?;
+    let rows1 = workbook1
+        .rows(cli.sheet1.as_deref())
+        .with_context(|| format!("Failed to read {}", cli.file1))?;
+    let rows2 = workbook2
+        .rows(cli.sheet2.as_deref())
+        .with_context(|| format!("Failed to read {}", cli.file2))?;
+
+    // Cells are compared by column letter, as whole sheets are without --stream
+    let (origin1, origin2) = (rows1.origin(), rows2.origin());
+    let col = origin1.1.min(origin2.1);
+    let mut rows1 = rows1.extend_to((origin1.0, col));
+    let rows2 = rows2.extend_to((origin2.0, col));
+    let origins = SheetOrigins::new(rows1.origin(), rows2.origin());
+
+    // Columns are named by the header row, so the first rows are read ahead to resolve them
+    println!("Streaming {} and {}...", cli.file1, cli.file2);
+    let first_rows: Vec<SheetRow> = rows1
+        .by_ref()
+        .take(cli.header_row.search_rows().max(1))
+        .collect::<Result<_>>()
+        .with_context(|| format!("Failed to read {}", cli.file1))?;
+    let header_rows: Vec<Row> = first_rows.iter().map(|(_, row)| row.clone()).collect();
+
+    println!("\nComparing worksheets...");
+    print_options(cli);
+    let differ = build_differ(cli, &header_rows, col)?;
+    let diffs = differ
+        .compare_iter(first_rows.into_iter().map(Ok).chain(rows1), rows2)
+        .with_origins(origins);
+
+    let output = cli.output_path();
+    println!("\nWriting diff to {}...", output);
+    // Rows are compared while the output is written, so errors may come from either
+    let summary = cli
+        .excel_writer()
+        .write_stream(diffs, origins, &output, cli.diff_only, !cli.no_header)?;
+
+    print_summary(&summary);
+    println!("\nDone! Diff written to {}", output);
+    Ok(())
+}
+
+/// Whether a range selection determines the sheet it is read from
+fn names_sheet(range: Option<&RangeSelection>) -> bool {
+    matches!(range, Some(RangeSelection::SheetCells(..) | RangeSelection::Name(_)))
+}
+
+/// Compare every sheet of both workbooks, pairing sheets by name
+fn run_all_sheets(cli: &Cli, reader1: &dyn FileReader, reader2: &dyn FileReader) -> Result<()> {
+    let sheets1 = reader1
+        .get_sheet_names(&cli.file1)
+        .with_context(|| format!("Failed to read sheet names from {}", cli.file1))?;
+    let sheets2 = reader2
+        .get_sheet_names(&cli.file2)
+        .with_context(|| format!("Failed to read sheet names from {}", cli.file2))?;
+
+    println!("Comparing all sheets of {} and {}...", cli.file1, cli.file2);
+    print_options(cli);
+
+    let mut sheet_diffs = Vec::new();
+    for (name, status) in pair_sheets(&sheets1, &sheets2) {
+        let data1 = if status == SheetStatus::OnlyInSecond {
+            Worksheet::new()
+        } else {
+            reader1
+                .read(&cli.file1, Some(&name))
+                .with_context(|| format!("Failed to read sheet '{}' from {}", name, cli.file1))?
+        };
+        let data2 = if status == SheetStatus::OnlyInFirst {
+            Worksheet::new()
+        } else {
+            reader2
+                .read(&cli.file2, Some(&name))
+                .with_context(|| format!("Failed to read sheet '{}' from {}", name, cli.file2))?
+        };
+        let (data1, data2) = cli.anchor().align(data1, data2);
+
+        let header_source = if data1.is_empty() { &data2 } else { &data1 };
+        let (header, first_col) = (header_cells(cli, header_source), header_source.origin.1);
+        // A column missing from one tab should not abort the whole workbook, only its option is left out
+        let (mut differ, errors) = column_differ(cli, header, first_col);
+        for e in errors {
+            println!("  Warning: sheet '{}': {:#}; option left out for this sheet", name, e);
+        }
+        if !cli.key.is_empty() {
+            match key_columns(cli, header, first_col) {
+                Ok(key_columns) => differ = differ.with_key_columns(key_columns),
+                Err(e) => println!("  Warning: sheet '{}': {:#}; matching rows by content", name, e),
+            }
+        }
+        let columns = differ.compare_columns(&data1, &data2);
+        let diffs = differ.compare_worksheets(&data1, &data2);
+
+        let summary = DiffSummary::from_diffs(&diffs);
+        match status {
+            SheetStatus::Compared => println!(
+                "  '{}': {} identical, {} modified, {} removed, {} added",
+                name, summary.identical, summary.modified, summary.removed, summary.added
+            ),
+            _ => println!("  '{}': {} ({} rows)", name, status.as_str(), diffs.len()),
+        }
+        if summary.moved > 0 {
+            println!("    {} moved rows", summary.moved);
+        }
+        if summary.new_errors > 0 {
+            println!("    {} new error values", summary.new_errors);
+        }
+        if summary.type_changes > 0 {
+            println!("    {} type-only changes", summary.type_changes);
+        }
+        if summary.ignored > 0 {
+            println!("    {} ignored rows", summary.ignored);
+        }
+
+        let column_changes = columns
+            .iter()
+            .filter(|c| c.change != ColumnChange::Unchanged)
+            .count();
+        if column_changes > 0 {
+            println!("    {} column changes", column_changes);
+        }
+
+        sheet_diffs.push(
+            SheetDiff::new(name, status, diffs)
+                .with_columns(columns)
+                .with_origins(data1.origin, data2.origin)
+                .with_styles(data1.styles, data2.styles),
+        );
+    }
+
+    let mut total = DiffSummary::default();
+    for sheet in &sheet_diffs {
+        total.add(&sheet.summary());
+    }
+    let one_sided = sheet_diffs
+        .iter()
+        .filter(|sheet| sheet.status != SheetStatus::Compared)
+        .count();
+    println!(
+        "\n{} sheets compared, {} only in one file",
+        sheet_diffs.len() - one_sided,
+        one_sided
+    );
+    print_summary(&total);
+
+    // Text without an output file goes to the terminal
+    if cli.prints_text() {
+        println!();
+        let stdout = io::stdout();
+        return cli
+            .text_writer()
+            .write_workbook(&mut stdout.lock(), &sheet_diffs, cli.diff_only);
+    }
+
+    // Write output
+    let output = cli.output_path();
+    println!("\nWriting diff to {}...", output);
+    let include_header = cli.diff_only && !cli.no_header;
+    match cli.output_format() {
+        OutputFormat::Xlsx => {
+            cli.excel_writer().write_workbook(&sheet_diffs, &output, cli.diff_only, include_header)
+        }
+        OutputFormat::Json => JsonDiffWriter::new().write_workbook(&sheet_diffs, &output, cli.diff_only),
+        OutputFormat::Text => write_text_file(&output, |out| {
+            cli.text_writer().write_workbook(out, &sheet_diffs, cli.diff_only)
+        }),
+        OutputFormat::Html => cli.html_writer().write_workbook(&sheet_diffs, &output, cli.diff_only),
     }
+    .with_context(|| format!("Failed to write output to {}", output))?;
+
+    println!(
+        "\nDone! Diff written to {} (summary + {} sheets)",
+        output,
+        sheet_diffs.len()
+    );
+
+    Ok(())
+}
 
+/// Write text output to a file
+fn write_text_file(path: &str, write: impl FnOnce(&mut BufWriter<File>) -> Result<()>) -> Result<()> {
+    let file = File::create(path).with_context(|| format!("Failed to create file: {}", path))?;
+    let mut out = BufWriter::new(file);
+    write(&mut out)?;
+    out.flush()?;
     Ok(())
 }
+
+/// Print the comparison options that are in effect
+fn print_options(cli: &Cli) {
+    let normalizer = cli.normalizer();
+    if !normalizer.is_empty() {
+        println!("  Normalizing text: {}", normalizer);
+    }
+    if !cli.column_normalize.is_empty() {
+        let names: Vec<String> = cli.column_normalize.iter().map(|column| column.to_string()).collect();
+        println!("  Column text normalization: {}", names.join(", "));
+    }
+    if !cli.key.is_empty() {
+        let names: Vec<String> = cli.key.iter().map(|spec| spec.to_string()).collect();
+        println!("  Matching rows on key columns: {}", names.join(", "));
+    }
+    if !cli.ignore_columns.is_empty() {
+        let names: Vec<String> = cli.ignore_columns.iter().map(|selector| selector.to_string()).collect();
+        println!("  Ignoring columns: {}", names.join(", "));
+    }
+    if !cli.ignore_rows.is_empty() {
+        let patterns: Vec<String> = cli.ignore_rows.iter().map(|pattern| pattern.to_string()).collect();
+        println!("  Ignoring rows: {}", patterns.join(", "));
+    }
+    if cli.align != AlignMode::Similarity {
+        println!("  Aligning rows in order ({} diff)", cli.align.as_str());
+    }
+    if cli.align_columns {
+        println!("  Aligning columns by header name");
+    }
+    if cli.ignore_order {
+        println!("  Ignoring row order");
+    }
+    if cli.anchor() == Anchor::Relative {
+        println!("  Comparing each sheet from its first cell");
+    }
+    if cli.coerce_types {
+        let formats: Vec<String> = cli.date_format.iter().map(|format| format.to_string()).collect();
+        if formats.is_empty() {
+            println!("  Coercing text to numbers, booleans and dates");
+        } else {
+            println!("  Coercing text to numbers, booleans and dates ({})", formats.join(", "));
+        }
+    }
+    let tolerance = cli.tolerance();
+    if !tolerance.is_zero() {
+        println!("  Numeric tolerance: {}", tolerance);
+    }
+    if !cli.column_tolerance.is_empty() {
+        let names: Vec<String> = cli.column_tolerance.iter().map(|column| column.to_string()).collect();
+        println!("  Column tolerances: {}", names.join(", "));
+    }
+    match cli.compare {
+        CompareMode::Values => {}
+        CompareMode::Formulas => println!("  Comparing formulas instead of values of formula cells"),
+        CompareMode::Both => println!("  Comparing values and formulas of formula cells"),
+    }
+}
+
+/// Create a differ for the CLI options that do not depend on the sheet's columns
+fn content_differ(cli: &Cli) -> WorksheetDiffer {
+    WorksheetDiffer::new()
+        .with_normalizer(cli.normalizer())
+        .with_similarity_threshold(cli.min_similarity)
+        .with_align_mode(cli.align)
+        .with_column_alignment(cli.align_columns)
+        .with_move_detection(!cli.ignore_order)
+        .with_compare_mode(cli.compare)
+        .with_tolerance(cli.tolerance())
+        .with_type_coercion(cli.coerce_types)
+        .with_date_formats(cli.date_format.clone())
+        .with_header_row(cli.header_row)
+}
+
+/// Create a differ for the CLI options, resolving columns against the header row
+///
+/// Column letters refer to the file, so they are counted from `first_col`,
+/// the column where the sheet starts.
+fn build_differ(cli: &Cli, sheet: &[Row], first_col: usize) -> Result<WorksheetDiffer> {
+    let header = header_cells(cli, sheet);
+    let (differ, errors) = column_differ(cli, header, first_col);
+    if let Some(e) = errors.into_iter().next() {
+        return Err(e);
+    }
+    if cli.key.is_empty() {
+        return Ok(differ);
+    }
+    Ok(differ.with_key_columns(key_columns(cli, header, first_col)?))
+}
+
+/// Cells naming the columns of a sheet: its header row, or its first row if there is none
+fn header_cells<'a>(cli: &Cli, sheet: &'a [Row]) -> &'a [CellValue] {
+    cli.header_row
+        .locate(sheet)
+        .map_or(sheet.first(), |row| sheet.get(row))
+        .map(|row| row.as_slice())
+        .unwrap_or_default()
+}
+
+/// Resolve the --key columns against the header cells
+fn key_columns(cli: &Cli, header: &[CellValue], first_col: usize) -> Result<Vec<usize>> {
+    ColumnSpec::resolve_all(&cli.key, header, first_col).context("Failed to resolve key columns")
+}
+
+/// Create a differ for the CLI options except --key, resolving columns against the header cells
+///
+/// Options whose column cannot be resolved are left out; their errors are returned with the differ.
+fn column_differ(cli: &Cli, header: &[CellValue], first_col: usize) -> (WorksheetDiffer, Vec<anyhow::Error>) {
+    let mut differ = content_differ(cli);
+    let mut errors = Vec::new();
+    for column in &cli.column_normalize {
+        match column.column.resolve(header, first_col) {
+            Ok(col_idx) => differ = differ.with_column_normalizer(col_idx, column.normalizer.clone()),
+            Err(e) => errors.push(e.context("Failed to resolve normalization column")),
+        }
+    }
+    for column in &cli.column_tolerance {
+        match column.column.resolve(header, first_col) {
+            Ok(col_idx) => differ = differ.with_column_tolerance(col_idx, column.tolerance),
+            Err(e) => errors.push(e.context("Failed to resolve tolerance column")),
+        }
+    }
+    for selector in &cli.ignore_columns {
+        match selector.resolve(header, first_col) {
+            Ok(columns) => differ = differ.with_ignored_columns(columns),
+            Err(e) => errors.push(e.context("Failed to resolve ignored columns")),
+        }
+    }
+    for pattern in &cli.ignore_rows {
+        match pattern.resolve(header, first_col) {
+            Ok(filter) => differ = differ.with_row_filter(filter),
+            Err(e) => errors.push(e.context("Failed to resolve the column of ignored rows")),
+        }
+    }
+
+    (differ, errors)
+}
+
+/// Print the columns that were added, removed, renamed or moved
+fn print_column_changes(columns: &[ColumnDiff]) {
+    let changes: Vec<&ColumnDiff> = columns
+        .iter()
+        .filter(|c| c.change != ColumnChange::Unchanged)
+        .collect();
+    if changes.is_empty() {
+        return;
+    }
+
+    println!("\nColumn changes:");
+    for column in changes {
+        println!("  '{}': {}", column.header(), column.describe());
+    }
+}
+
+/// Print the number of modified cells per column, naming columns by their header
+fn print_changed_columns(sheet: &SheetDiff) {
+    let width = sheet.diffs.iter().map(|d| d.row_data.len()).max().unwrap_or(0);
+    let mut counts = vec![0; width];
+    for diff in sheet.diffs.iter().filter(|d| !d.header) {
+        for &col_idx in &diff.modified_cells {
+            counts[col_idx] += 1;
+        }
+    }
+    if counts.iter().all(|&count| count == 0) {
+        return;
+    }
+
+    let header = sheet.diffs.iter().find(|d| d.header);
+    println!("\nModified cells per column:");
+    for (col_idx, count) in counts.into_iter().enumerate().filter(|&(_, count)| count > 0) {
+        let source_col = sheet
+            .columns
+            .get(col_idx)
+            .map_or(Some(col_idx), |column| column.new_index)
+            .map_or(col_idx, |col| col + sheet.origins.new.1);
+        let name = header
+            .and_then(|header| header.row_data.get(col_idx))
+            .map(|name| name.to_string())
+            .filter(|name| !name.trim().is_empty());
+        match name {
+            Some(name) => println!("  '{}' (column {}): {}", name.trim(), column_letter(source_col), count),
+            None => println!("  Column {}: {}", column_letter(source_col), count),
+        }
+    }
+}
+
+/// Print the number of rows per diff type
+fn print_summary(summary: &DiffSummary) {
+    println!("\nDiff Summary:");
+    println!("  Identical rows: {}", summary.identical);
+    println!("  Modified rows:  {}", summary.modified);
+    println!("  Moved rows:     {}", summary.moved);
+    println!("  Removed rows:   {}", summary.removed);
+    println!("  Added rows:     {}", summary.added);
+    if summary.new_errors > 0 {
+        println!("  New errors:     {} (cells that now hold an error value such as #REF!)", summary.new_errors);
+    }
+    if summary.type_changes > 0 {
+        println!("  Type changes:   {} (cells with an equal value of another type)", summary.type_changes);
+    }
+    if summary.ignored > 0 {
+        println!("  Ignored rows:   {}", summary.ignored);
+    }
+}
@@ -0,0 +1,181 @@
+//! Indexed lookup of the most similar row for modified-row detection.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::differ::WorksheetDiffer;
+use crate::reader::Row;
+
+/// Index over candidate rows that finds the most similar row for a target row
+///
+/// A row is a modification candidate if at least `threshold` of its cells are
+/// equal to the target row. Instead of scoring every candidate, the index
+/// keeps a posting list per column and cell value. By the pigeonhole
+/// principle, a row with at least `k` of `n` matching cells must match on at
+/// least one of any `n - k + 1` columns, so only the postings of the most
+/// selective columns of the target row need to be visited. Each candidate is
+/// then scored exactly, which keeps the result identical to a full scan.
+///
+/// The postings are visited from the most selective column on. Once a match
+/// is found, a row outside the visited postings can only beat it by matching
+/// on all remaining columns, so the search usually stops before the postings
+/// of common values such as blank cells or booleans.
+pub(crate) struct SimilarityIndex<'a> {
+    differ: &'a WorksheetDiffer,
+    rows: &'a [Row],
+    /// Per column, the candidate rows for each normalized cell value
+    postings: Vec<HashMap<Vec<u8>, Vec<usize>>>,
+    /// Candidate rows that are still available for matching
+    available: Vec<bool>,
+    /// Number of rows still available
+    remaining: usize,
+}
+
+impl<'a> SimilarityIndex<'a> {
+    /// Build an index over the given candidate rows of a worksheet
+    ///
+    /// # Arguments
+    /// * `differ` - Differ providing the cell normalization and comparison
+    /// * `rows` - All rows of the worksheet
+    /// * `candidates` - Indices of the rows that may be matched
+    pub(crate) fn new(
+        differ: &'a WorksheetDiffer,
+        rows: &'a [Row],
+        candidates: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        let mut postings: Vec<HashMap<Vec<u8>, Vec<usize>>> = Vec::new();
+        let mut available = vec![false; rows.len()];
+        let mut remaining = 0;
+
+        for idx in candidates {
+            let row = &rows[idx];
+            // Ignored rows are never a modified version of another row
+            if differ.is_ignored_row(row) {
+                continue;
+            }
+            if postings.len() < row.len() {
+                postings.resize_with(row.len(), HashMap::new);
+            }
+            for (col_idx, value) in row.iter().enumerate() {
+                if !differ.is_compared(col_idx) {
+                    continue;
+                }
+                postings[col_idx]
+                    .entry(differ.index_key(col_idx, value))
+                    .or_default()
+                    .push(idx);
+            }
+            available[idx] = true;
+            remaining += 1;
+        }
+        // Postings in row order, so a search for an earlier row can stop early
+        for list in postings.iter_mut().flat_map(|column| column.values_mut()) {
+            list.sort_unstable();
+        }
+
+        SimilarityIndex {
+            differ,
+            rows,
+            postings,
+            available,
+            remaining,
+        }
+    }
+
+    /// Find the available row most similar to the target row
+    ///
+    /// Returns (row_index, list of modified cell indices) for the row with the
+    /// highest share of equal cells, provided it reaches `threshold` and has at
+    /// least one equal cell. Ties are resolved in favour of the row that comes
+    /// first.
+    pub(crate) fn best_match(&self, target_row: &Row, threshold: f64) -> Option<(usize, Vec<usize>)> {
+        if self.remaining == 0 || self.differ.is_ignored_row(target_row) {
+            return None;
+        }
+
+        let compared: Vec<usize> = (0..target_row.len())
+            .filter(|&col_idx| self.differ.is_compared(col_idx))
+            .collect();
+        let n = compared.len();
+        if n == 0 {
+            return None;
+        }
+        // A row without any equal cell is never a modified version of the target row
+        let required = required_matches(n, threshold).max(1);
+        if required > n {
+            return None;
+        }
+
+        // Postings of the target row's values, most selective column first
+        let mut lists: Vec<&[usize]> = compared
+            .iter()
+            .map(|&col_idx| {
+                self.postings
+                    .get(col_idx)
+                    .and_then(|column| column.get(&self.differ.index_key(col_idx, &target_row[col_idx])))
+                    .map(|list| list.as_slice())
+                    .unwrap_or(&[])
+            })
+            .collect();
+        lists.sort_by_key(|list| list.len());
+
+        // Best match so far: (row index, equal cells, modified cells)
+        let mut best: Option<(usize, usize, Vec<usize>)> = None;
+        let mut scored = HashSet::new();
+
+        for (visited, list) in lists.into_iter().enumerate() {
+            // A row in none of the visited postings differs in all their columns
+            let unvisited_max = n - visited;
+            let needed = best.as_ref().map_or(required, |&(_, matches, _)| matches);
+            if unvisited_max < needed {
+                break;
+            }
+
+            for &idx in list {
+                if let Some((best_idx, best_matches, _)) = best {
+                    // Only an earlier row with as many equal cells can still win
+                    if unvisited_max == best_matches && idx > best_idx {
+                        break;
+                    }
+                }
+                if !self.available[idx] || !scored.insert(idx) {
+                    continue;
+                }
+
+                let modified = self.differ.modified_columns(target_row, &self.rows[idx]);
+                let matches = n.saturating_sub(modified.len());
+                let better = match best {
+                    Some((best_idx, best_matches, _)) => {
+                        matches > best_matches || (matches == best_matches && idx < best_idx)
+                    }
+                    None => matches >= required,
+                };
+                if better {
+                    best = Some((idx, matches, modified));
+                }
+            }
+        }
+
+        best.map(|(idx, _, modified)| (idx, modified))
+    }
+
+    /// Mark a row as matched so it is no longer returned
+    pub(crate) fn remove(&mut self, idx: usize) {
+        if self.available[idx] {
+            self.available[idx] = false;
+            self.remaining -= 1;
+        }
+    }
+}
+
+/// Smallest number of equal cells out of `n` that reaches the threshold
+fn required_matches(n: usize, threshold: f64) -> usize {
+    let mut required = (threshold * n as f64).ceil().max(0.0) as usize;
+    // Guard against floating point rounding in either direction
+    while required > 0 && (required - 1) as f64 / n as f64 >= threshold {
+        required -= 1;
+    }
+    while required <= n && (required as f64 / n as f64) < threshold {
+        required += 1;
+    }
+    required
+}
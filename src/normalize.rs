@@ -0,0 +1,179 @@
+//! Text normalization applied to string cells before they are compared.
+
+use anyhow::{Context, Result};
+use caseless::default_case_fold_str;
+use std::fmt;
+use std::str::FromStr;
+use unicode_normalization::UnicodeNormalization;
+
+use crate::columns::ColumnSpec;
+
+/// One step of the text normalization pipeline
+///
+/// Steps are always applied in the order of this enum, whatever order they
+/// were selected in, so the same selection always yields the same result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TextNormalization {
+    /// Replace non-breaking spaces with spaces and remove zero-width characters
+    Invisible,
+    /// Unicode compatibility normalization (NFKC), e.g. "ﬁ" → "fi" and "é" in NFD → NFC
+    Nfkc,
+    /// Fold smart quotes to straight quotes and dashes to a hyphen
+    Punctuation,
+    /// Unicode case folding, so "Straße" equals "STRASSE"
+    Case,
+    /// Trim and collapse whitespace
+    Whitespace,
+}
+
+impl TextNormalization {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextNormalization::Invisible => "invisible",
+            TextNormalization::Nfkc => "nfkc",
+            TextNormalization::Punctuation => "punctuation",
+            TextNormalization::Case => "case",
+            TextNormalization::Whitespace => "whitespace",
+        }
+    }
+
+    /// Apply this step to a text
+    fn apply(&self, text: &str) -> String {
+        match self {
+            TextNormalization::Invisible => text
+                .chars()
+                .filter(|c| !matches!(c, '\u{200B}' | '\u{200C}' | '\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}'))
+                .map(|c| match c {
+                    '\u{00A0}' | '\u{2007}' | '\u{202F}' => ' ',
+                    c => c,
+                })
+                .collect(),
+            TextNormalization::Nfkc => text.nfkc().collect(),
+            TextNormalization::Punctuation => text
+                .chars()
+                .map(|c| match c {
+                    '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}' | '\u{2032}' => '\'',
+                    '\u{201C}' | '\u{201D}' | '\u{201E}' | '\u{201F}' | '\u{2033}' => '"',
+                    '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2015}' | '\u{2212}' => '-',
+                    c => c,
+                })
+                .collect(),
+            TextNormalization::Case => default_case_fold_str(text),
+            TextNormalization::Whitespace => text.split_whitespace().collect::<Vec<&str>>().join(" "),
+        }
+    }
+}
+
+impl FromStr for TextNormalization {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "invisible" => Ok(TextNormalization::Invisible),
+            "nfkc" | "unicode" => Ok(TextNormalization::Nfkc),
+            "punctuation" => Ok(TextNormalization::Punctuation),
+            "case" => Ok(TextNormalization::Case),
+            "whitespace" => Ok(TextNormalization::Whitespace),
+            _ => anyhow::bail!(
+                "Unknown normalization '{}' (expected invisible, nfkc, punctuation, case or whitespace)",
+                s
+            ),
+        }
+    }
+}
+
+/// Pipeline of text normalization steps
+///
+/// The default pipeline is empty and leaves text unchanged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Normalizer {
+    steps: Vec<TextNormalization>,
+}
+
+impl Normalizer {
+    pub fn new() -> Self {
+        Normalizer { steps: Vec::new() }
+    }
+
+    /// Add a step to the pipeline (adding a step twice has no effect)
+    pub fn with_step(mut self, step: TextNormalization) -> Self {
+        if let Err(position) = self.steps.binary_search(&step) {
+            self.steps.insert(position, step);
+        }
+        self
+    }
+
+    /// Whether the pipeline contains a step
+    pub fn has_step(&self, step: TextNormalization) -> bool {
+        self.steps.contains(&step)
+    }
+
+    /// Whether the pipeline leaves text unchanged
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Run a text through all steps of the pipeline
+    pub fn apply(&self, text: &str) -> String {
+        self.steps
+            .iter()
+            .fold(text.to_string(), |text, step| step.apply(&text))
+    }
+}
+
+impl FromIterator<TextNormalization> for Normalizer {
+    fn from_iter<I: IntoIterator<Item = TextNormalization>>(steps: I) -> Self {
+        steps.into_iter().fold(Normalizer::new(), Normalizer::with_step)
+    }
+}
+
+impl FromStr for Normalizer {
+    type Err = anyhow::Error;
+
+    /// Parse steps separated by `+` (e.g. `case+nfkc`), or `none` for no normalization
+    fn from_str(s: &str) -> Result<Self> {
+        if s.trim().eq_ignore_ascii_case("none") {
+            return Ok(Normalizer::new());
+        }
+        s.split('+').map(str::parse).collect()
+    }
+}
+
+impl fmt::Display for Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.steps.is_empty() {
+            return write!(f, "none");
+        }
+        let names: Vec<&str> = self.steps.iter().map(TextNormalization::as_str).collect();
+        write!(f, "{}", names.join("+"))
+    }
+}
+
+/// Normalization of one column, as given by the user (`Name=case+nfkc` or `Code=none`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnNormalization {
+    /// The column the normalization applies to
+    pub column: ColumnSpec,
+    /// Normalization of the strings in the column
+    pub normalizer: Normalizer,
+}
+
+impl FromStr for ColumnNormalization {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (column, normalizer) = s
+            .rsplit_once('=')
+            .with_context(|| format!("Invalid column normalization '{}' (expected COLUMN=STEPS)", s))?;
+        Ok(ColumnNormalization {
+            column: column.parse()?,
+            normalizer: normalizer.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for ColumnNormalization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.column, self.normalizer)
+    }
+}
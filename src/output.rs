@@ -0,0 +1,68 @@
+//! Output formats for diff results.
+
+use anyhow::Result;
+use std::str::FromStr;
+
+use crate::reader::has_extension;
+
+/// File format of the diff output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    /// Color-coded Excel workbook
+    #[default]
+    Xlsx,
+    /// Machine-readable JSON document
+    Json,
+    /// Text table for the terminal
+    Text,
+    /// Self-contained HTML report
+    Html,
+}
+
+impl OutputFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Xlsx => "xlsx",
+            OutputFormat::Json => "json",
+            OutputFormat::Text => "text",
+            OutputFormat::Html => "html",
+        }
+    }
+
+    /// File extension used for this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "txt",
+            _ => self.as_str(),
+        }
+    }
+
+    /// Output file name used when no output path is given
+    pub fn default_output(&self) -> String {
+        format!("diff_output.{}", self.extension())
+    }
+
+    /// Infer the format from the extension of an output path
+    ///
+    /// # Returns
+    /// The format, or None if the extension does not belong to any format
+    pub fn from_path(path: &str) -> Option<Self> {
+        [OutputFormat::Xlsx, OutputFormat::Json, OutputFormat::Text, OutputFormat::Html]
+            .into_iter()
+            .find(|format| has_extension(path, &[format.extension()]))
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "xlsx" => Ok(OutputFormat::Xlsx),
+            "json" => Ok(OutputFormat::Json),
+            "text" | "txt" => Ok(OutputFormat::Text),
+            "html" => Ok(OutputFormat::Html),
+            _ => anyhow::bail!("Unknown output format '{}' (expected xlsx, json, text or html)", s),
+        }
+    }
+}
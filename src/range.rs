@@ -0,0 +1,229 @@
+//! Cell ranges selecting the region of a worksheet that is compared.
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::columns::{cell_position, column_index, column_letter};
+use crate::reader::Worksheet;
+
+/// Rectangular region of a worksheet, with zero-based inclusive bounds
+///
+/// Written in A1 notation as a cell range (`A5:K2000`), a single cell (`B3`),
+/// whole columns (`A:K`) or whole rows (`5:2000`). Whole columns extend to the
+/// last row of the sheet, whole rows to its last column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CellRange {
+    /// First row of the range
+    pub first_row: usize,
+    /// First column of the range
+    pub first_col: usize,
+    /// Last row of the range, or None for all rows up to the end of the sheet
+    pub last_row: Option<usize>,
+    /// Last column of the range, or None for all columns up to the end of the sheet
+    pub last_col: Option<usize>,
+}
+
+impl CellRange {
+    /// Create a range from its first and last cell, as zero-based (row, column)
+    pub fn new(start: (usize, usize), end: (usize, usize)) -> Self {
+        CellRange {
+            first_row: start.0,
+            first_col: start.1,
+            last_row: Some(end.0),
+            last_col: Some(end.1),
+        }
+    }
+
+    /// Zero-based (row, column) of the first cell of the range
+    pub fn start(&self) -> (usize, usize) {
+        (self.first_row, self.first_col)
+    }
+
+    /// Last cell of the range within a sheet whose used area ends at `end`
+    ///
+    /// Returns None if the range lies entirely below or right of the used area.
+    pub fn end_within(&self, end: (usize, usize)) -> Option<(usize, usize)> {
+        let last_row = self.last_row.map_or(end.0, |row| row.min(end.0));
+        let last_col = self.last_col.map_or(end.1, |col| col.min(end.1));
+        (last_row >= self.first_row && last_col >= self.first_col).then_some((last_row, last_col))
+    }
+}
+
+impl FromStr for CellRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let reference = s.trim().replace('$', "");
+        let invalid = || format!("Invalid cell range '{}' (expected e.g. A5:K2000, A:K or 5:2000)", s.trim());
+        let (first, last, whole) = match reference.split_once(':') {
+            Some((first, last)) => (first, last, true),
+            None => (reference.as_str(), reference.as_str(), false),
+        };
+
+        let range = if let (Some(start), Some(end)) = (cell_position(first), cell_position(last)) {
+            CellRange::new(start, end)
+        } else if !whole {
+            anyhow::bail!(invalid());
+        } else if let (Some(first_col), Some(last_col)) = (column_index(first), column_index(last)) {
+            CellRange {
+                first_row: 0,
+                first_col,
+                last_row: None,
+                last_col: Some(last_col),
+            }
+        } else {
+            let row = |text: &str| text.parse::<usize>().ok().and_then(|row| row.checked_sub(1));
+            let (first_row, last_row) = row(first).zip(row(last)).with_context(invalid)?;
+            CellRange {
+                first_row,
+                first_col: 0,
+                last_row: Some(last_row),
+                last_col: None,
+            }
+        };
+
+        if range.last_row.is_some_and(|row| row < range.first_row)
+            || range.last_col.is_some_and(|col| col < range.first_col)
+        {
+            anyhow::bail!("Cell range '{}' must start at its top left cell", s.trim());
+        }
+        Ok(range)
+    }
+}
+
+impl fmt::Display for CellRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.last_row, self.last_col) {
+            (Some(last_row), Some(last_col)) => write!(
+                f,
+                "{}{}:{}{}",
+                column_letter(self.first_col),
+                self.first_row + 1,
+                column_letter(last_col),
+                last_row + 1
+            ),
+            (None, Some(last_col)) => write!(f, "{}:{}", column_letter(self.first_col), column_letter(last_col)),
+            (Some(last_row), None) => write!(f, "{}:{}", self.first_row + 1, last_row + 1),
+            // Up to the last cell Excel allows
+            (None, None) => write!(f, "{}{}:XFD1048576", column_letter(self.first_col), self.first_row + 1),
+        }
+    }
+}
+
+/// How the cells of two worksheets are lined up for the comparison
+///
+/// Rows are paired by content (or key) wherever they are in the sheet, so the
+/// anchor decides which columns are compared with each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Anchor {
+    /// Compare cells in the same column, e.g. C4 with C7
+    #[default]
+    Absolute,
+    /// Compare each worksheet from its first cell, so a table at C4 matches the same table at A1
+    Relative,
+}
+
+impl Anchor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Anchor::Absolute => "absolute",
+            Anchor::Relative => "relative",
+        }
+    }
+
+    /// Line up two worksheets for the comparison
+    ///
+    /// With absolute anchoring, worksheets that start in different columns (see
+    /// [`Worksheet::origin`]) are extended to start in the same column, so each
+    /// column of the comparison is the same column in both files. An empty
+    /// worksheet is left as it is.
+    pub fn align(&self, sheet1: Worksheet, sheet2: Worksheet) -> (Worksheet, Worksheet) {
+        let (col1, col2) = (sheet1.origin.1, sheet2.origin.1);
+        if *self == Anchor::Relative || sheet1.is_empty() || sheet2.is_empty() || col1 == col2 {
+            return (sheet1, sheet2);
+        }
+        let col = col1.min(col2);
+        (
+            sheet1.extend_to((sheet1.origin.0, col)),
+            sheet2.extend_to((sheet2.origin.0, col)),
+        )
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "absolute" => Ok(Anchor::Absolute),
+            "relative" => Ok(Anchor::Relative),
+            _ => anyhow::bail!("Unknown anchor '{}' (expected absolute or relative)", s),
+        }
+    }
+}
+
+/// Region of a workbook to compare, as given by the user
+///
+/// Names that look like a cell reference (such as `TBL1`) are read as one,
+/// as Excel does not allow such names either.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RangeSelection {
+    /// Cell range of the selected sheet (`A5:K2000`)
+    Cells(CellRange),
+    /// Cell range of a given sheet (`'Q1 Data'!A5:K2000`), as defined names refer to ranges
+    SheetCells(String, CellRange),
+    /// Defined name or Excel table, resolved by the reader
+    Name(String),
+}
+
+impl RangeSelection {
+    /// Sheet to read for this selection, checking it against the sheet selected by the user
+    ///
+    /// # Arguments
+    /// * `sheet_name` - Sheet selected by the user, if any
+    /// * `referenced` - Sheet the selection refers to, if it names one
+    pub(crate) fn sheet<'a>(sheet_name: Option<&'a str>, referenced: Option<&'a str>) -> Result<Option<&'a str>> {
+        match (sheet_name, referenced) {
+            (Some(selected), Some(referenced)) if selected != referenced => anyhow::bail!(
+                "The range refers to sheet '{}', but sheet '{}' was selected",
+                referenced,
+                selected
+            ),
+            (selected, referenced) => Ok(referenced.or(selected)),
+        }
+    }
+}
+
+impl FromStr for RangeSelection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        if let Some((sheet, range)) = s.rsplit_once('!') {
+            // Sheet names with spaces or punctuation are quoted, with quotes doubled
+            let sheet = match sheet.strip_prefix('\'').and_then(|sheet| sheet.strip_suffix('\'')) {
+                Some(quoted) => quoted.replace("''", "'"),
+                None => sheet.to_string(),
+            };
+            return Ok(RangeSelection::SheetCells(sheet, range.parse()?));
+        }
+        if let Ok(range) = s.parse() {
+            return Ok(RangeSelection::Cells(range));
+        }
+        if s.is_empty() || s.contains(|c: char| c.is_whitespace() || matches!(c, ':' | ',' | '(' | ')')) {
+            anyhow::bail!("Invalid range '{}' (expected a cell range such as A5:K2000, a defined name or a table name)", s);
+        }
+        Ok(RangeSelection::Name(s.to_string()))
+    }
+}
+
+impl fmt::Display for RangeSelection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RangeSelection::Cells(range) => write!(f, "{}", range),
+            RangeSelection::SheetCells(sheet, range) => write!(f, "'{}'!{}", sheet.replace('\'', "''"), range),
+            RangeSelection::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
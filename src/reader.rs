@@ -1,15 +1,27 @@
 //! File reader interface and implementations.
 
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::datetime::format_excel_serial;
+use crate::range::{CellRange, RangeSelection};
+use crate::styles::SheetStyles;
 
 /// Cell value type that can hold various data types from Excel
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
 pub enum CellValue {
     String(String),
     Float(f64),
     Int(i64),
     Bool(bool),
     DateTime(f64), // Excel datetime stored as float (days since 1900-01-01)
+    Error(String), // Excel error value as shown in the cell, e.g. "#REF!" or "#DIV/0!"
     Empty,
 }
 
@@ -39,24 +51,226 @@ impl CellValue {
         }
     }
 
-    /// Convert to display string
-    pub fn to_string(&self) -> String {
+    /// Name of the value type for display (e.g. "number" or "text")
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            CellValue::String(_) => "text",
+            CellValue::Float(_) => "number",
+            CellValue::Int(_) => "integer",
+            CellValue::Bool(_) => "boolean",
+            CellValue::DateTime(_) => "date",
+            CellValue::Error(_) => "error",
+            CellValue::Empty => "empty",
+        }
+    }
+
+    /// Numeric value of integer and float cells
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            CellValue::Int(i) => Some(*i as f64),
+            CellValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Whether the value is an Excel error such as `#REF!`
+    pub fn is_error(&self) -> bool {
+        matches!(self, CellValue::Error(_))
+    }
+
+    /// Format for display in reports, with dates as `YYYY-MM-DD HH:MM:SS` text
+    pub fn formatted(&self) -> String {
         match self {
-            CellValue::String(s) => s.clone(),
-            CellValue::Float(f) => f.to_string(),
-            CellValue::Int(i) => i.to_string(),
-            CellValue::Bool(b) => b.to_string(),
-            CellValue::DateTime(f) => f.to_string(), // Display as numeric value for comparison purposes
-            CellValue::Empty => String::new(),
+            CellValue::DateTime(serial) => format_excel_serial(*serial).unwrap_or_else(|| self.to_string()),
+            _ => self.to_string(),
         }
     }
 }
 
+impl fmt::Display for CellValue {
+    /// Format as display string
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellValue::String(s) => write!(f, "{}", s),
+            CellValue::Float(v) => write!(f, "{}", v),
+            CellValue::Int(i) => write!(f, "{}", i),
+            CellValue::Bool(b) => write!(f, "{}", b),
+            CellValue::DateTime(v) => write!(f, "{}", v), // Display as numeric value for comparison purposes
+            CellValue::Error(e) => write!(f, "{}", e),
+            CellValue::Empty => Ok(()),
+        }
+    }
+}
+
+/// Error values that Excel accepts as literals in formulas
+pub const EXCEL_ERRORS: [&str; 7] = ["#NULL!", "#DIV/0!", "#VALUE!", "#REF!", "#NAME?", "#NUM!", "#N/A"];
+
 /// A row is a vector of cell values
 pub type Row = Vec<CellValue>;
 
-/// A worksheet is a vector of rows
-pub type Worksheet = Vec<Row>;
+/// A worksheet: its rows of cell values and, if the reader captures them, the styles of its cells
+///
+/// A worksheet dereferences to its rows, so it can be used like a `Vec<Row>`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Worksheet {
+    /// Rows of cell values
+    pub rows: Vec<Row>,
+    /// Cell styles of the source workbook (None if the file format has no styles or they were not read)
+    pub styles: Option<Arc<SheetStyles>>,
+    /// Formula text (without the leading `=`) per (row, column) of the cells
+    /// that contain a formula; empty unless the reader was asked for formulas
+    pub formulas: HashMap<(usize, usize), String>,
+    /// Zero-based (row, column) of the first cell in the source sheet, e.g. (4, 0) for a range starting at A5
+    pub origin: (usize, usize),
+}
+
+impl Worksheet {
+    pub fn new() -> Self {
+        Worksheet {
+            rows: Vec::new(),
+            styles: None,
+            formulas: HashMap::new(),
+            origin: (0, 0),
+        }
+    }
+
+    /// Attach the cell styles of the source workbook
+    pub fn with_styles(mut self, styles: SheetStyles) -> Self {
+        self.styles = Some(Arc::new(styles));
+        self
+    }
+
+    /// Attach the formulas of the cells
+    pub fn with_formulas(mut self, formulas: HashMap<(usize, usize), String>) -> Self {
+        self.formulas = formulas;
+        self
+    }
+
+    /// Set the position of the first cell in the source sheet
+    pub fn with_origin(mut self, origin: (usize, usize)) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Extend the worksheet to start at an earlier cell of the source sheet
+    ///
+    /// The cells between `start` and the first cell of the worksheet are empty.
+    pub fn extend_to(&self, start: (usize, usize)) -> Worksheet {
+        self.crop(&CellRange {
+            first_row: start.0,
+            first_col: start.1,
+            last_row: None,
+            last_col: None,
+        })
+    }
+
+    /// Keep only the cells within a range of the source sheet
+    ///
+    /// Cells of the range outside the worksheet are empty. The origin of the
+    /// result is the first cell of the range, and formulas and styles are
+    /// moved along.
+    pub fn crop(&self, range: &CellRange) -> Worksheet {
+        let width = self.rows.iter().map(Row::len).max().unwrap_or(0);
+        let (origin_row, origin_col) = self.origin;
+        let last_cell = (origin_row + self.rows.len())
+            .checked_sub(1)
+            .zip((origin_col + width).checked_sub(1));
+        let Some((last_row, last_col)) = last_cell.and_then(|end| range.end_within(end)) else {
+            return Worksheet::new().with_origin(range.start());
+        };
+
+        // Offsets of the range within the worksheet (the range may also start before it)
+        let source_row = |row: usize| row.checked_sub(origin_row);
+        let source_col = |col: usize| col.checked_sub(origin_col);
+        let rows = (range.first_row..=last_row)
+            .map(|row| {
+                let cells = source_row(row).and_then(|row| self.rows.get(row));
+                (range.first_col..=last_col)
+                    .map(|col| {
+                        cells
+                            .zip(source_col(col))
+                            .and_then(|(cells, col)| cells.get(col))
+                            .cloned()
+                            .unwrap_or(CellValue::Empty)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let offset = (
+            range.first_row as isize - origin_row as isize,
+            range.first_col as isize - origin_col as isize,
+        );
+        let formulas = self
+            .formulas
+            .iter()
+            .filter_map(|(&(row, col), formula)| {
+                let row = usize::try_from(row as isize - offset.0).ok()?;
+                let col = usize::try_from(col as isize - offset.1).ok()?;
+                Some(((row, col), formula.clone()))
+            })
+            .collect();
+
+        Worksheet {
+            rows,
+            styles: self.styles.as_ref().map(|styles| Arc::new(styles.shifted(offset))),
+            formulas,
+            origin: range.start(),
+        }
+    }
+
+    /// Formula of the cell at a position (without the leading `=`), if it contains one
+    pub fn formula(&self, row: usize, col: usize) -> Option<&str> {
+        self.formulas.get(&(row, col)).map(String::as_str)
+    }
+}
+
+impl Deref for Worksheet {
+    type Target = Vec<Row>;
+
+    fn deref(&self) -> &Vec<Row> {
+        &self.rows
+    }
+}
+
+impl DerefMut for Worksheet {
+    fn deref_mut(&mut self) -> &mut Vec<Row> {
+        &mut self.rows
+    }
+}
+
+impl From<Vec<Row>> for Worksheet {
+    fn from(rows: Vec<Row>) -> Self {
+        Worksheet {
+            rows,
+            ..Worksheet::new()
+        }
+    }
+}
+
+impl IntoIterator for Worksheet {
+    type Item = Row;
+    type IntoIter = std::vec::IntoIter<Row>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Worksheet {
+    type Item = &'a Row;
+    type IntoIter = std::slice::Iter<'a, Row>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.iter()
+    }
+}
+
+impl FromIterator<Row> for Worksheet {
+    fn from_iter<I: IntoIterator<Item = Row>>(iter: I) -> Self {
+        Worksheet::from(iter.into_iter().collect::<Vec<Row>>())
+    }
+}
 
 /// Abstract trait for file readers that can read worksheet data
 pub trait FileReader {
@@ -70,6 +284,30 @@ pub trait FileReader {
     /// A worksheet containing rows of cell values
     fn read(&self, file_path: &str, sheet_name: Option<&str>) -> Result<Worksheet>;
 
+    /// Read a region of a worksheet
+    ///
+    /// The origin of the worksheet is the first cell of the region, so diffs
+    /// can still be reported at their address in the source sheet. The default
+    /// implementation crops the worksheet returned by [`read`] and does not
+    /// know any named ranges.
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to the file
+    /// * `sheet_name` - Optional sheet name (None for first sheet, or the sheet the selection refers to)
+    /// * `selection` - Cell range, defined name or table to read
+    ///
+    /// [`read`]: FileReader::read
+    fn read_range(&self, file_path: &str, sheet_name: Option<&str>, selection: &RangeSelection) -> Result<Worksheet> {
+        let (sheet, range) = match selection {
+            RangeSelection::Cells(range) => (sheet_name, range),
+            RangeSelection::SheetCells(sheet, range) => (RangeSelection::sheet(sheet_name, Some(sheet))?, range),
+            RangeSelection::Name(name) => {
+                anyhow::bail!("{} has no defined names or tables, so '{}' cannot be found", file_path, name)
+            }
+        };
+        Ok(self.read(file_path, sheet)?.crop(range))
+    }
+
     /// Get list of sheet names in the file
     ///
     /// # Arguments
@@ -88,3 +326,12 @@ pub trait FileReader {
     /// true if the reader can handle this file
     fn supports(&self, file_path: &str) -> bool;
 }
+
+/// Check whether a file has one of the given extensions (case-insensitive)
+pub(crate) fn has_extension(file_path: &str, extensions: &[&str]) -> bool {
+    Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+        .unwrap_or(false)
+}
@@ -0,0 +1,53 @@
+//! Selection of a file reader by file type.
+
+use crate::csv_reader::CsvReader;
+use crate::excel_reader::ExcelReader;
+use crate::reader::FileReader;
+
+/// Collection of file readers, queried in registration order
+///
+/// The default registry knows the Excel and delimited text readers. Additional
+/// formats can be supported by registering another `FileReader`.
+pub struct ReaderRegistry {
+    readers: Vec<Box<dyn FileReader>>,
+}
+
+impl ReaderRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        ReaderRegistry { readers: Vec::new() }
+    }
+
+    /// Add a reader; readers registered earlier take precedence
+    pub fn register(&mut self, reader: Box<dyn FileReader>) {
+        self.readers.push(reader);
+    }
+
+    /// Add a reader and return the registry (for chaining)
+    pub fn with_reader(mut self, reader: Box<dyn FileReader>) -> Self {
+        self.register(reader);
+        self
+    }
+
+    /// Find the first reader that supports the given file
+    ///
+    /// # Arguments
+    /// * `file_path` - Path to the file
+    ///
+    /// # Returns
+    /// The reader, or None if no registered reader supports the file
+    pub fn reader_for(&self, file_path: &str) -> Option<&dyn FileReader> {
+        self.readers
+            .iter()
+            .find(|reader| reader.supports(file_path))
+            .map(|reader| reader.as_ref())
+    }
+}
+
+impl Default for ReaderRegistry {
+    fn default() -> Self {
+        ReaderRegistry::new()
+            .with_reader(Box::new(ExcelReader::new()))
+            .with_reader(Box::new(CsvReader::new()))
+    }
+}
@@ -0,0 +1,520 @@
+//! Streaming comparison of worksheets too large to load at once.
+
+use anyhow::{Context, Result};
+use calamine::{open_workbook, Cell, DataRef, Reader, Xlsx, XlsxError};
+use caseless::Caseless;
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::BufReader;
+
+use crate::columns::cell_address;
+use crate::datetime::parse_iso_datetime;
+use crate::differ::{DiffType, RowDiff, WorksheetDiffer};
+use crate::excel_reader::WorkbookFormat;
+use crate::header::HeaderRow;
+use crate::reader::{CellValue, Row};
+use crate::workbook::SheetOrigins;
+
+/// A row of a worksheet with its zero-based row number
+///
+/// As in a [`Worksheet`](crate::reader::Worksheet), the row number and the
+/// cells count from the origin of the rows (see [`RowStream::origin`]).
+pub type SheetRow = (usize, Row);
+
+/// An .xlsx or .xlsm workbook whose sheets are read one row at a time
+///
+/// Only the shared strings of the workbook are loaded; cells are parsed from
+/// the sheet as the rows are read.
+pub struct StreamingWorkbook {
+    workbook: Xlsx<BufReader<File>>,
+}
+
+impl StreamingWorkbook {
+    /// Open a workbook, which must be in the Office Open XML format
+    pub fn open(file_path: &str) -> Result<Self> {
+        if WorkbookFormat::detect(file_path).ok() != Some(WorkbookFormat::Xlsx) {
+            anyhow::bail!("Only .xlsx and .xlsm workbooks can be streamed: {}", file_path);
+        }
+        let workbook = open_workbook(file_path)
+            .with_context(|| format!("Failed to open {} workbook: {}", WorkbookFormat::Xlsx.as_str(), file_path))?;
+        Ok(StreamingWorkbook { workbook })
+    }
+
+    /// Names of the sheets of the workbook, in workbook order
+    pub fn sheet_names(&self) -> Vec<String> {
+        self.workbook.sheet_names()
+    }
+
+    /// Read the rows of a sheet, or of the first sheet if no name is given
+    ///
+    /// Rows without any value are skipped. Rows and cells count from the first
+    /// cell of the used range of the sheet, as declared by the workbook, like
+    /// the worksheets read by [`ExcelReader`](crate::excel_reader::ExcelReader).
+    pub fn rows(&mut self, sheet_name: Option<&str>) -> Result<RowStream<'_>> {
+        let sheet = match sheet_name {
+            Some(name) => name.to_string(),
+            None => self
+                .workbook
+                .sheet_names()
+                .first()
+                .context("Workbook has no sheets")?
+                .clone(),
+        };
+        let mut cells = self
+            .workbook
+            .worksheet_cells_reader(&sheet)
+            .with_context(|| format!("Failed to read sheet: {}", sheet))?;
+        let (row, col) = cells.dimensions().start;
+        Ok(RowStream {
+            next_cell: Box::new(move || cells.next_cell()),
+            pending: None,
+            origin: (row as usize, col as usize),
+            finished: false,
+        })
+    }
+}
+
+/// Rows of a worksheet, read from the cells of the sheet in order
+///
+/// Created by [`StreamingWorkbook::rows`]. A read error is yielded once and
+/// ends the stream.
+pub struct RowStream<'a> {
+    next_cell: Box<dyn FnMut() -> Result<Option<Cell<DataRef<'a>>>, XlsxError> + 'a>,
+    /// First cell of the next row, read while looking for the end of the previous one
+    pending: Option<Cell<DataRef<'a>>>,
+    /// Zero-based (row, column) in the sheet of the first cell of the rows
+    origin: (usize, usize),
+    finished: bool,
+}
+
+impl RowStream<'_> {
+    /// Zero-based (row, column) in the sheet of the first cell of the rows
+    ///
+    /// This is the start of the used range of the sheet, unless the rows were
+    /// extended with [`extend_to`](RowStream::extend_to).
+    pub fn origin(&self) -> (usize, usize) {
+        self.origin
+    }
+
+    /// Start the rows at an earlier cell of the sheet
+    ///
+    /// The cells between `start` and the origin are empty, as with
+    /// [`Worksheet::extend_to`](crate::reader::Worksheet::extend_to). Must be
+    /// called before the first row is read.
+    pub fn extend_to(mut self, start: (usize, usize)) -> Self {
+        self.origin = (self.origin.0.min(start.0), self.origin.1.min(start.1));
+        self
+    }
+
+    /// Read the next row that has a value, if any
+    fn read_row(&mut self) -> Result<Option<SheetRow>> {
+        let mut current: Option<SheetRow> = None;
+        loop {
+            let cell = match self.pending.take() {
+                Some(cell) => cell,
+                None => match (self.next_cell)().context("Failed to read a cell")? {
+                    Some(cell) => cell,
+                    // The reader must not be called again after the end of the sheet
+                    None => {
+                        self.finished = true;
+                        return Ok(current);
+                    }
+                },
+            };
+            let (row_idx, col_idx) = cell.get_position();
+            let (row_idx, col_idx) = (row_idx as usize, col_idx as usize);
+            let (Some(row_idx), Some(col_idx)) =
+                (row_idx.checked_sub(self.origin.0), col_idx.checked_sub(self.origin.1))
+            else {
+                anyhow::bail!(
+                    "Cell {} lies before the start of the used range of the sheet ({})",
+                    cell_address(row_idx, col_idx),
+                    cell_address(self.origin.0, self.origin.1)
+                );
+            };
+            match &current {
+                Some((current_row, _)) if *current_row != row_idx => {
+                    self.pending = Some(cell);
+                    return Ok(current);
+                }
+                _ => {}
+            }
+
+            // Cells without a value (such as formatted blank cells) do not make a row
+            let value = cell_value(cell.get_value());
+            if value == CellValue::Empty {
+                continue;
+            }
+            let (_, row) = current.get_or_insert_with(|| (row_idx, Row::new()));
+            if row.len() <= col_idx {
+                row.resize(col_idx + 1, CellValue::Empty);
+            }
+            row[col_idx] = value;
+        }
+    }
+}
+
+impl Iterator for RowStream<'_> {
+    type Item = Result<SheetRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let row = self.read_row().transpose();
+        if !matches!(row, Some(Ok(_))) {
+            self.finished = true;
+        }
+        row
+    }
+}
+
+/// Convert a streamed calamine cell to a cell value
+fn cell_value(value: &DataRef) -> CellValue {
+    match value {
+        DataRef::Int(i) => CellValue::Int(*i),
+        DataRef::Float(f) => CellValue::Float(*f),
+        DataRef::String(s) => CellValue::String(s.clone()),
+        DataRef::SharedString(s) => CellValue::String(s.to_string()),
+        DataRef::Bool(b) => CellValue::Bool(*b),
+        DataRef::DateTime(dt) => CellValue::DateTime(dt.as_f64()),
+        DataRef::DateTimeIso(s) => parse_iso_datetime(s)
+            .map(CellValue::DateTime)
+            .unwrap_or_else(|| CellValue::String(s.clone())),
+        DataRef::DurationIso(s) => CellValue::String(s.clone()),
+        DataRef::Error(e) => CellValue::Error(e.to_string()),
+        DataRef::Empty => CellValue::Empty,
+    }
+}
+
+/// Row diffs of two sorted row streams, created by [`WorksheetDiffer::compare_iter`]
+pub struct RowDiffs<'a, I1, I2> {
+    differ: &'a WorksheetDiffer,
+    old: SortedRows<I1>,
+    new: SortedRows<I2>,
+    /// Diffs of the rows up to the header rows, compared by position
+    leading: VecDeque<RowDiff>,
+    /// Diffs of a run of rows whose keys are equal ignoring case, not yet returned
+    run: VecDeque<RowDiff>,
+    origins: SheetOrigins,
+    started: bool,
+    finished: bool,
+    row_index: usize,
+}
+
+impl<'a, I1, I2> RowDiffs<'a, I1, I2>
+where
+    I1: Iterator<Item = Result<SheetRow>>,
+    I2: Iterator<Item = Result<SheetRow>>,
+{
+    pub(crate) fn new(differ: &'a WorksheetDiffer, rows1: I1, rows2: I2) -> Self {
+        RowDiffs {
+            differ,
+            old: SortedRows::new(rows1, "old"),
+            new: SortedRows::new(rows2, "new"),
+            leading: VecDeque::new(),
+            run: VecDeque::new(),
+            origins: SheetOrigins::default(),
+            started: false,
+            finished: false,
+            row_index: 0,
+        }
+    }
+
+    /// Set where the rows of both streams start in their sheets (see [`RowStream::origin`])
+    ///
+    /// The origins are used for the addresses of the row diffs and in error messages.
+    pub fn with_origins(mut self, origins: SheetOrigins) -> Self {
+        self.origins = origins;
+        self.old.origin_row = origins.old.0;
+        self.new.origin_row = origins.new.0;
+        self
+    }
+
+    /// Compare the rows above the header and the header rows of both streams by position
+    fn compare_leading_rows(&mut self) -> Result<()> {
+        let header_row = self.differ.header_row();
+        self.old.read_ahead(header_row.search_rows())?;
+        self.new.read_ahead(header_row.search_rows())?;
+        let header1 = self.old.locate_header(header_row);
+        let header2 = self.new.locate_header(header_row);
+        if header1.is_none() && header2.is_none() {
+            return Ok(());
+        }
+
+        let (above1, above2) = (header1.unwrap_or(0), header2.unwrap_or(0));
+        for idx in 0..above1.max(above2) {
+            let row1 = (idx < above1).then(|| self.old.take_buffered()).flatten();
+            let row2 = (idx < above2).then(|| self.new.take_buffered()).flatten();
+            self.leading.extend(row_diff(self.differ, row1, row2));
+        }
+        let row1 = header1.and_then(|_| self.old.take_buffered());
+        let row2 = header2.and_then(|_| self.new.take_buffered());
+        if let Some(header) = row_diff(self.differ, row1, row2) {
+            self.leading.push_back(RowDiff { header: true, ..header });
+        }
+        Ok(())
+    }
+
+    /// Pair the next rows of both streams by their sort keys
+    fn merge_next(&mut self) -> Result<Option<RowDiff>> {
+        let differ = self.differ;
+        let order = match (self.old.peek_key(differ)?, self.new.peek_key(differ)?) {
+            (None, None) => return Ok(None),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(key1), Some(key2)) => compare_keys(key1, key2, compare_cells),
+        };
+        Ok(match order {
+            Ordering::Less => row_diff(differ, Some(self.old.take()), None),
+            Ordering::Greater => row_diff(differ, None, Some(self.new.take())),
+            Ordering::Equal => {
+                let (old_run, new_run) = (self.old.take_run(differ)?, self.new.take_run(differ)?);
+                self.pair_run(old_run, new_run);
+                self.run.pop_front()
+            }
+        })
+    }
+
+    /// Pair the rows of both streams whose keys are equal ignoring case
+    ///
+    /// Excel may sort keys that only differ in case in any order, so rows are
+    /// paired on their exact key within the run and the diffs are returned in
+    /// character order of the keys.
+    fn pair_run(&mut self, old_run: Vec<(SheetRow, Row)>, new_run: Vec<(SheetRow, Row)>) {
+        let mut new_run: Vec<Option<(SheetRow, Row)>> = new_run.into_iter().map(Some).collect();
+        let mut diffs: Vec<(Row, Option<RowDiff>)> = Vec::new();
+        for (row1, key1) in old_run {
+            let partner = new_run.iter_mut().find(|new| {
+                new.as_ref()
+                    .is_some_and(|(_, key2)| compare_keys(&key1, key2, compare_cells_exact).is_eq())
+            });
+            let row2 = partner.and_then(Option::take).map(|(row, _)| row);
+            diffs.push((key1, row_diff(self.differ, Some(row1), row2)));
+        }
+        for (row2, key2) in new_run.into_iter().flatten() {
+            diffs.push((key2, row_diff(self.differ, None, Some(row2))));
+        }
+
+        diffs.sort_by(|(key1, _), (key2, _)| compare_keys(key1, key2, compare_cells_exact));
+        self.run.extend(diffs.into_iter().filter_map(|(_, diff)| diff));
+    }
+
+    fn next_diff(&mut self) -> Result<Option<RowDiff>> {
+        if !self.started {
+            self.started = true;
+            self.compare_leading_rows()?;
+        }
+        let diff = match self.leading.pop_front().or_else(|| self.run.pop_front()) {
+            Some(diff) => Some(diff),
+            None => self.merge_next()?,
+        };
+        let Some(mut diff) = diff else {
+            return Ok(None);
+        };
+
+        diff.row_index = self.row_index;
+        self.row_index += 1;
+        let diffs = std::slice::from_mut(&mut diff);
+        self.differ.mark_ignored(diffs);
+        self.origins.locate(diffs);
+        Ok(Some(diff))
+    }
+}
+
+impl<I1, I2> Iterator for RowDiffs<'_, I1, I2>
+where
+    I1: Iterator<Item = Result<SheetRow>>,
+    I2: Iterator<Item = Result<SheetRow>>,
+{
+    type Item = Result<RowDiff>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let diff = self.next_diff().transpose();
+        self.finished = !matches!(diff, Some(Ok(_)));
+        diff
+    }
+}
+
+/// One of the compared row streams, checked to be sorted as it is read
+struct SortedRows<I> {
+    rows: I,
+    /// Rows read ahead while looking for the header row
+    buffered: VecDeque<SheetRow>,
+    /// Next row to merge, with its sort key
+    head: Option<(SheetRow, Row)>,
+    /// Sort key of the last merged row
+    last_key: Option<Row>,
+    /// Which worksheet the rows belong to, for error messages
+    name: &'static str,
+    /// Zero-based row in the sheet of the first row, for error messages
+    origin_row: usize,
+}
+
+impl<I: Iterator<Item = Result<SheetRow>>> SortedRows<I> {
+    fn new(rows: I, name: &'static str) -> Self {
+        SortedRows {
+            rows,
+            buffered: VecDeque::new(),
+            head: None,
+            last_key: None,
+            name,
+            origin_row: 0,
+        }
+    }
+
+    /// Read rows ahead until `count` rows are buffered or the stream ends
+    fn read_ahead(&mut self, count: usize) -> Result<()> {
+        while self.buffered.len() < count {
+            match self.rows.next().transpose()? {
+                Some(row) => self.buffered.push_back(row),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Position of the header row among the buffered rows
+    fn locate_header(&self, header_row: HeaderRow) -> Option<usize> {
+        let rows: Vec<Row> = self.buffered.iter().map(|(_, row)| row.clone()).collect();
+        header_row.locate(&rows)
+    }
+
+    fn take_buffered(&mut self) -> Option<SheetRow> {
+        self.buffered.pop_front()
+    }
+
+    /// Sort key of the next row, checking it does not sort before the previous row
+    fn peek_key(&mut self, differ: &WorksheetDiffer) -> Result<Option<&Row>> {
+        if self.head.is_none() {
+            let row = match self.buffered.pop_front() {
+                Some(row) => Some(row),
+                None => self.rows.next().transpose()?,
+            };
+            if let Some(row) = row {
+                let key = differ.sort_key(&row.1);
+                let out_of_order = self
+                    .last_key
+                    .as_ref()
+                    .is_some_and(|last| compare_keys(&key, last, compare_cells) == Ordering::Less);
+                if out_of_order {
+                    anyhow::bail!(
+                        "Row {} of the {} sheet is out of order; a streaming comparison needs both sheets \
+                         sorted in ascending order on the key columns (or on all columns without key \
+                         columns) as Excel sorts them: numbers and dates, then text ignoring case, \
+                         booleans, error values and blank cells",
+                        self.origin_row + row.0 + 1,
+                        self.name
+                    );
+                }
+                self.head = Some((row, key));
+            }
+        }
+        Ok(self.head.as_ref().map(|(_, key)| key))
+    }
+
+    /// Take the row whose key was peeked last
+    fn take(&mut self) -> SheetRow {
+        let (row, key) = self.head.take().expect("row taken without peeking its key");
+        self.last_key = Some(key);
+        row
+    }
+
+    /// Take the next row and the rows after it whose keys are equal to its key ignoring case
+    fn take_run(&mut self, differ: &WorksheetDiffer) -> Result<Vec<(SheetRow, Row)>> {
+        let mut run: Vec<(SheetRow, Row)> = Vec::new();
+        while let Some(key) = self.peek_key(differ)? {
+            if run
+                .first()
+                .is_some_and(|(_, first)| compare_keys(key, first, compare_cells).is_ne())
+            {
+                break;
+            }
+            let key = key.clone();
+            run.push((self.take(), key));
+        }
+        Ok(run)
+    }
+}
+
+/// Build the diff of a row of either stream, or of a pair of rows, if either exists
+fn row_diff(differ: &WorksheetDiffer, old: Option<SheetRow>, new: Option<SheetRow>) -> Option<RowDiff> {
+    let diff = match (old, new) {
+        (Some((idx1, row1)), Some((idx2, row2))) => {
+            let width = row1.len().max(row2.len());
+            let row1 = differ.normalize_row(&row1, width);
+            let row2 = differ.normalize_row(&row2, width);
+            differ
+                .paired_row(0, std::slice::from_ref(&row1), 0, std::slice::from_ref(&row2))
+                .with_positions(Some(idx1), Some(idx2))
+        }
+        (Some((idx1, row1)), None) => {
+            RowDiff::new(0, DiffType::Removed, row1, vec![], None).with_positions(Some(idx1), None)
+        }
+        (None, Some((idx2, row2))) => {
+            RowDiff::new(0, DiffType::Added, row2, vec![], None).with_positions(None, Some(idx2))
+        }
+        (None, None) => return None,
+    };
+    Some(diff)
+}
+
+/// Order of two sort keys, cell by cell, with missing cells as blank cells
+fn compare_keys(
+    key1: &[CellValue],
+    key2: &[CellValue],
+    compare: impl Fn(&CellValue, &CellValue) -> Ordering,
+) -> Ordering {
+    let width = key1.len().max(key2.len());
+    (0..width)
+        .map(|col_idx| {
+            compare(
+                key1.get(col_idx).unwrap_or(&CellValue::Empty),
+                key2.get(col_idx).unwrap_or(&CellValue::Empty),
+            )
+        })
+        .find(|order| order.is_ne())
+        .unwrap_or(Ordering::Equal)
+}
+
+/// Order of two normalized cells as Excel sorts them: numbers and dates, text, booleans, errors, then blank cells
+///
+/// Text is ordered by its Unicode case folding, so text that only differs in
+/// case is equal.
+fn compare_cells(value1: &CellValue, value2: &CellValue) -> Ordering {
+    let rank = |value: &CellValue| match value {
+        CellValue::Int(_) | CellValue::Float(_) | CellValue::DateTime(_) => 0,
+        CellValue::String(_) => 1,
+        CellValue::Bool(_) => 2,
+        CellValue::Error(_) => 3,
+        CellValue::Empty => 4,
+    };
+    // Dates are numbers in Excel, so they sort among the numbers
+    let number = |value: &CellValue| match value {
+        CellValue::DateTime(date) => Some(*date),
+        _ => value.as_number(),
+    };
+    match (value1, value2) {
+        (CellValue::String(s1), CellValue::String(s2)) => {
+            s1.chars().default_case_fold().cmp(s2.chars().default_case_fold())
+        }
+        (CellValue::Bool(b1), CellValue::Bool(b2)) => b1.cmp(b2),
+        (CellValue::Error(e1), CellValue::Error(e2)) => e1.cmp(e2),
+        _ => match (number(value1), number(value2)) {
+            (Some(n1), Some(n2)) => n1.total_cmp(&n2),
+            _ => rank(value1).cmp(&rank(value2)),
+        },
+    }
+}
+
+/// Order of two normalized cells, with text in character order
+fn compare_cells_exact(value1: &CellValue, value2: &CellValue) -> Ordering {
+    match (value1, value2) {
+        (CellValue::String(s1), CellValue::String(s2)) => s1.cmp(s2),
+        _ => compare_cells(value1, value2),
+    }
+}
@@ -0,0 +1,373 @@
+//! Cell styles of the source workbook: number formats, fonts, fills and column widths.
+//!
+//! calamine only reads cell values, so the styles of .xlsx and .xlsm
+//! workbooks are read from the archive's `xl/styles.xml` part and the
+//! worksheet XML directly.
+
+use anyhow::{Context, Result};
+use quick_xml::escape::unescape;
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek};
+
+use crate::columns::cell_position;
+
+/// Number format of a cell
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NumberFormat {
+    /// One of Excel's built-in formats, by index (e.g. 9 for `0%` or 14 for the short date)
+    ///
+    /// Built-in formats are kept by index, because Excel displays some of them
+    /// (such as the dates) according to the reader's locale.
+    BuiltIn(u8),
+    /// Custom format code, e.g. `#,##0.00 "€"`
+    Custom(String),
+}
+
+/// Presentation of a cell in the source workbook
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CellStyle {
+    /// Number format (None for General)
+    pub number_format: Option<NumberFormat>,
+    /// Bold font
+    pub bold: bool,
+    /// Italic font
+    pub italic: bool,
+    /// Font color as RGB (None for the default or a theme color)
+    pub font_color: Option<u32>,
+    /// Solid fill color as RGB (None for no fill or a theme color)
+    pub fill_color: Option<u32>,
+}
+
+/// Cell styles and column widths of a worksheet
+///
+/// Positions are zero-based and relative to the worksheet's data, so they
+/// can be looked up with the row and column index of a cell value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SheetStyles {
+    /// Distinct styles, referenced by index from `cells`
+    styles: Vec<CellStyle>,
+    /// Style index per (row, column) of cells that have a style
+    cells: HashMap<(usize, usize), usize>,
+    /// Column width in characters, for columns with a custom width
+    column_widths: HashMap<usize, f64>,
+}
+
+impl SheetStyles {
+    /// Style of the cell at a position, if it has one
+    pub fn style(&self, row: usize, col: usize) -> Option<&CellStyle> {
+        self.cells.get(&(row, col)).and_then(|&idx| self.styles.get(idx))
+    }
+
+    /// Width of a column in characters, if it has a custom width
+    pub fn column_width(&self, col: usize) -> Option<f64> {
+        self.column_widths.get(&col).copied()
+    }
+
+    /// Styles with every position moved up and left by an offset, dropping those that fall before the start
+    pub(crate) fn shifted(&self, (rows, cols): (isize, isize)) -> SheetStyles {
+        let shift = |position: usize, by: isize| usize::try_from(position as isize - by).ok();
+        SheetStyles {
+            styles: self.styles.clone(),
+            cells: self
+                .cells
+                .iter()
+                .filter_map(|(&(row, col), &style)| Some(((shift(row, rows)?, shift(col, cols)?), style)))
+                .collect(),
+            column_widths: self
+                .column_widths
+                .iter()
+                .filter_map(|(&col, &width)| Some((shift(col, cols)?, width)))
+                .collect(),
+        }
+    }
+}
+
+/// Read the cell styles of a sheet of an .xlsx or .xlsm workbook
+///
+/// # Arguments
+/// * `file_path` - Path to the workbook
+/// * `sheet_name` - Name of the sheet
+/// * `origin` - Zero-based (row, column) of the sheet's first data cell, which becomes position (0, 0)
+pub(crate) fn read_xlsx_styles(file_path: &str, sheet_name: &str, origin: (usize, usize)) -> Result<SheetStyles> {
+    let file = File::open(file_path).with_context(|| format!("Failed to open file: {}", file_path))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .with_context(|| format!("{} is not a valid zip archive", file_path))?;
+
+    let sheet_path = sheet_part(&mut archive, sheet_name)?;
+    let styles = match archive.index_for_name("xl/styles.xml") {
+        Some(_) => read_stylesheet(&mut xml_part(&mut archive, "xl/styles.xml")?)?,
+        None => Vec::new(),
+    };
+
+    let mut sheet = SheetStyles {
+        styles,
+        ..SheetStyles::default()
+    };
+    read_sheet_styles(&mut xml_part(&mut archive, &sheet_path)?, origin, &mut sheet)?;
+    Ok(sheet)
+}
+
+/// Open a part of the archive as an XML reader
+fn xml_part<'a, R: Read + Seek>(
+    archive: &'a mut zip::ZipArchive<R>,
+    name: &str,
+) -> Result<quick_xml::Reader<BufReader<zip::read::ZipFile<'a>>>> {
+    let entry = archive
+        .by_name(name)
+        .with_context(|| format!("Workbook has no part {}", name))?;
+    let mut reader = quick_xml::Reader::from_reader(BufReader::new(entry));
+    reader.trim_text(true);
+    Ok(reader)
+}
+
+/// Find the path of a sheet's XML part through the workbook relationships
+fn sheet_part<R: Read + Seek>(archive: &mut zip::ZipArchive<R>, sheet_name: &str) -> Result<String> {
+    let mut relationship = None;
+    let mut reader = xml_part(archive, "xl/workbook.xml")?;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) | Event::Empty(e)
+                if e.local_name().as_ref() == b"sheet" && attribute(&e, b"name").as_deref() == Some(sheet_name) =>
+            {
+                // The relationship id is the namespaced r:id attribute
+                relationship = e
+                    .attributes()
+                    .flatten()
+                    .find(|a| a.key.prefix().is_some() && a.key.local_name().as_ref() == b"id")
+                    .and_then(|a| attribute_value(&a));
+                break;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    drop(reader);
+    let relationship = relationship.with_context(|| format!("Sheet not found: {}", sheet_name))?;
+
+    let mut reader = xml_part(archive, "xl/_rels/workbook.xml.rels")?;
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) | Event::Empty(e)
+                if e.local_name().as_ref() == b"Relationship"
+                    && attribute(&e, b"Id").as_deref() == Some(relationship.as_str()) =>
+            {
+                let target = attribute(&e, b"Target").context("Sheet relationship has no target")?;
+                return Ok(match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("xl/{}", target),
+                });
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    anyhow::bail!("Sheet '{}' has no worksheet part", sheet_name)
+}
+
+/// Read the cell formats (`cellXfs`) of a stylesheet, in index order
+fn read_stylesheet<R: BufRead>(reader: &mut quick_xml::Reader<R>) -> Result<Vec<CellStyle>> {
+    #[derive(Default)]
+    struct Font {
+        bold: bool,
+        italic: bool,
+        color: Option<u32>,
+    }
+
+    let mut custom_formats: HashMap<u32, String> = HashMap::new();
+    let mut fonts: Vec<Font> = Vec::new();
+    let mut fills: Vec<Option<u32>> = Vec::new();
+    let mut cell_formats: Vec<(u32, usize, usize)> = Vec::new();
+    let mut solid_fill = false;
+
+    // Parent element of the current element, to tell e.g. font colors from fill colors
+    let mut section: Vec<u8> = Vec::new();
+    let mut buf = Vec::new();
+    loop {
+        let event = reader.read_event_into(&mut buf)?;
+        let (e, is_empty) = match &event {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::End(e) => {
+                if e.local_name().as_ref() == section.as_slice() {
+                    section.clear();
+                }
+                buf.clear();
+                continue;
+            }
+            Event::Eof => break,
+            _ => {
+                buf.clear();
+                continue;
+            }
+        };
+
+        match e.local_name().as_ref() {
+            name @ (b"fonts" | b"fills" | b"cellXfs" | b"cellStyleXfs" | b"numFmts") if !is_empty => {
+                section = name.to_vec();
+            }
+            b"numFmt" => {
+                if let (Some(id), Some(code)) = (attribute(e, b"numFmtId"), attribute(e, b"formatCode")) {
+                    if let Ok(id) = id.parse() {
+                        custom_formats.insert(id, code);
+                    }
+                }
+            }
+            b"font" if section == b"fonts" => fonts.push(Font::default()),
+            b"b" if section == b"fonts" => {
+                if let Some(font) = fonts.last_mut() {
+                    font.bold = is_enabled(e);
+                }
+            }
+            b"i" if section == b"fonts" => {
+                if let Some(font) = fonts.last_mut() {
+                    font.italic = is_enabled(e);
+                }
+            }
+            b"color" if section == b"fonts" => {
+                if let Some(font) = fonts.last_mut() {
+                    font.color = rgb_attribute(e);
+                }
+            }
+            b"fill" if section == b"fills" => fills.push(None),
+            b"patternFill" if section == b"fills" => {
+                // Only solid fills are kept; patterns would hide the diff highlighting
+                solid_fill = attribute(e, b"patternType").as_deref() == Some("solid");
+            }
+            b"fgColor" if section == b"fills" && solid_fill => {
+                if let Some(fill) = fills.last_mut() {
+                    *fill = rgb_attribute(e);
+                }
+            }
+            b"xf" if section == b"cellXfs" => {
+                let number = |key: &[u8]| attribute(e, key).and_then(|v| v.parse().ok());
+                cell_formats.push((
+                    number(b"numFmtId").unwrap_or(0),
+                    number(b"fontId").unwrap_or(0) as usize,
+                    number(b"fillId").unwrap_or(0) as usize,
+                ));
+            }
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    let styles = cell_formats
+        .into_iter()
+        .map(|(format_id, font_id, fill_id)| {
+            let number_format = match custom_formats.get(&format_id) {
+                Some(code) => Some(NumberFormat::Custom(code.clone())),
+                None if format_id == 0 => None,
+                None => u8::try_from(format_id).ok().map(NumberFormat::BuiltIn),
+            };
+            let font = fonts.get(font_id);
+            CellStyle {
+                number_format,
+                bold: font.is_some_and(|f| f.bold),
+                italic: font.is_some_and(|f| f.italic),
+                font_color: font.and_then(|f| f.color),
+                fill_color: fills.get(fill_id).copied().flatten(),
+            }
+        })
+        .collect();
+
+    Ok(styles)
+}
+
+/// Read the style index of every cell and the custom column widths of a worksheet
+fn read_sheet_styles<R: BufRead>(
+    reader: &mut quick_xml::Reader<R>,
+    origin: (usize, usize),
+    sheet: &mut SheetStyles,
+) -> Result<()> {
+    let (first_row, first_col) = origin;
+    // Cells and rows without a reference follow the previous one
+    let mut row: Option<usize> = None;
+    let mut col = 0usize;
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                b"col" => {
+                    let min: Option<usize> = attribute(&e, b"min").and_then(|v| v.parse().ok());
+                    let max: Option<usize> = attribute(&e, b"max").and_then(|v| v.parse().ok());
+                    let width: Option<f64> = attribute(&e, b"width").and_then(|v| v.parse().ok());
+                    if let (Some(min), Some(max), Some(width)) = (min, max.or(min), width) {
+                        // Limit the range: the last <col> often spans up to column XFD
+                        for col in min.max(first_col + 1)..=max.min(first_col + 1024) {
+                            sheet.column_widths.insert(col - 1 - first_col, character_width(width));
+                        }
+                    }
+                }
+                b"row" => {
+                    let next = row.map_or(0, |r| r + 1);
+                    row = Some(
+                        attribute(&e, b"r")
+                            .and_then(|v| v.parse::<usize>().ok())
+                            .and_then(|r| r.checked_sub(1))
+                            .unwrap_or(next),
+                    );
+                    col = 0;
+                }
+                b"c" => {
+                    if let Some((cell_row, cell_col)) = attribute(&e, b"r").and_then(|r| cell_position(&r)) {
+                        row = Some(cell_row);
+                        col = cell_col;
+                    }
+                    let cell_row = row.unwrap_or(0);
+                    let style = attribute(&e, b"s").and_then(|v| v.parse::<usize>().ok()).unwrap_or(0);
+                    if style > 0 && cell_row >= first_row && col >= first_col {
+                        sheet.cells.insert((cell_row - first_row, col - first_col), style);
+                    }
+                    col += 1;
+                }
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(())
+}
+
+/// Unescaped value of an attribute, by name
+fn attribute(e: &BytesStart, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .flatten()
+        .find(|a| a.key.as_ref() == key)
+        .and_then(|a| attribute_value(&a))
+}
+
+/// Unescaped value of an attribute
+fn attribute_value(attribute: &Attribute) -> Option<String> {
+    let raw = std::str::from_utf8(&attribute.value).ok()?;
+    unescape(raw).ok().map(|value| value.into_owned())
+}
+
+/// Whether a boolean font property such as `<b/>` or `<b val="0"/>` is on
+fn is_enabled(e: &BytesStart) -> bool {
+    !matches!(attribute(e, b"val").as_deref(), Some("0" | "false"))
+}
+
+/// RGB value of an `rgb="FFRRGGBB"` color attribute (theme and indexed colors are not resolved)
+fn rgb_attribute(e: &BytesStart) -> Option<u32> {
+    let argb = attribute(e, b"rgb")?;
+    u32::from_str_radix(&argb, 16).ok().map(|argb| argb & 0xFF_FFFF)
+}
+
+/// Convert a column width as stored in the file to the width in characters shown by Excel
+///
+/// The stored width includes 5 pixels of cell padding, at 7 pixels per character.
+fn character_width(stored: f64) -> f64 {
+    if stored >= 12.0 / 7.0 {
+        stored - 5.0 / 7.0
+    } else {
+        stored * 7.0 / 12.0
+    }
+}
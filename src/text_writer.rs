@@ -0,0 +1,704 @@
+//! Terminal output of diff results as a colored text table.
+
+use anyhow::Result;
+use std::io::Write;
+use std::str::FromStr;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::column_diff::{ColumnChange, ColumnDiff};
+use crate::differ::{DiffType, RowDiff};
+use crate::reader::CellValue;
+use crate::workbook::{SheetDiff, SheetOrigins, SheetStatus};
+
+/// Number of identical rows shown before and after each change
+pub const DEFAULT_CONTEXT_ROWS: usize = 3;
+
+/// Line width used when the terminal width cannot be determined
+pub const DEFAULT_TEXT_WIDTH: usize = 120;
+
+/// Narrowest a column is truncated to before columns are left out
+const MIN_COLUMN_WIDTH: usize = 4;
+
+/// Widest a column is shown, so one long value does not take up the whole line
+const MAX_COLUMN_WIDTH: usize = 40;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const ORANGE: &str = "\x1b[38;5;208m";
+const PURPLE: &str = "\x1b[35m";
+const BLUE: &str = "\x1b[34m";
+const CYAN: &str = "\x1b[36m";
+
+/// Arrangement of the old and new values in the text output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextLayout {
+    /// One line per row; modified cells show "old → new"
+    #[default]
+    Unified,
+    /// Old row on the left, new row on the right
+    SideBySide,
+}
+
+impl TextLayout {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TextLayout::Unified => "unified",
+            TextLayout::SideBySide => "side-by-side",
+        }
+    }
+}
+
+impl FromStr for TextLayout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "unified" => Ok(TextLayout::Unified),
+            "side-by-side" | "side" => Ok(TextLayout::SideBySide),
+            _ => anyhow::bail!("Unknown text layout '{}' (expected unified or side-by-side)", s),
+        }
+    }
+}
+
+/// Width of the terminal, or of the `COLUMNS` environment variable if set
+///
+/// Falls back to [`DEFAULT_TEXT_WIDTH`] when output is not a terminal.
+pub fn terminal_width() -> usize {
+    std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok())
+        .filter(|&width: &usize| width > 0)
+        .or_else(|| terminal_size::terminal_size().map(|(width, _)| width.0 as usize))
+        .unwrap_or(DEFAULT_TEXT_WIDTH)
+}
+
+/// Writer for diff results as a text table, for reading in a terminal
+///
+/// Rows are marked with ` ` (identical), `~` (modified), `>` (moved), `-`
+/// (removed) and `+` (added). With colors enabled, the existing color scheme
+/// is used: modified cells in red, moved rows in cyan, removed rows in yellow
+/// and added rows in orange.
+/// Only the rows around changes are shown; longer runs of identical rows are
+/// collapsed into a single line.
+#[derive(Debug, Clone)]
+pub struct TextDiffWriter {
+    layout: TextLayout,
+    color: bool,
+    context: usize,
+    width: usize,
+}
+
+/// A row of rendered cells ready for output
+struct Line {
+    marker: char,
+    /// Row numbers in the compared files, as shown in the gutter
+    number: String,
+    cells: Vec<String>,
+    /// Color of each cell (None for the row color)
+    cell_colors: Vec<Option<&'static str>>,
+    color: Option<&'static str>,
+}
+
+impl TextDiffWriter {
+    /// Create a writer with colors, the default context and the terminal width
+    pub fn new() -> Self {
+        TextDiffWriter {
+            layout: TextLayout::default(),
+            color: true,
+            context: DEFAULT_CONTEXT_ROWS,
+            width: terminal_width(),
+        }
+    }
+
+    /// Set the arrangement of old and new values
+    pub fn with_layout(mut self, layout: TextLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Enable or disable ANSI colors
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Set the number of identical rows shown around each change
+    pub fn with_context(mut self, context: usize) -> Self {
+        self.context = context;
+        self
+    }
+
+    /// Set the maximum line width; columns are truncated to fit
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Write diff results as a text table
+    ///
+    /// # Arguments
+    /// * `out` - Destination of the text
+    /// * `diffs` - List of RowDiff objects
+    /// * `columns` - Column diffs matching the column layout of the row data
+    /// * `diff_only` - If true, show no identical rows besides the header row
+    pub fn write<W: Write>(
+        &self,
+        out: &mut W,
+        diffs: &[RowDiff],
+        columns: &[ColumnDiff],
+        diff_only: bool,
+    ) -> Result<()> {
+        self.write_rows(out, diffs, columns, &SheetOrigins::default(), diff_only)
+    }
+
+    /// Write the diff of one sheet as a text table, numbering rows as in the compared files
+    ///
+    /// Same as [`write`], for a sheet diff of a cell range (see [`SheetDiff::with_origins`]).
+    ///
+    /// # Arguments
+    /// * `out` - Destination of the text
+    /// * `sheet` - Diff results of the sheet
+    /// * `diff_only` - If true, show no identical rows besides the header row
+    ///
+    /// [`write`]: TextDiffWriter::write
+    pub fn write_sheet_diff<W: Write>(&self, out: &mut W, sheet: &SheetDiff, diff_only: bool) -> Result<()> {
+        self.write_rows(out, &sheet.diffs, &sheet.columns, &sheet.origins, diff_only)
+    }
+
+    /// Write the diff results of a whole workbook comparison, one table per sheet
+    ///
+    /// # Arguments
+    /// * `out` - Destination of the text
+    /// * `sheets` - Diff results per sheet
+    /// * `diff_only` - If true, show no identical rows besides the header row
+    pub fn write_workbook<W: Write>(&self, out: &mut W, sheets: &[SheetDiff], diff_only: bool) -> Result<()> {
+        for (idx, sheet) in sheets.iter().enumerate() {
+            if idx > 0 {
+                writeln!(out)?;
+            }
+            let title = match sheet.status {
+                SheetStatus::Compared => format!("=== {} ===", sheet.name),
+                _ => format!("=== {} ({}) ===", sheet.name, sheet.status.as_str()),
+            };
+            writeln!(out, "{}", self.paint(&title, BOLD))?;
+
+            let summary = sheet.summary();
+            let columns_changed = sheet.columns.iter().any(|c| c.change != ColumnChange::Unchanged);
+            if sheet.status == SheetStatus::Compared && summary.changed() == 0 && !columns_changed {
+                writeln!(out, "{}", self.paint("(no differences)", DIM))?;
+                continue;
+            }
+            self.write_rows(out, &sheet.diffs, &sheet.columns, &sheet.origins, diff_only)?;
+        }
+        Ok(())
+    }
+
+    /// Write the table of one sheet, numbering rows from the origins of the compared range
+    fn write_rows<W: Write>(
+        &self,
+        out: &mut W,
+        diffs: &[RowDiff],
+        columns: &[ColumnDiff],
+        origins: &SheetOrigins,
+        diff_only: bool,
+    ) -> Result<()> {
+        let context = if diff_only { 0 } else { self.context };
+
+        if columns.iter().any(|c| c.change != ColumnChange::Unchanged) {
+            let changes: Vec<String> = columns
+                .iter()
+                .filter(|c| c.change != ColumnChange::Unchanged)
+                .map(|c| format!("'{}' {}", c.header(), c.describe()))
+                .collect();
+            writeln!(out, "{}", self.paint(&format!("Columns: {}", changes.join(", ")), DIM))?;
+        }
+
+        if diffs.is_empty() {
+            writeln!(out, "{}", self.paint("(no rows)", DIM))?;
+            return Ok(());
+        }
+
+        // Rows to show: the header row and the rows within `context` of a change
+        let header = diffs.iter().position(|diff| diff.header);
+        let mut visible = vec![false; diffs.len()];
+        if let Some(header) = header {
+            visible[header] = true;
+        }
+        for (idx, diff) in diffs.iter().enumerate() {
+            if diff.has_changes() {
+                let start = idx.saturating_sub(context);
+                let end = (idx + context + 1).min(diffs.len());
+                visible[start..end].iter_mut().for_each(|v| *v = true);
+            }
+        }
+
+        match self.layout {
+            TextLayout::Unified => self.write_unified(out, diffs, columns, origins, header, &visible),
+            TextLayout::SideBySide => self.write_side_by_side(out, diffs, origins, header, &visible),
+        }
+    }
+
+    fn write_unified<W: Write>(
+        &self,
+        out: &mut W,
+        diffs: &[RowDiff],
+        columns: &[ColumnDiff],
+        origins: &SheetOrigins,
+        header: Option<usize>,
+        visible: &[bool],
+    ) -> Result<()> {
+        let mut lines: Vec<Option<Line>> = diffs
+            .iter()
+            .zip(visible)
+            .map(|(diff, &shown)| shown.then(|| unified_line(diff, origins)))
+            .collect();
+
+        // Mark added and removed columns in the header row
+        if let Some(header) = header.and_then(|header| lines[header].as_mut()) {
+            for (col_idx, column) in columns.iter().enumerate() {
+                let color = match column.change {
+                    ColumnChange::Added => Some(GREEN),
+                    ColumnChange::Removed => Some(DIM),
+                    _ => None,
+                };
+                if let Some(cell_color) = header.cell_colors.get_mut(col_idx) {
+                    *cell_color = cell_color.or(color);
+                }
+            }
+        }
+
+        let numbers = number_width(lines.iter().flatten());
+        let prefix = numbers + 4;
+        let widths = fit_widths(
+            &natural_widths(lines.iter().flatten().map(|line| &line.cells)),
+            self.width.saturating_sub(prefix + 1),
+        );
+        let table_width = table_width(prefix, &widths, lines.iter().flatten());
+
+        self.write_lines(out, &lines, |out, idx, line| {
+            writeln!(out, "{}", self.render_line(line, &widths, numbers))?;
+            if Some(idx) == header {
+                writeln!(out, "{}", self.paint(&rule(prefix, &widths, table_width), DIM))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn write_side_by_side<W: Write>(
+        &self,
+        out: &mut W,
+        diffs: &[RowDiff],
+        origins: &SheetOrigins,
+        header: Option<usize>,
+        visible: &[bool],
+    ) -> Result<()> {
+        let sides: Vec<Option<(Line, Line)>> = diffs
+            .iter()
+            .zip(visible)
+            .map(|(diff, &shown)| shown.then(|| side_by_side_lines(diff, origins)))
+            .collect();
+
+        let numbers = number_width(sides.iter().flatten().flat_map(|(left, right)| [left, right]));
+        let prefix = numbers + 4;
+        let all_cells = sides
+            .iter()
+            .flatten()
+            .flat_map(|(left, right)| [&left.cells, &right.cells]);
+        let half = self.width.saturating_sub(3) / 2;
+        let widths = fit_widths(&natural_widths(all_cells), half.saturating_sub(prefix + 1));
+        let side_width = table_width(
+            prefix,
+            &widths,
+            sides.iter().flatten().flat_map(|(left, right)| [left, right]),
+        );
+
+        self.write_lines(out, &sides, |out, idx, (left, right)| {
+            let left = self.render_line(left, &widths, numbers);
+            let right = self.render_line(right, &widths, numbers);
+            writeln!(out, "{} {} {}", pad_ansi(&left, side_width), self.paint("║", DIM), right)?;
+            if Some(idx) == header {
+                let rule = rule(prefix, &widths, side_width);
+                writeln!(out, "{}", self.paint(&format!("{}═╬═{}", rule, rule), DIM))?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Write the visible entries, collapsing runs of hidden rows into one line
+    fn write_lines<W: Write, T>(
+        &self,
+        out: &mut W,
+        entries: &[Option<T>],
+        mut write_entry: impl FnMut(&mut W, usize, &T) -> Result<()>,
+    ) -> Result<()> {
+        let mut hidden = 0;
+        for (idx, entry) in entries.iter().enumerate() {
+            match entry {
+                None => hidden += 1,
+                Some(entry) => {
+                    if hidden > 0 {
+                        writeln!(out, "{}", self.paint(&skipped(hidden), DIM))?;
+                        hidden = 0;
+                    }
+                    write_entry(out, idx, entry)?;
+                }
+            }
+        }
+        if hidden > 0 {
+            writeln!(out, "{}", self.paint(&skipped(hidden), DIM))?;
+        }
+        Ok(())
+    }
+
+    /// Render a line with its cells padded or truncated to the column widths
+    fn render_line(&self, line: &Line, widths: &[usize], number_width: usize) -> String {
+        let mut text = format!("{} {:>width$} │", line.marker, line.number, width = number_width);
+        if let Some(color) = line.color {
+            text = self.paint(&text, color);
+        }
+
+        for (col_idx, &width) in widths.iter().enumerate() {
+            let cell = line.cells.get(col_idx).map(|s| s.as_str()).unwrap_or_default();
+            let cell = pad(&truncate(cell, width), width);
+            let color = line.cell_colors.get(col_idx).copied().flatten().or(line.color);
+            text.push(' ');
+            text.push_str(&match color {
+                Some(color) => self.paint(&cell, color),
+                None => cell,
+            });
+            if col_idx + 1 < widths.len() {
+                text.push_str(&match line.color {
+                    Some(color) => self.paint(" │", color),
+                    None => " │".to_string(),
+                });
+            }
+        }
+
+        if widths.len() < line.cells.len() {
+            text.push_str(" …");
+        }
+        text.trim_end().to_string()
+    }
+
+    /// Wrap text in an ANSI color if colors are enabled
+    fn paint(&self, text: &str, color: &str) -> String {
+        if self.color && !text.is_empty() {
+            format!("{}{}{}", color, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+impl Default for TextDiffWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Build the unified line of a row diff
+///
+/// Rows show their number in both files, as `old→new` when they differ.
+fn unified_line(diff: &RowDiff, origins: &SheetOrigins) -> Line {
+    let mut cells: Vec<String> = diff.row_data.iter().map(cell_text).collect();
+    let mut cell_colors = ignored_colors(diff);
+
+    let (marker, color) = match diff.diff_type {
+        DiffType::Identical => (' ', None),
+        DiffType::Modified | DiffType::Moved => {
+            for &col_idx in &diff.modified_cells {
+                let (old, new) = modified_texts(diff, col_idx);
+                if col_idx < cells.len() {
+                    cells[col_idx] = format!("{} → {}", old, new);
+                    cell_colors[col_idx] = Some(modified_color(diff, col_idx));
+                }
+            }
+            match diff.diff_type {
+                DiffType::Moved => ('>', Some(CYAN)),
+                _ => ('~', None),
+            }
+        }
+        DiffType::Removed => ('-', Some(YELLOW)),
+        DiffType::Added => ('+', Some(ORANGE)),
+    };
+    // Ignored rows are not changes, so only their marker shows the side they are on
+    let color = color.filter(|_| !diff.ignored);
+
+    // Cells whose type changed show the old and the new type after the value
+    for &col_idx in &diff.type_changes {
+        if let (Some((old, new)), Some(cell)) = (diff.type_change(col_idx), cells.get_mut(col_idx)) {
+            *cell = format!("{} ({} → {})", cell_text(new), old.type_name(), new.type_name());
+            cell_colors[col_idx] = Some(BLUE);
+        }
+    }
+
+    Line {
+        marker,
+        number: origins.row_label(diff),
+        cells,
+        cell_colors,
+        color,
+    }
+}
+
+/// Build the old (left) and new (right) lines of a row diff
+fn side_by_side_lines(diff: &RowDiff, origins: &SheetOrigins) -> (Line, Line) {
+    let (old_row, new_row) = origins.rows(diff);
+    let values = |row: &[CellValue]| -> Vec<String> { row.iter().map(cell_text).collect() };
+    let empty = || Line {
+        marker: ' ',
+        number: String::new(),
+        cells: Vec::new(),
+        cell_colors: Vec::new(),
+        color: None,
+    };
+
+    match diff.diff_type {
+        DiffType::Identical => {
+            let mut cell_colors = ignored_colors(diff);
+            mark_type_changes(diff, &mut cell_colors);
+            let line = |number: Option<usize>, row: &[CellValue]| Line {
+                marker: ' ',
+                number: row_number(number),
+                cells: values(row),
+                cell_colors: cell_colors.clone(),
+                color: None,
+            };
+            let old_data = diff.original_row_data.as_deref().unwrap_or(&diff.row_data);
+            (line(old_row, old_data), line(new_row, &diff.row_data))
+        }
+        DiffType::Modified | DiffType::Moved => {
+            // Moved rows only have old values if their cells changed
+            let mut old_cells = values(diff.original_row_data.as_deref().unwrap_or(&diff.row_data));
+            let mut new_cells = values(&diff.row_data);
+            let mut cell_colors = ignored_colors(diff);
+            cell_colors.resize(new_cells.len().max(old_cells.len()), None);
+            for &col_idx in &diff.modified_cells {
+                if col_idx < cell_colors.len() {
+                    cell_colors[col_idx] = Some(modified_color(diff, col_idx));
+                }
+                let (old, new) = modified_texts(diff, col_idx);
+                if let Some(cell) = old_cells.get_mut(col_idx) {
+                    *cell = old;
+                }
+                if let Some(cell) = new_cells.get_mut(col_idx) {
+                    *cell = new;
+                }
+            }
+            mark_type_changes(diff, &mut cell_colors);
+            let (marker, color) = match diff.diff_type {
+                DiffType::Moved => ('>', Some(CYAN)),
+                _ => ('~', None),
+            };
+            (
+                Line {
+                    marker,
+                    number: row_number(old_row),
+                    cells: old_cells,
+                    cell_colors: cell_colors.clone(),
+                    color,
+                },
+                Line {
+                    marker,
+                    number: row_number(new_row),
+                    cells: new_cells,
+                    cell_colors,
+                    color,
+                },
+            )
+        }
+        DiffType::Removed => (
+            Line {
+                marker: '-',
+                number: row_number(old_row),
+                cells: values(&diff.row_data),
+                cell_colors: ignored_colors(diff),
+                color: Some(YELLOW).filter(|_| !diff.ignored),
+            },
+            empty(),
+        ),
+        DiffType::Added => (
+            empty(),
+            Line {
+                marker: '+',
+                number: row_number(new_row),
+                cells: values(&diff.row_data),
+                cell_colors: ignored_colors(diff),
+                color: Some(ORANGE).filter(|_| !diff.ignored),
+            },
+        ),
+    }
+}
+
+/// Old and new text of a modified cell: the formulas if only the formula changed, else the values
+fn modified_texts(diff: &RowDiff, col_idx: usize) -> (String, String) {
+    let old = diff
+        .original_row_data
+        .as_ref()
+        .and_then(|row| row.get(col_idx))
+        .map(cell_text)
+        .unwrap_or_default();
+    let new = diff.row_data.get(col_idx).map(cell_text).unwrap_or_default();
+    match (diff.formula_only_change(col_idx), diff.delta_text(col_idx)) {
+        (Some(change), _) => (
+            change.old_formula().unwrap_or(old),
+            change.new_formula().unwrap_or(new),
+        ),
+        (None, Some(delta)) => (old, format!("{} ({})", new, delta)),
+        (None, None) => (old, new),
+    }
+}
+
+/// Cell colors of a row with the cells that were left out of the comparison dimmed
+fn ignored_colors(diff: &RowDiff) -> Vec<Option<&'static str>> {
+    (0..diff.row_data.len())
+        .map(|col_idx| diff.is_ignored(col_idx).then_some(DIM))
+        .collect()
+}
+
+/// Color the cells whose only change is their type
+fn mark_type_changes(diff: &RowDiff, cell_colors: &mut [Option<&'static str>]) {
+    for &col_idx in &diff.type_changes {
+        if let Some(color) = cell_colors.get_mut(col_idx) {
+            *color = Some(BLUE);
+        }
+    }
+}
+
+/// Color of a modified cell: purple if only its formula changed, else red
+fn modified_color(diff: &RowDiff, col_idx: usize) -> &'static str {
+    if diff.formula_only_change(col_idx).is_some() {
+        PURPLE
+    } else {
+        RED
+    }
+}
+
+/// Display text of a cell value (dates in ISO format)
+fn cell_text(value: &CellValue) -> String {
+    // Keep each row on one line
+    value.formatted().replace(['\n', '\r', '\t'], " ")
+}
+
+/// 1-based number of a zero-based row, or nothing for a row that does not exist
+fn row_number(row: Option<usize>) -> String {
+    row.map(|row| (row + 1).to_string()).unwrap_or_default()
+}
+
+/// Width of the row number gutter
+fn number_width<'a>(lines: impl Iterator<Item = &'a Line>) -> usize {
+    lines.map(|line| line.number.chars().count()).max().unwrap_or(0).max(1)
+}
+
+/// Widest cell text per column over all rows
+fn natural_widths<'a>(rows: impl Iterator<Item = &'a Vec<String>>) -> Vec<usize> {
+    let mut widths: Vec<usize> = Vec::new();
+    for row in rows {
+        if widths.len() < row.len() {
+            widths.resize(row.len(), 0);
+        }
+        for (col_idx, cell) in row.iter().enumerate() {
+            widths[col_idx] = widths[col_idx].max(cell.width().min(MAX_COLUMN_WIDTH));
+        }
+    }
+    widths
+}
+
+/// Shrink column widths to fit the available width
+///
+/// The widest columns are narrowed first, down to [`MIN_COLUMN_WIDTH`]; if the
+/// columns still do not fit, the trailing columns are left out.
+fn fit_widths(natural: &[usize], available: usize) -> Vec<usize> {
+    let total = |widths: &[usize]| widths.iter().sum::<usize>() + 3 * widths.len().saturating_sub(1);
+
+    let mut widths: Vec<usize> = natural.iter().map(|&w| w.max(1)).collect();
+    while total(&widths) > available {
+        match widths.iter_mut().filter(|w| **w > MIN_COLUMN_WIDTH).max_by_key(|w| **w) {
+            Some(widest) => *widest -= 1,
+            None => break,
+        }
+    }
+    // Leave room for the "…" marking the left out columns
+    while widths.len() > 1 && total(&widths) + 2 > available {
+        widths.pop();
+    }
+    widths
+}
+
+/// Shorten text to a display width, ending in "…" if it was cut
+fn truncate(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let mut result = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        result.push(c);
+        used += w;
+    }
+    result.push('…');
+    result
+}
+
+/// Pad text with spaces to a display width
+fn pad(text: &str, width: usize) -> String {
+    format!("{}{}", text, " ".repeat(width.saturating_sub(text.width())))
+}
+
+/// Pad text that may contain ANSI color codes to a display width
+fn pad_ansi(text: &str, width: usize) -> String {
+    let mut visible = 0;
+    let mut in_escape = false;
+    for c in text.chars() {
+        if in_escape {
+            in_escape = c != 'm';
+        } else if c == '\x1b' {
+            in_escape = true;
+        } else {
+            visible += c.width().unwrap_or(0);
+        }
+    }
+    format!("{}{}", text, " ".repeat(width.saturating_sub(visible)))
+}
+
+/// Display width of a rendered table, including the "…" of left out columns
+fn table_width<'a>(prefix: usize, widths: &[usize], mut lines: impl Iterator<Item = &'a Line>) -> usize {
+    let width = prefix + 1 + widths.iter().sum::<usize>() + 3 * widths.len().saturating_sub(1);
+    if lines.any(|line| line.cells.len() > widths.len()) {
+        width + 2
+    } else {
+        width
+    }
+}
+
+/// Horizontal rule below the header row, crossing the column separators
+fn rule(prefix: usize, widths: &[usize], table_width: usize) -> String {
+    let mut rule = "─".repeat(prefix - 1);
+    rule.push('┼');
+    for (col_idx, &width) in widths.iter().enumerate() {
+        rule.push_str(&"─".repeat(width + 1));
+        if col_idx + 1 < widths.len() {
+            rule.push_str("─┼");
+        }
+    }
+    let length = rule.chars().count();
+    rule.push_str(&"─".repeat(table_width.saturating_sub(length)));
+    rule
+}
+
+/// Line standing in for a run of unchanged rows that are not shown
+fn skipped(count: usize) -> String {
+    if count == 1 {
+        "  ⋮ 1 unchanged row".to_string()
+    } else {
+        format!("  ⋮ {} unchanged rows", count)
+    }
+}
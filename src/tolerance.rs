@@ -0,0 +1,107 @@
+//! Numeric tolerance for treating nearly equal numbers as equal.
+
+use anyhow::{Context, Result};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::columns::ColumnSpec;
+
+/// Allowed difference between two numbers that are still considered equal
+///
+/// Two numbers are equal if their difference is at most the absolute
+/// tolerance, or at most the relative tolerance times the larger of the two
+/// magnitudes. The default tolerance is zero, so only equal numbers match.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Tolerance {
+    /// Maximum absolute difference (e.g. 0.01 for a cent)
+    pub absolute: f64,
+    /// Maximum difference relative to the larger magnitude (e.g. 0.001 for 0.1%)
+    pub relative: f64,
+}
+
+impl Tolerance {
+    pub fn new(absolute: f64, relative: f64) -> Self {
+        Tolerance { absolute, relative }
+    }
+
+    /// Whether no difference is tolerated
+    pub fn is_zero(&self) -> bool {
+        self.absolute == 0.0 && self.relative == 0.0
+    }
+
+    /// Whether two numbers are equal within the tolerance
+    pub fn allows(&self, a: f64, b: f64) -> bool {
+        if a == b {
+            return true;
+        }
+        // Rounded like normalized floats, so a difference of exactly the tolerance is allowed
+        let difference = ((a - b).abs() * 1e10).round() / 1e10;
+        difference <= self.absolute || difference <= self.relative * a.abs().max(b.abs())
+    }
+}
+
+impl FromStr for Tolerance {
+    type Err = anyhow::Error;
+
+    /// Parse an absolute tolerance (`0.01`) or a relative one in percent (`0.5%`)
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+        let (number, percent) = match s.strip_suffix('%') {
+            Some(number) => (number.trim(), true),
+            None => (s, false),
+        };
+        let value: f64 = number
+            .parse()
+            .with_context(|| format!("Invalid tolerance '{}' (expected a number or a percentage)", s))?;
+        if !value.is_finite() || value < 0.0 {
+            anyhow::bail!("Tolerance '{}' must be a non-negative number", s);
+        }
+
+        Ok(if percent {
+            Tolerance::new(0.0, value / 100.0)
+        } else {
+            Tolerance::new(value, 0.0)
+        })
+    }
+}
+
+impl fmt::Display for Tolerance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.relative == 0.0 {
+            write!(f, "±{}", self.absolute)
+        } else if self.absolute == 0.0 {
+            write!(f, "±{}%", self.relative * 100.0)
+        } else {
+            write!(f, "±{} or ±{}%", self.absolute, self.relative * 100.0)
+        }
+    }
+}
+
+/// Tolerance for one column, as given by the user (`Price=0.01` or `Rate=1%`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnTolerance {
+    /// The column the tolerance applies to
+    pub column: ColumnSpec,
+    /// Tolerance for the numbers in the column
+    pub tolerance: Tolerance,
+}
+
+impl FromStr for ColumnTolerance {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (column, tolerance) = s
+            .rsplit_once('=')
+            .with_context(|| format!("Invalid column tolerance '{}' (expected COLUMN=TOLERANCE)", s))?;
+        Ok(ColumnTolerance {
+            column: column.parse()?,
+            tolerance: tolerance.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for ColumnTolerance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.column, self.tolerance)
+    }
+}
@@ -0,0 +1,199 @@
+//! Whole-workbook comparison: pairing sheets by name and collecting their diffs.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::column_diff::ColumnDiff;
+use crate::columns::cell_address;
+use crate::differ::{CellPosition, DiffSummary, RowDiff};
+use crate::styles::SheetStyles;
+
+/// Presence of a sheet in the two compared workbooks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SheetStatus {
+    /// Sheet exists in both workbooks
+    Compared,
+    /// Sheet only exists in the first workbook
+    OnlyInFirst,
+    /// Sheet only exists in the second workbook
+    OnlyInSecond,
+}
+
+impl SheetStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SheetStatus::Compared => "compared",
+            SheetStatus::OnlyInFirst => "only in first file",
+            SheetStatus::OnlyInSecond => "only in second file",
+        }
+    }
+}
+
+/// Positions of the first compared cell of a sheet in the first and the second file
+///
+/// Rows and columns of a diff count from the start of the compared cell range.
+/// Adding the origin gives the address of a cell in its file, which is what the
+/// output shows. When whole sheets are compared, the origins are the first
+/// cell of each sheet's used range.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SheetOrigins {
+    /// Zero-based (row, column) of the first compared cell in the first file
+    pub old: CellPosition,
+    /// Zero-based (row, column) of the first compared cell in the second file
+    pub new: CellPosition,
+}
+
+impl SheetOrigins {
+    pub fn new(old: CellPosition, new: CellPosition) -> Self {
+        SheetOrigins { old, new }
+    }
+
+    /// Zero-based rows of a row diff in the first and the second file
+    pub fn rows(&self, diff: &RowDiff) -> (Option<usize>, Option<usize>) {
+        (
+            diff.old_row.map(|row| row + self.old.0),
+            diff.new_row.map(|row| row + self.new.0),
+        )
+    }
+
+    /// Zero-based column in its file of the cell shown at a column of a row diff
+    ///
+    /// # Arguments
+    /// * `diff` - Row diff showing the cell
+    /// * `columns` - Column diffs matching the column layout of the row data (empty if columns were not aligned)
+    /// * `col_idx` - Column of the cell in the row data
+    pub fn column(&self, diff: &RowDiff, columns: &[ColumnDiff], col_idx: usize) -> usize {
+        let (old, new) = diff.cell_positions(columns, col_idx);
+        let column = if diff.shows_old_cell(columns, col_idx) {
+            old.map(|(_, col)| col + self.old.1)
+        } else {
+            new.map(|(_, col)| col + self.new.1)
+        };
+        column.unwrap_or(col_idx + self.new.1)
+    }
+
+    /// A1 addresses of the first cell of a row diff in the first and the second file
+    pub fn addresses(&self, diff: &RowDiff) -> (Option<String>, Option<String>) {
+        let (old_row, new_row) = self.rows(diff);
+        (
+            old_row.map(|row| cell_address(row, self.old.1)),
+            new_row.map(|row| cell_address(row, self.new.1)),
+        )
+    }
+
+    /// Set the addresses of row diffs (see [`RowDiff::old_address`]) to their cells in the files
+    pub fn locate(&self, diffs: &mut [RowDiff]) {
+        for diff in diffs {
+            (diff.old_address, diff.new_address) = self.addresses(diff);
+        }
+    }
+
+    /// 1-based row numbers of a row diff in the files, as shown in the output
+    ///
+    /// A row at the same number in both files shows it once, a row at different
+    /// numbers as `old→new`, and a removed or added row its number in the file
+    /// it exists in.
+    pub fn row_label(&self, diff: &RowDiff) -> String {
+        match self.rows(diff) {
+            (Some(old), Some(new)) if old == new => (new + 1).to_string(),
+            (Some(old), Some(new)) => format!("{}→{}", old + 1, new + 1),
+            (Some(row), None) | (None, Some(row)) => (row + 1).to_string(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Diff result for one sheet of a workbook comparison
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetDiff {
+    /// Name of the sheet
+    pub name: String,
+    /// Whether the sheet exists in one or both workbooks
+    pub status: SheetStatus,
+    /// Row diffs of the sheet (all Removed or all Added for one-sided sheets)
+    pub diffs: Vec<RowDiff>,
+    /// Column diffs of the sheet (empty unless columns were aligned by header)
+    pub columns: Vec<ColumnDiff>,
+    /// Positions of the compared cell range in both files
+    #[serde(default)]
+    pub origins: SheetOrigins,
+    /// Cell styles of the sheet in the first workbook
+    #[serde(skip)]
+    pub old_styles: Option<Arc<SheetStyles>>,
+    /// Cell styles of the sheet in the second workbook
+    #[serde(skip)]
+    pub new_styles: Option<Arc<SheetStyles>>,
+}
+
+impl SheetDiff {
+    pub fn new(name: String, status: SheetStatus, diffs: Vec<RowDiff>) -> Self {
+        SheetDiff {
+            name,
+            status,
+            diffs,
+            columns: Vec::new(),
+            origins: SheetOrigins::default(),
+            old_styles: None,
+            new_styles: None,
+        }
+    }
+
+    /// Attach the column diffs of the sheet
+    pub fn with_columns(mut self, columns: Vec<ColumnDiff>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Set the positions of the compared cell range in both files, so the output shows cell addresses of the files
+    ///
+    /// Also sets the addresses of the row diffs to their cells in the files.
+    pub fn with_origins(mut self, old: CellPosition, new: CellPosition) -> Self {
+        self.origins = SheetOrigins::new(old, new);
+        self.origins.locate(&mut self.diffs);
+        self
+    }
+
+    /// Attach the cell styles of the sheet in both workbooks, so the Excel output can reproduce them
+    pub fn with_styles(mut self, old_styles: Option<Arc<SheetStyles>>, new_styles: Option<Arc<SheetStyles>>) -> Self {
+        self.old_styles = old_styles;
+        self.new_styles = new_styles;
+        self
+    }
+
+    /// Count the rows of this sheet per diff type
+    pub fn summary(&self) -> DiffSummary {
+        DiffSummary::from_diffs(&self.diffs)
+    }
+}
+
+/// Pair the sheets of two workbooks by name
+///
+/// Sheets are returned in the order of the first workbook, followed by the
+/// sheets that only exist in the second workbook in their original order.
+///
+/// # Arguments
+/// * `sheets1` - Sheet names of the first workbook
+/// * `sheets2` - Sheet names of the second workbook
+pub fn pair_sheets(sheets1: &[String], sheets2: &[String]) -> Vec<(String, SheetStatus)> {
+    let mut pairs: Vec<(String, SheetStatus)> = sheets1
+        .iter()
+        .map(|name| {
+            let status = if sheets2.contains(name) {
+                SheetStatus::Compared
+            } else {
+                SheetStatus::OnlyInFirst
+            };
+            (name.clone(), status)
+        })
+        .collect();
+
+    pairs.extend(
+        sheets2
+            .iter()
+            .filter(|name| !sheets1.contains(name))
+            .map(|name| (name.clone(), SheetStatus::OnlyInSecond)),
+    );
+
+    pairs
+}
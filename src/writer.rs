@@ -1,17 +1,117 @@
 //! Excel writer with color formatting for diffs.
 
 use anyhow::Result;
-use rust_xlsxwriter::{Color, Format, Workbook};
+use rust_xlsxwriter::{Color, Format, Formula, Note, Url, Workbook};
+use std::collections::HashSet;
+use std::str::FromStr;
 
-use crate::differ::{DiffType, RowDiff};
-use crate::reader::CellValue;
+use crate::column_diff::{ColumnChange, ColumnDiff};
+use crate::columns::column_letter;
+use crate::differ::{DiffSummary, DiffType, FormulaChange, RowDiff};
+use crate::reader::{CellValue, EXCEL_ERRORS};
+use crate::styles::{CellStyle, NumberFormat, SheetStyles};
+use crate::workbook::{SheetDiff, SheetOrigins, SheetStatus};
+
+/// Name of the overview tab in workbook comparisons
+const SUMMARY_SHEET_NAME: &str = "Summary";
+
+/// Font color of cells whose formula changed but whose value did not (purple)
+const FORMULA_COLOR: u32 = 0x7030A0;
+
+/// Font color of cells whose value is equal but whose type changed (blue)
+const TYPE_COLOR: u32 = 0x0070C0;
+
+/// Font color of cells that were left out of the comparison (grey)
+const IGNORED_COLOR: u32 = 0xA6A6A6;
+
+/// Fill color of moved rows and of the description of moved columns (light blue)
+const MOVED_COLOR: u32 = 0xDDEBF7;
+
+/// Font color of the row numbers of the compared files (dark grey)
+const ROW_NUMBER_COLOR: u32 = 0x808080;
+
+/// Title of the row number column, written in the header row
+const ROW_NUMBER_TITLE: &str = "Row (old/new)";
+
+/// Number format for dates that have no number format in the source workbook
+const DEFAULT_DATETIME_FORMAT: &str = "yyyy-mm-dd hh:mm:ss";
+
+/// How modified cells are written to the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ModifiedStyle {
+    /// Text "old → new" in red
+    #[default]
+    Arrow,
+    /// The new value with its original type in red, with the old value in a cell note
+    Note,
+}
+
+impl ModifiedStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModifiedStyle::Arrow => "arrow",
+            ModifiedStyle::Note => "note",
+        }
+    }
+}
+
+impl FromStr for ModifiedStyle {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "arrow" => Ok(ModifiedStyle::Arrow),
+            "note" => Ok(ModifiedStyle::Note),
+            _ => anyhow::bail!("Unknown modified cell style '{}' (expected arrow or note)", s),
+        }
+    }
+}
 
 /// Writer for creating Excel files with diff highlighting
-pub struct ExcelDiffWriter;
+#[derive(Debug, Clone)]
+pub struct ExcelDiffWriter {
+    modified_style: ModifiedStyle,
+    copy_styles: bool,
+    row_numbers: bool,
+}
 
 impl ExcelDiffWriter {
     pub fn new() -> Self {
-        ExcelDiffWriter
+        ExcelDiffWriter {
+            modified_style: ModifiedStyle::default(),
+            copy_styles: false,
+            row_numbers: true,
+        }
+    }
+
+    /// Set how modified cells are written
+    ///
+    /// With [`ModifiedStyle::Note`], modified cells keep their new value and
+    /// type (so formulas on the diff sheet still work) and get a note with
+    /// the column header and the old value and type.
+    pub fn with_modified_style(mut self, modified_style: ModifiedStyle) -> Self {
+        self.modified_style = modified_style;
+        self
+    }
+
+    /// Also copy the fonts, fill colors and column widths of the source cells
+    ///
+    /// Number formats of the source cells are always kept. Fonts and fills are
+    /// opt-in, because a source fill can look like diff highlighting; the diff
+    /// colors are applied on top of them.
+    pub fn with_copy_styles(mut self, copy_styles: bool) -> Self {
+        self.copy_styles = copy_styles;
+        self
+    }
+
+    /// Write the row numbers of each row in the compared files in a first column (enabled by default)
+    ///
+    /// The column shows one number for a row at the same position in both
+    /// files, `old→new` for a row at different positions and the row in its
+    /// file for a removed or added row, so a reviewer can find the source cells.
+    pub fn with_row_numbers(mut self, row_numbers: bool) -> Self {
+        self.row_numbers = row_numbers;
+        self
     }
 
     /// Write diff results to an Excel file with color highlighting
@@ -20,178 +120,589 @@ impl ExcelDiffWriter {
     /// * `diffs` - List of RowDiff objects
     /// * `output_path` - Path to write the output file
     /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
-    /// * `include_header` - If true, include the first row as header (only applies when diff_only=true)
+    /// * `include_header` - If true, include the header row above the changed rows (only applies when diff_only=true)
     ///
     /// # Color scheme
     /// - Identical rows: No coloring
-    /// - Modified rows: Red cells for changed values
+    /// - Modified rows: Red cells for changed values ("old → new", or the new
+    ///   value with a note, see [`with_modified_style`])
+    /// - Moved rows: Light blue background for entire row, with a note giving
+    ///   the old and new row; changed cells as in modified rows
     /// - Removed rows: Yellow background for entire row
     /// - Added rows: Orange background for entire row
+    /// - Cells whose formula changed but whose value did not: purple text
+    ///   (only when formulas are compared, see [`CompareMode`])
+    ///
+    /// [`with_modified_style`]: ExcelDiffWriter::with_modified_style
+    /// [`CompareMode`]: crate::differ::CompareMode
     pub fn write(
         &self,
         diffs: &[RowDiff],
         output_path: &str,
         diff_only: bool,
         include_header: bool,
+    ) -> Result<()> {
+        self.write_with_columns(diffs, &[], output_path, diff_only, include_header)
+    }
+
+    /// Write diff results with column-level changes to an Excel file
+    ///
+    /// Same as [`write`], but when columns were added, removed, renamed or
+    /// moved, an extra first row describes each column change, cells of
+    /// removed columns are struck through in grey and cells of added columns
+    /// are written in green.
+    ///
+    /// # Arguments
+    /// * `diffs` - List of RowDiff objects
+    /// * `columns` - Column diffs matching the column layout of the row data
+    /// * `output_path` - Path to write the output file
+    /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
+    /// * `include_header` - If true, include the header row above the changed rows (only applies when diff_only=true)
+    ///
+    /// [`write`]: ExcelDiffWriter::write
+    pub fn write_with_columns(
+        &self,
+        diffs: &[RowDiff],
+        columns: &[ColumnDiff],
+        output_path: &str,
+        diff_only: bool,
+        include_header: bool,
+    ) -> Result<()> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name("Diff")?;
+
+        self.write_sheet(worksheet, diffs, columns, SourceStyles::default(), diff_only, include_header)?;
+
+        workbook.save(output_path)?;
+        Ok(())
+    }
+
+    /// Write the diff of one sheet to an Excel file, reproducing the styles of the source cells
+    ///
+    /// Same as [`write_with_columns`], for a sheet diff with the cell styles of
+    /// both workbooks attached (see [`SheetDiff::with_styles`]). Each cell gets
+    /// the number format of the cell it was read from, with the diff colors on top.
+    ///
+    /// # Arguments
+    /// * `sheet` - Diff results of the sheet
+    /// * `output_path` - Path to write the output file
+    /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
+    /// * `include_header` - If true, include the header row above the changed rows (only applies when diff_only=true)
+    ///
+    /// [`write_with_columns`]: ExcelDiffWriter::write_with_columns
+    pub fn write_sheet_diff(
+        &self,
+        sheet: &SheetDiff,
+        output_path: &str,
+        diff_only: bool,
+        include_header: bool,
     ) -> Result<()> {
         let mut workbook = Workbook::new();
         let worksheet = workbook.add_worksheet();
         worksheet.set_name("Diff")?;
 
-        // Create formats for different diff types
-        let format_modified = Format::new().set_font_color(Color::Red);
+        let styles = SourceStyles::of(sheet);
+        self.write_sheet(worksheet, &sheet.diffs, &sheet.columns, styles, diff_only, include_header)?;
+
+        workbook.save(output_path)?;
+        Ok(())
+    }
+
+    /// Write row diffs to an Excel file as they are produced
+    ///
+    /// Same as [`write`], for diffs that do not fit in memory, such as those of
+    /// [`WorksheetDiffer::compare_iter`]. Rows are written out as they arrive,
+    /// using the constant memory mode of rust_xlsxwriter, and the summary of
+    /// the written diffs is returned. Columns keep their default width, as
+    /// written rows are no longer available to fit them. With `diff_only` and
+    /// `include_header`, the header row is written where it occurs in the diffs.
+    ///
+    /// # Arguments
+    /// * `diffs` - Row diffs in output order; writing stops at the first error, which is returned
+    /// * `origins` - Where the compared rows start in both files (see [`RowDiffs::with_origins`])
+    /// * `output_path` - Path to write the output file
+    /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
+    /// * `include_header` - If true, include the header row between the changed rows (only applies when diff_only=true)
+    ///
+    /// [`write`]: ExcelDiffWriter::write
+    /// [`WorksheetDiffer::compare_iter`]: crate::differ::WorksheetDiffer::compare_iter
+    /// [`RowDiffs::with_origins`]: crate::stream::RowDiffs::with_origins
+    pub fn write_stream(
+        &self,
+        diffs: impl IntoIterator<Item = Result<RowDiff>>,
+        origins: SheetOrigins,
+        output_path: &str,
+        diff_only: bool,
+        include_header: bool,
+    ) -> Result<DiffSummary> {
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet_with_constant_memory();
+        worksheet.set_name("Diff")?;
+
+        let styles = SourceStyles {
+            origins,
+            ..SourceStyles::default()
+        };
+        let mut summary = DiffSummary::default();
+        let mut header: Option<RowDiff> = None;
+        let (mut row_idx, mut header_idx, mut width) = (0u32, None, 0);
+        for diff in diffs {
+            let diff = diff?;
+            summary.add(&DiffSummary::from_diffs(std::slice::from_ref(&diff)));
+            width = width.max(diff.row_data.len());
+            if diff.header {
+                header = Some(diff.clone());
+            }
+            if diff_only && !diff.has_changes() && !(include_header && diff.header) {
+                continue;
+            }
+            if diff.header {
+                header_idx = Some(row_idx);
+            }
+            self.write_row(worksheet, row_idx, &diff, header.as_ref(), &[], &styles)?;
+            row_idx += 1;
+        }
+
+        // Keep the header row in view and filter the rows below it
+        if let (Some(header_idx), Some(last_col)) = (header_idx, width.checked_sub(1)) {
+            worksheet.set_freeze_panes(header_idx + 1, self.data_column(0))?;
+            worksheet.autofilter(header_idx, 0, row_idx - 1, self.data_column(last_col))?;
+        }
+
+        workbook.save(output_path)?;
+        Ok(summary)
+    }
+
+    /// Write the diff results of a whole workbook comparison to an Excel file
+    ///
+    /// The output starts with a "Summary" tab listing every sheet, whether it
+    /// exists in one or both files and its row counts per diff type, followed
+    /// by one diff tab per sheet using the same color scheme as [`write`].
+    ///
+    /// # Arguments
+    /// * `sheets` - Diff results per sheet
+    /// * `output_path` - Path to write the output file
+    /// * `diff_only` - If true, only write rows with differences (exclude identical rows)
+    /// * `include_header` - If true, include the header row above the changed rows (only applies when diff_only=true)
+    ///
+    /// [`write`]: ExcelDiffWriter::write
+    pub fn write_workbook(
+        &self,
+        sheets: &[SheetDiff],
+        output_path: &str,
+        diff_only: bool,
+        include_header: bool,
+    ) -> Result<()> {
+        let mut workbook = Workbook::new();
+
+        // Tab names must be unique, so reserve the summary name first
+        let mut used_names = HashSet::new();
+        used_names.insert(SUMMARY_SHEET_NAME.to_lowercase());
+        let tab_names: Vec<String> = sheets
+            .iter()
+            .map(|sheet| unique_tab_name(&sheet.name, &mut used_names))
+            .collect();
+
+        let summary = workbook.add_worksheet();
+        summary.set_name(SUMMARY_SHEET_NAME)?;
+        self.write_summary(summary, sheets, &tab_names)?;
+
+        for (sheet, tab_name) in sheets.iter().zip(&tab_names) {
+            let worksheet = workbook.add_worksheet();
+            worksheet.set_name(tab_name)?;
+            let styles = SourceStyles::of(sheet);
+            self.write_sheet(worksheet, &sheet.diffs, &sheet.columns, styles, diff_only, include_header)?;
+        }
+
+        workbook.save(output_path)?;
+        Ok(())
+    }
+
+    /// Write the summary tab of a workbook comparison
+    fn write_summary(
+        &self,
+        worksheet: &mut rust_xlsxwriter::Worksheet,
+        sheets: &[SheetDiff],
+        tab_names: &[String],
+    ) -> Result<()> {
+        let format_header = Format::new().set_bold();
         let format_removed = Format::new().set_background_color(Color::Yellow);
         let format_added = Format::new().set_background_color(Color::RGB(0xFFA500)); // Orange
 
-        // Filter diffs if needed
+        let headers = [
+            "Sheet",
+            "Status",
+            "Identical",
+            "Modified",
+            "Moved",
+            "Removed",
+            "Added",
+            "New errors",
+            "Type changes",
+            "Ignored",
+        ];
+        for (col_idx, header) in headers.iter().enumerate() {
+            worksheet.write_string_with_format(0, col_idx as u16, *header, &format_header)?;
+        }
+
+        let mut total = DiffSummary::default();
+        let mut row_idx = 1u32;
+        for (sheet, tab_name) in sheets.iter().zip(tab_names) {
+            let counts = sheet.summary();
+            let link = Url::new(format!("internal:'{}'!A1", tab_name.replace('\'', "''")));
+            worksheet.write_url_with_text(row_idx, 0, link, &sheet.name)?;
+
+            match sheet.status {
+                SheetStatus::Compared => {
+                    worksheet.write_string(row_idx, 1, sheet.status.as_str())?;
+                }
+                SheetStatus::OnlyInFirst => {
+                    worksheet.write_string_with_format(row_idx, 1, sheet.status.as_str(), &format_removed)?;
+                }
+                SheetStatus::OnlyInSecond => {
+                    worksheet.write_string_with_format(row_idx, 1, sheet.status.as_str(), &format_added)?;
+                }
+            }
+
+            let values = [
+                counts.identical,
+                counts.modified,
+                counts.moved,
+                counts.removed,
+                counts.added,
+                counts.new_errors,
+                counts.type_changes,
+                counts.ignored,
+            ];
+            for (offset, value) in values.iter().enumerate() {
+                worksheet.write_number(row_idx, 2 + offset as u16, *value as f64)?;
+            }
+
+            total.add(&counts);
+            row_idx += 1;
+        }
+
+        worksheet.write_string_with_format(row_idx, 0, "Total", &format_header)?;
+        let values = [
+            total.identical,
+            total.modified,
+            total.moved,
+            total.removed,
+            total.added,
+            total.new_errors,
+            total.type_changes,
+            total.ignored,
+        ];
+        for (offset, value) in values.iter().enumerate() {
+            worksheet.write_number_with_format(row_idx, 2 + offset as u16, *value as f64, &format_header)?;
+        }
+
+        worksheet.autofit();
+        Ok(())
+    }
+
+    /// Write the rows of one diff to a worksheet with color highlighting
+    fn write_sheet(
+        &self,
+        worksheet: &mut rust_xlsxwriter::Worksheet,
+        diffs: &[RowDiff],
+        columns: &[ColumnDiff],
+        styles: SourceStyles,
+        diff_only: bool,
+        include_header: bool,
+    ) -> Result<()> {
+        // The header row is written once; in diff_only mode above the changed rows
+        let header = diffs.iter().find(|diff| diff.header);
         let diffs_to_write: Vec<&RowDiff> = if diff_only {
             diffs
                 .iter()
-                .filter(|d| d.diff_type != DiffType::Identical)
+                .filter(|d| d.has_changes() && !(include_header && d.header))
                 .collect()
         } else {
             diffs.iter().collect()
         };
 
-        // Include header row if requested
         let mut row_idx = 0u32;
-        if include_header && !diffs.is_empty() {
-            let header_row = &diffs[0];
-            for (col_idx, value) in header_row.row_data.iter().enumerate() {
-                self.write_cell(worksheet, row_idx, col_idx as u16, value, None)?;
-            }
+
+        // Describe column changes above the data
+        if columns.iter().any(|c| c.change != ColumnChange::Unchanged) {
+            self.write_column_changes(worksheet, row_idx, columns)?;
+            row_idx += 1;
+        }
+
+        // Include header row if requested
+        let mut header_idx = None;
+        if let (true, Some(header_row)) = (diff_only && include_header, header) {
+            self.write_row(worksheet, row_idx, header_row, header, columns, &styles)?;
+            header_idx = Some(row_idx);
             row_idx += 1;
         }
 
         // Write all rows with appropriate formatting
         for diff in diffs_to_write {
-            match diff.diff_type {
-                DiffType::Identical => {
-                    // No coloring for identical rows
-                    for (col_idx, value) in diff.row_data.iter().enumerate() {
-                        self.write_cell(worksheet, row_idx, col_idx as u16, value, None)?;
-                    }
+            if diff.header {
+                header_idx = Some(row_idx);
+            }
+            self.write_row(worksheet, row_idx, diff, header, columns, &styles)?;
+            row_idx += 1;
+        }
+
+        // Keep the header row in view and filter the rows below it
+        let width = diffs.iter().map(|d| d.row_data.len()).max().unwrap_or(0);
+        if let (Some(header_idx), Some(last_col)) = (header_idx, width.checked_sub(1)) {
+            worksheet.set_freeze_panes(header_idx + 1, self.data_column(0))?;
+            worksheet.autofilter(header_idx, 0, row_idx - 1, self.data_column(last_col))?;
+        }
+
+        // Auto-adjust column widths, keeping the custom widths of the source columns
+        worksheet.autofit();
+        if self.copy_styles {
+            for col_idx in 0..width {
+                if let Some(column_width) = styles.column_width(columns, col_idx) {
+                    worksheet.set_column_width(self.data_column(col_idx), column_width)?;
                 }
-                DiffType::Modified => {
-                    // For modified cells, show both old and new values
-                    for (col_idx, value) in diff.row_data.iter().enumerate() {
-                        if diff.modified_cells.contains(&col_idx) {
-                            if let Some(ref original_row) = diff.original_row_data {
-                                let old_value = original_row.get(col_idx).unwrap_or(&CellValue::Empty);
-                                let old_str = old_value.to_string();
-                                let new_str = value.to_string();
-                                let combined = format!("{} → {}", old_str, new_str);
-
-                                // Write with red font
-                                worksheet.write_string_with_format(
-                                    row_idx,
-                                    col_idx as u16,
-                                    &combined,
-                                    &format_modified,
-                                )?;
-
-                                // Note: Comments would be added here with worksheet.insert_note()
-                                // but it requires a Note object which is more complex
-                            } else {
-                                self.write_cell(worksheet, row_idx, col_idx as u16, value, None)?;
-                            }
-                        } else {
-                            // Cell not modified, just write the value
-                            self.write_cell(worksheet, row_idx, col_idx as u16, value, None)?;
-                        }
-                    }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write one row diff with color highlighting
+    ///
+    /// # Arguments
+    /// * `row_idx` - Row of the output worksheet
+    /// * `diff` - Row diff to write
+    /// * `header` - Header row of the diff, whose names are used in notes
+    fn write_row(
+        &self,
+        worksheet: &mut rust_xlsxwriter::Worksheet,
+        row_idx: u32,
+        diff: &RowDiff,
+        header: Option<&RowDiff>,
+        columns: &[ColumnDiff],
+        styles: &SourceStyles,
+    ) -> Result<()> {
+        // Color entire removed rows yellow and added rows orange; identical and ignored rows are not colored
+        let row_color = match diff.diff_type {
+            _ if diff.ignored => None,
+            DiffType::Removed => Some(Color::Yellow),
+            DiffType::Added => Some(Color::RGB(0xFFA500)), // Orange
+            DiffType::Moved => Some(Color::RGB(MOVED_COLOR)),
+            DiffType::Identical | DiffType::Modified => None,
+        };
+        if self.row_numbers {
+            self.write_row_number(worksheet, row_idx, diff, row_color, styles.origins)?;
+        }
+
+        for (col_idx, value) in diff.row_data.iter().enumerate() {
+            // Diff highlighting is layered on top of the style of the source cell
+            let mut format = self.cell_format(styles.cell_style(diff, columns, col_idx), value);
+            if let Some(color) = row_color {
+                format = format.set_background_color(color);
+            }
+            let mut format = self.column_format(columns, col_idx, format);
+            if diff.is_ignored(col_idx) {
+                format = format.set_font_color(Color::RGB(IGNORED_COLOR));
+            }
+            let header = header.and_then(|header_row| header_row.row_data.get(col_idx));
+
+            let original_row = diff
+                .original_row_data
+                .as_ref()
+                .filter(|_| diff.modified_cells.contains(&col_idx));
+            let Some(original_row) = original_row else {
+                // Cells whose only change is their type are blue, with the types in a note
+                let Some((old_value, _)) = diff.type_change(col_idx) else {
+                    self.write_cell(worksheet, row_idx, self.data_column(col_idx), value, &format)?;
+                    continue;
+                };
+                let format = format.set_font_color(Color::RGB(TYPE_COLOR));
+                self.write_cell(worksheet, row_idx, self.data_column(col_idx), value, &format)?;
+                if self.modified_style == ModifiedStyle::Note {
+                    let column = styles.origins.column(diff, columns, col_idx);
+                    let note = change_note(column, header, old_value, value, None, None);
+                    worksheet.insert_note(row_idx, self.data_column(col_idx), &note)?;
                 }
-                DiffType::Removed => {
-                    // Color entire row yellow
-                    for (col_idx, value) in diff.row_data.iter().enumerate() {
-                        self.write_cell(
-                            worksheet,
-                            row_idx,
-                            col_idx as u16,
-                            value,
-                            Some(&format_removed),
-                        )?;
-                    }
+                continue;
+            };
+
+            // For modified cells, show both old and new values in red, or
+            // in purple when only the formula changed
+            let old_value = original_row.get(col_idx).unwrap_or(&CellValue::Empty);
+            let formula_only = diff.formula_only_change(col_idx);
+            let color = if formula_only.is_some() { Color::RGB(FORMULA_COLOR) } else { Color::Red };
+            let format = format.set_font_color(color);
+            match self.modified_style {
+                ModifiedStyle::Arrow => {
+                    let combined = match formula_only {
+                        Some(change) => format!(
+                            "{} → {}",
+                            change.old_formula().unwrap_or_else(|| old_value.to_string()),
+                            change.new_formula().unwrap_or_else(|| value.to_string())
+                        ),
+                        None => match diff.delta_text(col_idx) {
+                            Some(delta) => format!("{} → {} ({})", old_value, value, delta),
+                            None => format!("{} → {}", old_value, value),
+                        },
+                    };
+                    worksheet.write_string_with_format(row_idx, self.data_column(col_idx), &combined, &format)?;
                 }
-                DiffType::Added => {
-                    // Color entire row orange
-                    for (col_idx, value) in diff.row_data.iter().enumerate() {
-                        self.write_cell(
-                            worksheet,
-                            row_idx,
-                            col_idx as u16,
-                            value,
-                            Some(&format_added),
-                        )?;
-                    }
+                ModifiedStyle::Note => {
+                    // Keep the typed new value, with the old value in a note
+                    self.write_cell(worksheet, row_idx, self.data_column(col_idx), value, &format)?;
+                    let note = change_note(
+                        styles.origins.column(diff, columns, col_idx),
+                        header,
+                        old_value,
+                        value,
+                        diff.delta_text(col_idx),
+                        diff.formula_change(col_idx),
+                    );
+                    worksheet.insert_note(row_idx, self.data_column(col_idx), &note)?;
                 }
             }
-            row_idx += 1;
         }
 
-        // Auto-adjust column widths
-        worksheet.autofit();
+        // Moved rows get a note on their first cell, unless it holds the note of a changed value
+        if diff.diff_type == DiffType::Moved {
+            let first_cell_noted = !self.row_numbers
+                && self.modified_style == ModifiedStyle::Note
+                && (diff.modified_cells.contains(&0) || diff.type_changes.contains(&0));
+            if let (false, (Some(old_row), Some(new_row))) = (first_cell_noted, styles.origins.rows(diff)) {
+                worksheet.insert_note(row_idx, 0, &move_note(old_row, new_row))?;
+            }
+        }
+        Ok(())
+    }
 
-        workbook.save(output_path)?;
+    /// Write the row numbers of a row diff in the compared files, or the column title for the header row
+    fn write_row_number(
+        &self,
+        worksheet: &mut rust_xlsxwriter::Worksheet,
+        row_idx: u32,
+        diff: &RowDiff,
+        row_color: Option<Color>,
+        origins: SheetOrigins,
+    ) -> Result<()> {
+        let mut format = Format::new().set_font_color(Color::RGB(ROW_NUMBER_COLOR));
+        if let Some(color) = row_color {
+            format = format.set_background_color(color);
+        }
+        if diff.header {
+            worksheet.write_string_with_format(row_idx, 0, ROW_NUMBER_TITLE, &format.set_bold())?;
+            return Ok(());
+        }
+
+        // Single row numbers are written as numbers, so the column sorts in row order
+        let label = origins.row_label(diff);
+        match label.parse::<f64>() {
+            Ok(number) => worksheet.write_number_with_format(row_idx, 0, number, &format)?,
+            Err(_) => worksheet.write_string_with_format(row_idx, 0, &label, &format)?,
+        };
+        Ok(())
+    }
+
+    /// Output column of a column of the row data, after the row number column if it is written
+    fn data_column(&self, col_idx: usize) -> u16 {
+        (col_idx + usize::from(self.row_numbers)) as u16
+    }
+
+    /// Write a row describing the change of every column
+    fn write_column_changes(
+        &self,
+        worksheet: &mut rust_xlsxwriter::Worksheet,
+        row_idx: u32,
+        columns: &[ColumnDiff],
+    ) -> Result<()> {
+        for (col_idx, column) in columns.iter().enumerate() {
+            let color = match column.change {
+                ColumnChange::Unchanged => continue,
+                ColumnChange::Added => 0xC6EFCE,   // Light green
+                ColumnChange::Removed => 0xD9D9D9, // Grey
+                ColumnChange::Renamed => 0xE4DFEC, // Light purple
+                ColumnChange::Moved => MOVED_COLOR,
+            };
+            let format = Format::new()
+                .set_bold()
+                .set_italic()
+                .set_background_color(Color::RGB(color));
+            worksheet.write_string_with_format(row_idx, self.data_column(col_idx), column.describe(), &format)?;
+        }
         Ok(())
     }
 
-    /// Helper function to write a cell value with optional format
+    /// Format reproducing a source cell: its number format and, when styles are copied, its font and fill
+    fn cell_format(&self, style: Option<&CellStyle>, value: &CellValue) -> Format {
+        let mut format = Format::new();
+        match style.and_then(|style| style.number_format.as_ref()) {
+            Some(NumberFormat::BuiltIn(index)) => format = format.set_num_format_index(*index),
+            Some(NumberFormat::Custom(code)) => format = format.set_num_format(code),
+            // Dates are numbers in Excel, so they need a date format to be shown as dates
+            None if matches!(value, CellValue::DateTime(_)) => format = format.set_num_format(DEFAULT_DATETIME_FORMAT),
+            None => {}
+        }
+
+        if let Some(style) = style.filter(|_| self.copy_styles) {
+            if style.bold {
+                format = format.set_bold();
+            }
+            if style.italic {
+                format = format.set_italic();
+            }
+            if let Some(color) = style.font_color {
+                format = format.set_font_color(Color::RGB(color));
+            }
+            if let Some(color) = style.fill_color {
+                format = format.set_background_color(Color::RGB(color));
+            }
+        }
+        format
+    }
+
+    /// Format for a cell in an added or removed column, layered on top of the row format
+    fn column_format(&self, columns: &[ColumnDiff], col_idx: usize, base: Format) -> Format {
+        match columns.get(col_idx).map(|column| column.change) {
+            Some(ColumnChange::Removed) => base
+                .set_font_color(Color::RGB(0x808080))
+                .set_font_strikethrough(),
+            Some(ColumnChange::Added) => base.set_font_color(Color::RGB(0x008000)),
+            _ => base,
+        }
+    }
+
+    /// Helper function to write a cell value with its format
     fn write_cell(
         &self,
         worksheet: &mut rust_xlsxwriter::Worksheet,
         row: u32,
         col: u16,
         value: &CellValue,
-        format: Option<&Format>,
+        format: &Format,
     ) -> Result<()> {
         match value {
             CellValue::String(s) => {
-                if let Some(fmt) = format {
-                    worksheet.write_string_with_format(row, col, s, fmt)?;
-                } else {
-                    worksheet.write_string(row, col, s)?;
-                }
+                worksheet.write_string_with_format(row, col, s, format)?;
             }
             CellValue::Int(i) => {
-                if let Some(fmt) = format {
-                    worksheet.write_number_with_format(row, col, *i as f64, fmt)?;
-                } else {
-                    worksheet.write_number(row, col, *i as f64)?;
-                }
+                worksheet.write_number_with_format(row, col, *i as f64, format)?;
             }
-            CellValue::Float(f) => {
-                if let Some(fmt) = format {
-                    worksheet.write_number_with_format(row, col, *f, fmt)?;
-                } else {
-                    worksheet.write_number(row, col, *f)?;
-                }
-            }
-            CellValue::DateTime(dt) => {
-                // Create a datetime format - use Excel's built-in datetime format
-                let datetime_format = if let Some(fmt) = format {
-                    // Clone and add datetime number format
-                    fmt.clone().set_num_format("yyyy-mm-dd hh:mm:ss")
-                } else {
-                    // Create new format with datetime number format
-                    Format::new().set_num_format("yyyy-mm-dd hh:mm:ss")
-                };
-
-                worksheet.write_number_with_format(row, col, *dt, &datetime_format)?;
+            CellValue::Float(f) | CellValue::DateTime(f) => {
+                worksheet.write_number_with_format(row, col, *f, format)?;
             }
             CellValue::Bool(b) => {
-                if let Some(fmt) = format {
-                    worksheet.write_boolean_with_format(row, col, *b, fmt)?;
-                } else {
-                    worksheet.write_boolean(row, col, *b)?;
-                }
+                worksheet.write_boolean_with_format(row, col, *b, format)?;
+            }
+            CellValue::Error(e) if EXCEL_ERRORS.contains(&e.as_str()) => {
+                // An error literal as formula makes Excel show a real error value
+                let formula = Formula::new(format!("={}", e)).set_result(e);
+                worksheet.write_formula_with_format(row, col, formula, format)?;
+            }
+            CellValue::Error(e) => {
+                worksheet.write_string_with_format(row, col, e, format)?;
             }
             CellValue::Empty => {
-                if let Some(fmt) = format {
-                    worksheet.write_string_with_format(row, col, "", fmt)?;
-                } else {
-                    worksheet.write_string(row, col, "")?;
-                }
+                worksheet.write_string_with_format(row, col, "", format)?;
             }
         }
         Ok(())
@@ -203,3 +714,125 @@ impl Default for ExcelDiffWriter {
         Self::new()
     }
 }
+
+/// Cell styles of the two compared sheets, and where the compared cells are in them
+#[derive(Debug, Clone, Copy, Default)]
+struct SourceStyles<'a> {
+    old: Option<&'a SheetStyles>,
+    new: Option<&'a SheetStyles>,
+    origins: SheetOrigins,
+}
+
+impl<'a> SourceStyles<'a> {
+    fn of(sheet: &'a SheetDiff) -> Self {
+        SourceStyles {
+            old: sheet.old_styles.as_deref(),
+            new: sheet.new_styles.as_deref(),
+            origins: sheet.origins,
+        }
+    }
+
+    /// Style of the source cell shown in a column of a diff row
+    fn cell_style(&self, diff: &RowDiff, columns: &[ColumnDiff], col_idx: usize) -> Option<&'a CellStyle> {
+        let (old_pos, new_pos) = diff.cell_positions(columns, col_idx);
+        let (styles, (row, col)) = if diff.shows_old_cell(columns, col_idx) {
+            (self.old?, old_pos?)
+        } else {
+            (self.new?, new_pos?)
+        };
+        styles.style(row, col)
+    }
+
+    /// Custom width of the source column shown in a column of the output
+    fn column_width(&self, columns: &[ColumnDiff], col_idx: usize) -> Option<f64> {
+        let column = columns.get(col_idx);
+        let new_width = column
+            .map_or(Some(col_idx), |c| c.new_index)
+            .and_then(|source_col| self.new?.column_width(source_col));
+        new_width.or_else(|| {
+            column
+                .map_or(Some(col_idx), |c| c.old_index)
+                .and_then(|source_col| self.old?.column_width(source_col))
+        })
+    }
+}
+
+/// Note describing a modified cell: its column, its old and new value and type, and its formulas if they changed
+///
+/// `column` is the column of the cell in its file, which differs from its
+/// column in the output when only a cell range was compared.
+fn change_note(
+    column: usize,
+    header: Option<&CellValue>,
+    old_value: &CellValue,
+    new_value: &CellValue,
+    delta: Option<String>,
+    formula_change: Option<&FormulaChange>,
+) -> Note {
+    let column = match header.map(|h| h.to_string()).filter(|h| !h.trim().is_empty()) {
+        Some(header) => format!("{} (column {})", header.trim(), column_letter(column)),
+        None => format!("Column {}", column_letter(column)),
+    };
+    let describe = |value: &CellValue| match value {
+        CellValue::Empty => "(empty)".to_string(),
+        _ => format!("{} ({})", value.formatted(), value.type_name()),
+    };
+    let mut text = format!(
+        "{}\nOld: {}\nNew: {}",
+        column,
+        describe(old_value),
+        describe(new_value)
+    );
+    if let Some(delta) = delta {
+        text.push_str(&format!("\nDelta: {}", delta));
+    }
+    if let Some(change) = formula_change {
+        let none = || "(none)".to_string();
+        text.push_str(&format!(
+            "\nOld formula: {}\nNew formula: {}",
+            change.old_formula().unwrap_or_else(none),
+            change.new_formula().unwrap_or_else(none)
+        ));
+    }
+
+    Note::new(text)
+        .add_author_prefix(false)
+        .set_author("exceldiff")
+        .set_width(220)
+}
+
+/// Note on a moved row giving its 0-based rows in the first and the second file as row numbers
+fn move_note(old_row: usize, new_row: usize) -> Note {
+    Note::new(format!("Moved from row {} to row {}", old_row + 1, new_row + 1))
+        .add_author_prefix(false)
+        .set_author("exceldiff")
+}
+
+/// Turn a sheet name into a valid, unique worksheet tab name
+///
+/// Excel limits tab names to 31 characters, forbids `[]:*?/\` and compares
+/// names case-insensitively.
+fn unique_tab_name(name: &str, used_names: &mut HashSet<String>) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if "[]:*?/\\".contains(c) { '_' } else { c })
+        .collect();
+    let cleaned = cleaned.trim_matches('\'');
+    let base: String = if cleaned.is_empty() {
+        "Sheet".to_string()
+    } else {
+        cleaned.chars().take(31).collect()
+    };
+
+    let mut candidate = base.clone();
+    let mut counter = 2;
+    while used_names.contains(&candidate.to_lowercase()) {
+        let suffix = format!(" ({})", counter);
+        let prefix: String = base.chars().take(31 - suffix.chars().count()).collect();
+        candidate = format!("{}{}", prefix, suffix);
+        counter += 1;
+    }
+
+    used_names.insert(candidate.to_lowercase());
+    candidate
+}
@@ -0,0 +1,90 @@
+//! Order-preserving row alignment with the Myers and patience algorithms.
+
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{AlignMode, DiffType, RowDiff, WorksheetDiffer};
+
+fn row(values: &[&str]) -> Row {
+    values.iter().map(|v| CellValue::String(v.to_string())).collect()
+}
+
+/// (type, old row, new row) of each diff, in result order
+fn layout(diffs: &[RowDiff]) -> Vec<(DiffType, Option<usize>, Option<usize>)> {
+    diffs.iter().map(|d| (d.diff_type, d.old_row, d.new_row)).collect()
+}
+
+fn sequence_differs() -> Vec<WorksheetDiffer> {
+    vec![
+        WorksheetDiffer::new().with_align_mode(AlignMode::Myers),
+        WorksheetDiffer::new().with_align_mode(AlignMode::Patience),
+    ]
+}
+
+#[test]
+fn added_rows_appear_where_they_were_inserted() {
+    let sheet1 = vec![row(&["Ann", "1"]), row(&["Bob", "2"]), row(&["Cid", "3"])];
+    let sheet2 = vec![
+        row(&["Ann", "1"]),
+        row(&["New", "9"]),
+        row(&["Bob", "2"]),
+        row(&["Cid", "3"]),
+        row(&["Dee", "4"]),
+    ];
+
+    for differ in sequence_differs() {
+        let diffs = differ.compare(&sheet1, &sheet2);
+        assert_eq!(
+            layout(&diffs),
+            vec![
+                (DiffType::Identical, Some(0), Some(0)),
+                (DiffType::Added, None, Some(1)),
+                (DiffType::Identical, Some(1), Some(2)),
+                (DiffType::Identical, Some(2), Some(3)),
+                (DiffType::Added, None, Some(4)),
+            ]
+        );
+        // Rows are numbered by their position in the result
+        assert!(diffs.iter().enumerate().all(|(idx, d)| d.row_index == idx));
+    }
+
+    // Matching by content lists added rows last
+    let diffs = WorksheetDiffer::new().compare(&sheet1, &sheet2);
+    assert!(diffs[..3].iter().all(|d| d.diff_type == DiffType::Identical));
+    assert!(diffs[3..].iter().all(|d| d.diff_type == DiffType::Added));
+}
+
+#[test]
+fn changed_rows_are_paired_within_their_hunk() {
+    let sheet1 = vec![
+        row(&["Ann", "Ghent", "1"]),
+        row(&["Bob", "Ypres", "2"]),
+        row(&["Old", "Lier", "3"]),
+        row(&["Eve", "Hal", "5"]),
+    ];
+    let sheet2 = vec![
+        row(&["Ann", "Ghent", "1"]),
+        row(&["Bob", "Ypres", "7"]),
+        row(&["Eve", "Hal", "5"]),
+    ];
+
+    for differ in sequence_differs() {
+        let diffs = differ.compare(&sheet1, &sheet2);
+        assert_eq!(
+            layout(&diffs),
+            vec![
+                (DiffType::Identical, Some(0), Some(0)),
+                (DiffType::Modified, Some(1), Some(1)),
+                (DiffType::Removed, Some(2), None),
+                (DiffType::Identical, Some(3), Some(2)),
+            ]
+        );
+        assert_eq!(diffs[1].modified_cells, vec![2]);
+    }
+}
+
+#[test]
+fn align_modes_are_parsed_by_name() {
+    assert_eq!("myers".parse::<AlignMode>().unwrap(), AlignMode::Myers);
+    assert_eq!("Patience".parse::<AlignMode>().unwrap(), AlignMode::Patience);
+    assert_eq!("similarity".parse::<AlignMode>().unwrap(), AlignMode::Similarity);
+    assert!("lcs".parse::<AlignMode>().is_err());
+}
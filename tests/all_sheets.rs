@@ -0,0 +1,80 @@
+//! Whole-workbook comparison with column options that only apply to some sheets.
+
+use std::path::PathBuf;
+use std::process::Command;
+
+use rust_xlsxwriter::Workbook;
+use serde_json::Value;
+
+/// Write a workbook with one sheet per (name, rows) entry, starting at A1
+fn write_workbook(name: &str, sheets: &[(&str, &[&[&str]])]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("exceldiff-all-{}-{}.xlsx", std::process::id(), name));
+    let mut workbook = Workbook::new();
+    for (sheet_name, rows) in sheets {
+        let worksheet = workbook.add_worksheet().set_name(*sheet_name).unwrap();
+        for (row_idx, row) in rows.iter().enumerate() {
+            for (col_idx, value) in row.iter().enumerate() {
+                worksheet.write_string(row_idx as u32, col_idx as u16, *value).unwrap();
+            }
+        }
+    }
+    workbook.save(&path).unwrap();
+    path
+}
+
+fn row_types(document: &Value, sheet: &str) -> Vec<String> {
+    let sheet = document["sheets"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|entry| entry["name"] == sheet)
+        .unwrap();
+    sheet["rows"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| row["type"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn missing_columns_leave_out_options_for_that_sheet_only() {
+    let path1 = write_workbook(
+        "old",
+        &[
+            ("Data", &[&["ID", "Name", "Updated"], &["1", "Ann", "Monday"]]),
+            ("Notes", &[&["ID", "Text"], &["1", "Draft"]]),
+        ],
+    );
+    let path2 = write_workbook(
+        "new",
+        &[
+            ("Data", &[&["ID", "Name", "Updated"], &["1", "Ann", "Tuesday"]]),
+            ("Notes", &[&["ID", "Text"], &["1", "Final"]]),
+        ],
+    );
+    let output = std::env::temp_dir().join(format!("exceldiff-all-{}.json", std::process::id()));
+
+    let result = Command::new(env!("CARGO_BIN_EXE_exceldiff"))
+        .args([path1.to_str().unwrap(), path2.to_str().unwrap(), "--all-sheets", "--header-row", "1"])
+        .args(["--ignore-columns", "Updated", "--ignore-rows", "Text=^Draft$", "--key", "ID"])
+        .args(["--output", output.to_str().unwrap()])
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(result.status.success(), "{}", String::from_utf8_lossy(&result.stderr));
+
+    // Each sheet warns about the option whose column it lacks and keeps the others
+    assert!(stdout.contains("Warning: sheet 'Notes': Failed to resolve ignored columns"));
+    assert!(stdout.contains("Warning: sheet 'Data': Failed to resolve the column of ignored rows"));
+
+    let document: Value = serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+    assert_eq!(row_types(&document, "Data"), vec!["identical", "identical"]);
+    // The draft row is ignored, so its change is not reported
+    assert_eq!(row_types(&document, "Notes"), vec!["identical", "identical"]);
+    assert_eq!(document["sheets"][1]["summary"]["ignored"], 1);
+
+    for path in [path1, path2, output] {
+        let _ = std::fs::remove_file(path);
+    }
+}
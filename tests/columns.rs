@@ -0,0 +1,74 @@
+//! Column alignment by header name.
+
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{ColumnAlignment, ColumnChange, DiffType, WorksheetDiffer};
+
+fn row(values: &[&str]) -> Row {
+    values.iter().map(|v| CellValue::String(v.to_string())).collect()
+}
+
+/// (change, old index, new index) of each column of the combined layout
+fn changes(alignment: &ColumnAlignment) -> Vec<(ColumnChange, Option<usize>, Option<usize>)> {
+    alignment
+        .columns
+        .iter()
+        .map(|c| (c.change, c.old_index, c.new_index))
+        .collect()
+}
+
+#[test]
+fn headers_are_paired_by_name_then_similarity() {
+    let header1 = row(&["ID", "Name", "Qty", "Notes", "City"]);
+    let header2 = row(&["ID", "City", "full name", "Quantity", "Email"]);
+
+    let alignment = ColumnAlignment::from_headers(&header1, &header2, 0.6);
+    assert_eq!(
+        changes(&alignment),
+        vec![
+            (ColumnChange::Unchanged, Some(0), Some(0)),
+            (ColumnChange::Moved, Some(4), Some(1)),
+            (ColumnChange::Renamed, Some(1), Some(2)),
+            (ColumnChange::Renamed, Some(2), Some(3)),
+            // Removed columns follow their old predecessor
+            (ColumnChange::Removed, Some(3), None),
+            (ColumnChange::Added, None, Some(4)),
+        ]
+    );
+    assert_eq!(alignment.columns[1].describe(), "moved from E");
+    assert_eq!(alignment.columns[3].describe(), "renamed from 'Qty'");
+}
+
+#[test]
+fn inserted_column_does_not_modify_rows() {
+    let sheet1 = vec![row(&["ID", "Name", "City"]), row(&["1", "Ann", "Ghent"]), row(&["2", "Bob", "Ypres"])];
+    let sheet2 = vec![
+        row(&["ID", "Email", "Name", "City"]),
+        row(&["1", "ann@example.com", "Ann", "Ghent"]),
+        row(&["2", "bob@example.com", "Bob", "Lier"]),
+    ];
+
+    // By position, every row looks modified
+    let diffs = WorksheetDiffer::new().compare(&sheet1, &sheet2);
+    assert!(diffs.iter().all(|d| d.diff_type != DiffType::Identical));
+
+    let differ = WorksheetDiffer::new().with_column_alignment(true);
+    let columns = differ.compare_columns(&sheet1, &sheet2);
+    let added: Vec<&str> = columns
+        .iter()
+        .filter(|c| c.change == ColumnChange::Added)
+        .map(|c| c.header())
+        .collect();
+    assert_eq!(added, vec!["Email"]);
+
+    let diffs = differ.compare(&sheet1, &sheet2);
+    let types: Vec<DiffType> = diffs.iter().map(|d| d.diff_type).collect();
+    assert_eq!(types, vec![DiffType::Identical, DiffType::Identical, DiffType::Modified]);
+    // Row data follows the combined layout, so City is the fourth column
+    assert_eq!(diffs[2].modified_cells, vec![3]);
+}
+
+#[test]
+fn column_alignment_is_off_by_default() {
+    let sheet = vec![row(&["ID", "Name"]), row(&["1", "Ann"])];
+    assert!(WorksheetDiffer::new().compare_columns(&sheet, &sheet).is_empty());
+}
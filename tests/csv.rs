@@ -0,0 +1,116 @@
+//! Reading delimited text files.
+
+use std::path::PathBuf;
+
+use exceldiff::csv_reader::infer_cell_value;
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{CsvReader, DiffType, FileReader, ReaderRegistry, WorksheetDiffer};
+
+/// Write a file with the given contents to the temporary directory
+fn write_file(name: &str, contents: &[u8]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("exceldiff-csv-{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Read a file with a detected delimiter, quote and encoding
+fn read(name: &str, contents: &[u8]) -> Vec<Row> {
+    let path = write_file(name, contents);
+    let worksheet = CsvReader::new().read(path.to_str().unwrap(), None).unwrap();
+    let _ = std::fs::remove_file(path);
+    worksheet.rows
+}
+
+fn text(value: &str) -> CellValue {
+    CellValue::String(value.to_string())
+}
+
+#[test]
+fn delimiter_is_detected_from_the_contents() {
+    let expected = vec![vec![text("Name"), text("City")], vec![text("Ann"), text("Ghent, BE")]];
+
+    assert_eq!(read("comma.csv", b"Name,City\nAnn,\"Ghent, BE\"\n"), expected);
+    assert_eq!(read("semicolon.csv", b"Name;City\nAnn;Ghent, BE\n"), expected);
+    assert_eq!(read("pipe.txt", b"Name|City\nAnn|Ghent, BE\n"), expected);
+    // The extension decides for .tsv files
+    assert_eq!(read("tab.tsv", b"Name\tCity\nAnn\tGhent, BE\n"), expected);
+}
+
+#[test]
+fn single_quoted_fields_are_unquoted() {
+    let rows = read("single.csv", b"'Name';'City'\n'Ann';'Ghent; BE'\n");
+    assert_eq!(rows[1], vec![text("Ann"), text("Ghent; BE")]);
+}
+
+#[test]
+fn encoding_is_detected() {
+    let expected = vec![vec![text("Café"), CellValue::Int(3)]];
+
+    assert_eq!(read("utf8.csv", "Café,3\n".as_bytes()), expected);
+    assert_eq!(read("bom.csv", "\u{feff}Café,3\n".as_bytes()), expected);
+    // Windows-1252 é
+    assert_eq!(read("ansi.csv", b"Caf\xe9,3\n"), expected);
+
+    let mut utf16 = vec![0xff, 0xfe];
+    utf16.extend("Café,3\n".encode_utf16().flat_map(u16::to_le_bytes));
+    assert_eq!(read("utf16.csv", &utf16), expected);
+}
+
+#[test]
+fn cell_types_are_inferred() {
+    assert_eq!(infer_cell_value("42"), CellValue::Int(42));
+    assert_eq!(infer_cell_value(" 3.5 "), CellValue::Float(3.5));
+    assert_eq!(infer_cell_value("1e3"), CellValue::Float(1000.0));
+    assert_eq!(infer_cell_value("TRUE"), CellValue::Bool(true));
+    assert_eq!(infer_cell_value("false"), CellValue::Bool(false));
+    assert_eq!(infer_cell_value("2024-03-01"), CellValue::DateTime(45352.0));
+    assert_eq!(infer_cell_value("#REF!"), CellValue::Error("#REF!".to_string()));
+    assert_eq!(infer_cell_value(""), CellValue::Empty);
+    // Codes with leading zeros stay text
+    assert_eq!(infer_cell_value("007"), text("007"));
+    assert_eq!(infer_cell_value("0.5"), CellValue::Float(0.5));
+    assert_eq!(infer_cell_value("1-2"), text("1-2"));
+}
+
+#[test]
+fn rows_are_padded_and_named_after_the_file() {
+    let path = write_file("ragged.csv", b"a,b,c\nd\n");
+    let reader = CsvReader::new();
+    let worksheet = reader.read(path.to_str().unwrap(), None).unwrap();
+    assert_eq!(worksheet[1], vec![text("d"), CellValue::Empty, CellValue::Empty]);
+
+    let names = reader.get_sheet_names(path.to_str().unwrap()).unwrap();
+    assert_eq!(names, vec![format!("exceldiff-csv-{}-ragged", std::process::id())]);
+    assert!(reader.read(path.to_str().unwrap(), Some("Other")).is_err());
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn registry_picks_the_reader_by_extension() {
+    let registry = ReaderRegistry::default();
+    assert!(registry.reader_for("export.csv").is_some());
+    assert!(registry.reader_for("export.TSV").is_some());
+    assert!(registry.reader_for("book.xlsx").is_some());
+    assert!(registry.reader_for("notes.pdf").is_none());
+    assert!(ReaderRegistry::new().reader_for("export.csv").is_none());
+}
+
+#[test]
+fn long_digit_strings_stay_text() {
+    assert_eq!(
+        infer_cell_value("12345678901234567891"),
+        CellValue::String("12345678901234567891".to_string())
+    );
+    assert_eq!(
+        infer_cell_value("1234567890123456"),
+        CellValue::String("1234567890123456".to_string())
+    );
+    // Up to 15 digits Excel keeps the number exactly
+    assert_eq!(infer_cell_value("123456789012345"), CellValue::Int(123456789012345));
+    assert_eq!(infer_cell_value("-42"), CellValue::Int(-42));
+
+    let sheet1 = vec![vec![infer_cell_value("12345678901234567891")]];
+    let sheet2 = vec![vec![infer_cell_value("12345678901234567892")]];
+    let diffs = WorksheetDiffer::new().compare(&sheet1, &sheet2);
+    assert!(diffs.iter().all(|d| d.diff_type != DiffType::Identical));
+}
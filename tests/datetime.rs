@@ -0,0 +1,45 @@
+//! Conversion between calendar dates and Excel serial dates.
+
+use exceldiff::datetime::{excel_serial, format_excel_serial, parse_iso_datetime};
+
+#[test]
+fn serial_dates_follow_the_1900_date_system() {
+    assert_eq!(excel_serial(1900, 1, 1, 0, 0, 0.0), Some(1.0));
+    assert_eq!(excel_serial(1900, 2, 28, 0, 0, 0.0), Some(59.0));
+    // Excel counts a 29 February 1900 that never existed
+    assert_eq!(excel_serial(1900, 2, 29, 0, 0, 0.0), Some(60.0));
+    assert_eq!(excel_serial(1900, 3, 1, 0, 0, 0.0), Some(61.0));
+    assert_eq!(excel_serial(2024, 3, 1, 12, 0, 0.0), Some(45352.5));
+
+    assert_eq!(excel_serial(1899, 12, 31, 0, 0, 0.0), None);
+    assert_eq!(excel_serial(2023, 2, 29, 0, 0, 0.0), None);
+    assert_eq!(excel_serial(2024, 13, 1, 0, 0, 0.0), None);
+    assert_eq!(excel_serial(2024, 1, 1, 24, 0, 0.0), None);
+}
+
+#[test]
+fn serial_dates_are_formatted() {
+    assert_eq!(format_excel_serial(59.0).as_deref(), Some("1900-02-28"));
+    assert_eq!(format_excel_serial(60.0).as_deref(), Some("1900-02-29"));
+    assert_eq!(format_excel_serial(61.0).as_deref(), Some("1900-03-01"));
+    assert_eq!(format_excel_serial(45352.5).as_deref(), Some("2024-03-01 12:00:00"));
+    assert_eq!(format_excel_serial(0.75).as_deref(), Some("18:00:00"));
+    assert_eq!(format_excel_serial(-1.0), None);
+
+    for serial in [1.0, 59.0, 60.0, 61.0, 45352.0] {
+        let text = format_excel_serial(serial).unwrap();
+        assert_eq!(parse_iso_datetime(&text), Some(serial), "{}", text);
+    }
+}
+
+#[test]
+fn iso_dates_and_times_are_parsed() {
+    assert_eq!(parse_iso_datetime("2024-03-01"), Some(45352.0));
+    assert_eq!(parse_iso_datetime("2024-03-01T06:00"), Some(45352.25));
+    assert_eq!(parse_iso_datetime("2024-03-01 06:00:00Z"), Some(45352.25));
+    assert_eq!(parse_iso_datetime("2024-03-01T00:00:00.5"), Some(45352.0 + 0.5 / 86_400.0));
+
+    assert_eq!(parse_iso_datetime("2024-3-1"), None);
+    assert_eq!(parse_iso_datetime("2024-03-01T6:00"), None);
+    assert_eq!(parse_iso_datetime("01/03/2024"), None);
+}
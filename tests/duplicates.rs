@@ -0,0 +1,138 @@
+//! Comparison of worksheets containing duplicate rows.
+
+use std::collections::HashSet;
+
+use exceldiff::reader::{CellValue, Row, Worksheet};
+use exceldiff::{AlignMode, DiffType, RowDiff, WorksheetDiffer};
+
+fn row(values: &[&str]) -> Row {
+    values
+        .iter()
+        .map(|v| match v.parse::<i64>() {
+            Ok(i) => CellValue::Int(i),
+            Err(_) => CellValue::String(v.to_string()),
+        })
+        .collect()
+}
+
+fn repeat(values: &[&str], times: usize) -> Worksheet {
+    (0..times).map(|_| row(values)).collect()
+}
+
+fn count(diffs: &[RowDiff], diff_type: DiffType) -> usize {
+    diffs.iter().filter(|d| d.diff_type == diff_type).count()
+}
+
+/// Every row of both sheets must be accounted for exactly once
+fn assert_positions_unique(diffs: &[RowDiff], rows1: usize, rows2: usize) {
+    let old: Vec<usize> = diffs.iter().filter_map(|d| d.old_row).collect();
+    let new: Vec<usize> = diffs.iter().filter_map(|d| d.new_row).collect();
+    assert_eq!(old.len(), rows1);
+    assert_eq!(new.len(), rows2);
+    assert_eq!(old.iter().collect::<HashSet<_>>().len(), rows1);
+    assert_eq!(new.iter().collect::<HashSet<_>>().len(), rows2);
+}
+
+fn differs() -> Vec<WorksheetDiffer> {
+    vec![
+        WorksheetDiffer::new(),
+        WorksheetDiffer::new().with_align_mode(AlignMode::Myers),
+        WorksheetDiffer::new().with_align_mode(AlignMode::Patience),
+    ]
+}
+
+#[test]
+fn more_copies_in_first_sheet_are_removed() {
+    let sheet1 = repeat(&["2024-01", "Coffee", "3"], 5);
+    let sheet2 = repeat(&["2024-01", "Coffee", "3"], 2);
+
+    for differ in differs() {
+        let diffs = differ.compare(&sheet1, &sheet2);
+        assert_eq!(count(&diffs, DiffType::Identical), 2);
+        assert_eq!(count(&diffs, DiffType::Removed), 3);
+        assert_eq!(count(&diffs, DiffType::Added), 0);
+        assert_positions_unique(&diffs, 5, 2);
+    }
+}
+
+#[test]
+fn more_copies_in_second_sheet_are_added() {
+    let sheet1 = repeat(&["2024-01", "Coffee", "3"], 2);
+    let sheet2 = repeat(&["2024-01", "Coffee", "3"], 6);
+
+    for differ in differs() {
+        let diffs = differ.compare(&sheet1, &sheet2);
+        assert_eq!(count(&diffs, DiffType::Identical), 2);
+        assert_eq!(count(&diffs, DiffType::Removed), 0);
+        assert_eq!(count(&diffs, DiffType::Added), 4);
+        assert_positions_unique(&diffs, 2, 6);
+    }
+}
+
+#[test]
+fn equal_copies_are_identical() {
+    let mut sheet1 = repeat(&["A", "1"], 3);
+    sheet1.extend(repeat(&["B", "2"], 4));
+    let mut sheet2 = repeat(&["B", "2"], 4);
+    sheet2.extend(repeat(&["A", "1"], 3));
+
+    let diffs = WorksheetDiffer::new().compare(&sheet1, &sheet2);
+    assert_eq!(count(&diffs, DiffType::Identical), 7);
+    assert_eq!(diffs.len(), 7);
+    assert_positions_unique(&diffs, 7, 7);
+}
+
+#[test]
+fn interleaved_duplicates_differ_by_count_difference() {
+    let lines = [["Rent", "1000"], ["Coffee", "3"], ["Coffee", "3"], ["Salary", "-2500"]];
+    let sheet1: Worksheet = lines.iter().cycle().take(40).map(|l| row(l)).collect();
+    let sheet2: Worksheet = lines.iter().cycle().take(28).map(|l| row(l)).collect();
+
+    for differ in differs() {
+        let diffs = differ.compare(&sheet1, &sheet2);
+        assert_eq!(count(&diffs, DiffType::Identical), 28);
+        assert_eq!(count(&diffs, DiffType::Removed), 12);
+        assert_eq!(count(&diffs, DiffType::Added), 0);
+        assert_eq!(count(&diffs, DiffType::Modified), 0);
+        assert_positions_unique(&diffs, 40, 28);
+    }
+}
+
+#[test]
+fn modified_duplicate_is_paired_once() {
+    let sheet1 = repeat(&["Coffee", "3", "cash"], 3);
+    let mut sheet2 = repeat(&["Coffee", "3", "cash"], 2);
+    sheet2.push(row(&["Coffee", "4", "cash"]));
+
+    for differ in differs() {
+        let diffs = differ.compare(&sheet1, &sheet2);
+        assert_eq!(count(&diffs, DiffType::Identical), 2);
+        assert_eq!(count(&diffs, DiffType::Modified), 1);
+        assert_eq!(count(&diffs, DiffType::Removed), 0);
+        assert_eq!(count(&diffs, DiffType::Added), 0);
+        assert_positions_unique(&diffs, 3, 3);
+
+        let modified = diffs.iter().find(|d| d.diff_type == DiffType::Modified).unwrap();
+        assert_eq!(modified.modified_cells, vec![1]);
+    }
+}
+
+#[test]
+fn duplicate_keys_pair_up_in_order() {
+    let sheet1 = vec![
+        row(&["ID", "Amount"]),
+        row(&["7", "10"]),
+        row(&["7", "20"]),
+        row(&["7", "30"]),
+    ];
+    let sheet2 = vec![row(&["ID", "Amount"]), row(&["7", "10"]), row(&["7", "25"])];
+
+    let diffs = WorksheetDiffer::new()
+        .with_key_columns(vec![0])
+        .compare(&sheet1, &sheet2);
+    assert_eq!(count(&diffs, DiffType::Identical), 2);
+    assert_eq!(count(&diffs, DiffType::Modified), 1);
+    assert_eq!(count(&diffs, DiffType::Removed), 1);
+    assert_eq!(count(&diffs, DiffType::Added), 0);
+    assert_positions_unique(&diffs, 4, 3);
+}
@@ -0,0 +1,35 @@
+//! Comparison of worksheets with a header row.
+
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{DiffSummary, DiffType, HeaderRow, WorksheetDiffer};
+
+fn row(values: &[&str]) -> Row {
+    values.iter().map(|v| CellValue::String(v.to_string())).collect()
+}
+
+#[test]
+fn header_row_is_not_counted_in_summary() {
+    let sheet1 = vec![row(&["Quarterly report"]), row(&["Name", "City"]), row(&["Ann", "Ghent"])];
+    let sheet2 = vec![row(&["Quarterly report"]), row(&["Name", "City"]), row(&["Ann", "Bruges"])];
+
+    let diffs = WorksheetDiffer::new()
+        .with_header_row(HeaderRow::Auto)
+        .compare(&sheet1, &sheet2);
+    let header = diffs.iter().find(|d| d.header).unwrap();
+    assert_eq!(header.old_row, Some(1));
+    assert_eq!(header.diff_type, DiffType::Identical);
+
+    let summary = DiffSummary::from_diffs(&diffs);
+    assert_eq!(summary.identical, 1);
+    assert_eq!(summary.modified, 1);
+}
+
+#[test]
+fn text_rows_are_data_without_header_row() {
+    let sheet1 = vec![row(&["Ann", "Ghent"]), row(&["Bob", "Ypres"])];
+    let sheet2 = vec![row(&["Ann", "Ghent"]), row(&["Bob", "Ypres"])];
+
+    let diffs = WorksheetDiffer::new().compare(&sheet1, &sheet2);
+    assert!(diffs.iter().all(|d| !d.header));
+    assert_eq!(DiffSummary::from_diffs(&diffs).identical, 2);
+}
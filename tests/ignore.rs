@@ -0,0 +1,34 @@
+//! Comparison of worksheets with rows left out of the comparison.
+
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{DiffSummary, DiffType, RowFilter, WorksheetDiffer};
+use regex::Regex;
+
+fn row(values: &[&str]) -> Row {
+    values.iter().map(|v| CellValue::String(v.to_string())).collect()
+}
+
+#[test]
+fn ignored_rows_keep_their_type_and_are_counted_apart() {
+    let sheet1 = vec![row(&["Ann", "final"]), row(&["Bob", "draft"])];
+    let sheet2 = vec![row(&["Ann", "final"]), row(&["Bob", "draft"]), row(&["Cid", "draft"])];
+
+    let diffs = WorksheetDiffer::new()
+        .with_row_filter(RowFilter::pattern(1, Regex::new("^draft$").unwrap()))
+        .compare(&sheet1, &sheet2);
+
+    let added = diffs.iter().find(|d| d.new_row == Some(2)).unwrap();
+    assert_eq!(added.diff_type, DiffType::Added);
+    assert!(added.ignored);
+    assert!(!added.has_changes());
+
+    let paired = diffs.iter().find(|d| d.new_row == Some(1)).unwrap();
+    assert_eq!(paired.diff_type, DiffType::Identical);
+    assert!(paired.ignored);
+
+    let summary = DiffSummary::from_diffs(&diffs);
+    assert_eq!(summary.identical, 1);
+    assert_eq!(summary.added, 0);
+    assert_eq!(summary.ignored, 2);
+    assert_eq!(summary.changed(), 0);
+}
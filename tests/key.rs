@@ -0,0 +1,90 @@
+//! Matching rows on primary key columns.
+
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{ColumnSpec, DiffSummary, DiffType, RowDiff, WorksheetDiffer};
+
+fn row(values: &[&str]) -> Row {
+    values
+        .iter()
+        .map(|v| match v.parse::<i64>() {
+            Ok(i) => CellValue::Int(i),
+            Err(_) => CellValue::String(v.to_string()),
+        })
+        .collect()
+}
+
+fn find(diffs: &[RowDiff], old_row: usize) -> &RowDiff {
+    diffs.iter().find(|d| d.old_row == Some(old_row)).unwrap()
+}
+
+fn customers() -> (Vec<Row>, Vec<Row>) {
+    let sheet1 = vec![
+        row(&["ID", "Name", "City", "Price"]),
+        row(&["1", "Ann", "Ghent", "10"]),
+        row(&["2", "Bob", "Ypres", "20"]),
+        row(&["3", "Cid", "Lier", "30"]),
+    ];
+    let sheet2 = vec![
+        row(&["ID", "Name", "City", "Price"]),
+        row(&["3", "Cid", "Lier", "30"]),
+        row(&["1", "Anna", "Ghent", "12"]),
+        row(&["4", "Dee", "Mol", "40"]),
+    ];
+    (sheet1, sheet2)
+}
+
+#[test]
+fn rows_with_the_same_key_are_modified_however_much_changed() {
+    let (sheet1, sheet2) = customers();
+
+    // Without a key, a record with half of its cells changed is not recognized
+    let diffs = WorksheetDiffer::new()
+        .with_similarity_threshold(0.8)
+        .compare(&sheet1, &sheet2);
+    assert_eq!(find(&diffs, 1).diff_type, DiffType::Removed);
+
+    let diffs = WorksheetDiffer::new()
+        .with_similarity_threshold(0.8)
+        .with_key_columns(vec![0])
+        .compare(&sheet1, &sheet2);
+    let ann = find(&diffs, 1);
+    assert_eq!(ann.diff_type, DiffType::Modified);
+    assert_eq!(ann.new_row, Some(2));
+    assert_eq!(ann.modified_cells, vec![1, 3]);
+    assert_eq!(find(&diffs, 3).diff_type, DiffType::Identical);
+
+    // Keys in one file only
+    assert_eq!(find(&diffs, 2).diff_type, DiffType::Removed);
+    let summary = DiffSummary::from_diffs(&diffs);
+    assert_eq!((summary.identical, summary.modified), (2, 1));
+    assert_eq!((summary.removed, summary.added), (1, 1));
+}
+
+#[test]
+fn composite_keys_pair_on_all_key_columns() {
+    let sheet1 = vec![row(&["North", "A1", "5"]), row(&["South", "A1", "6"])];
+    let sheet2 = vec![row(&["South", "A1", "7"]), row(&["North", "A1", "5"])];
+
+    let diffs = WorksheetDiffer::new()
+        .with_key_columns(vec![0, 1])
+        .compare(&sheet1, &sheet2);
+    assert_eq!(find(&diffs, 0).diff_type, DiffType::Identical);
+    assert_eq!(find(&diffs, 0).new_row, Some(1));
+    let south = find(&diffs, 1);
+    assert_eq!(south.diff_type, DiffType::Modified);
+    assert_eq!(south.new_row, Some(0));
+    assert_eq!(south.modified_cells, vec![2]);
+}
+
+#[test]
+fn key_columns_are_named_by_header_letter_or_number() {
+    let (sheet1, _) = customers();
+    let header = &sheet1[0];
+
+    let specs: Vec<ColumnSpec> = ["Price", "price", "B", "3"].iter().map(|s| s.parse().unwrap()).collect();
+    assert_eq!(ColumnSpec::resolve_all(&specs, header, 0).unwrap(), vec![3, 3, 1, 2]);
+    // A header name wins over the column letter it also is
+    assert_eq!("ID".parse::<ColumnSpec>().unwrap().resolve(header, 0).unwrap(), 0);
+    assert!("Missing".parse::<ColumnSpec>().unwrap().resolve(header, 0).is_err());
+    assert!("0".parse::<ColumnSpec>().is_err());
+}
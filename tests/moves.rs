@@ -0,0 +1,85 @@
+//! Detection of rows that moved to another position.
+
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{DiffSummary, DiffType, RowDiff, WorksheetDiffer};
+
+fn row(values: &[&str]) -> Row {
+    values.iter().map(|v| CellValue::String(v.to_string())).collect()
+}
+
+fn sheets() -> (Vec<Row>, Vec<Row>) {
+    let sheet1 = vec![
+        row(&["Ann", "Ghent", "1"]),
+        row(&["Bob", "Ypres", "2"]),
+        row(&["Cid", "Lier", "3"]),
+        row(&["Dee", "Mol", "4"]),
+        row(&["Eve", "Hal", "5"]),
+    ];
+    // Bob moved down below Dee, and Dee's number changed
+    let sheet2 = vec![
+        row(&["Ann", "Ghent", "1"]),
+        row(&["Cid", "Lier", "3"]),
+        row(&["Dee", "Mol", "9"]),
+        row(&["Bob", "Ypres", "2"]),
+        row(&["Eve", "Hal", "5"]),
+    ];
+    (sheet1, sheet2)
+}
+
+fn find<'a>(diffs: &'a [RowDiff], name: &str) -> &'a RowDiff {
+    diffs
+        .iter()
+        .find(|d| d.row_data.first() == Some(&CellValue::String(name.to_string())))
+        .unwrap()
+}
+
+#[test]
+fn rows_out_of_order_are_moved() {
+    let (sheet1, sheet2) = sheets();
+    let differs = [
+        WorksheetDiffer::new().with_move_detection(true),
+        WorksheetDiffer::new().with_move_detection(true).with_key_columns(vec![0]),
+    ];
+
+    for differ in differs {
+        let diffs = differ.compare(&sheet1, &sheet2);
+        let bob = find(&diffs, "Bob");
+        assert_eq!(bob.diff_type, DiffType::Moved);
+        assert_eq!((bob.old_row, bob.new_row), (Some(1), Some(3)));
+
+        // Rows shifted by the move keep their type
+        assert_eq!(find(&diffs, "Cid").diff_type, DiffType::Identical);
+        assert_eq!(find(&diffs, "Dee").diff_type, DiffType::Modified);
+
+        let summary = DiffSummary::from_diffs(&diffs);
+        assert_eq!(summary.moved, 1);
+        assert_eq!(summary.identical, 3);
+        assert_eq!(summary.modified, 1);
+    }
+}
+
+#[test]
+fn moved_rows_keep_their_changed_cells() {
+    let (sheet1, mut sheet2) = sheets();
+    sheet2[3] = row(&["Bob", "Ypres", "7"]);
+
+    let diffs = WorksheetDiffer::new()
+        .with_move_detection(true)
+        .with_key_columns(vec![0])
+        .compare(&sheet1, &sheet2);
+    let bob = find(&diffs, "Bob");
+    assert_eq!(bob.diff_type, DiffType::Moved);
+    assert_eq!(bob.modified_cells, vec![2]);
+}
+
+#[test]
+fn order_is_ignored_without_move_detection() {
+    // What --ignore-order does
+    let (sheet1, sheet2) = sheets();
+    let diffs = WorksheetDiffer::new()
+        .with_move_detection(false)
+        .compare(&sheet1, &sheet2);
+    assert!(diffs.iter().all(|d| d.diff_type != DiffType::Moved));
+    assert_eq!(find(&diffs, "Bob").diff_type, DiffType::Identical);
+    assert_eq!(DiffSummary::from_diffs(&diffs).moved, 0);
+}
@@ -0,0 +1,69 @@
+//! Text normalization of string cells before they are compared.
+
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{ColumnNormalization, DiffType, Normalizer, TextNormalization, WorksheetDiffer};
+
+fn row(values: &[&str]) -> Row {
+    values.iter().map(|v| CellValue::String(v.to_string())).collect()
+}
+
+fn normalizer(steps: &str) -> Normalizer {
+    steps.parse().unwrap()
+}
+
+#[test]
+fn each_step_folds_its_own_differences() {
+    assert_eq!(normalizer("invisible").apply("a\u{00A0}b\u{200B}c"), "a bc");
+    assert_eq!(normalizer("nfkc").apply("\u{FB01}le e\u{0301}"), "file \u{00E9}");
+    assert_eq!(normalizer("punctuation").apply("\u{201C}it\u{2019}s\u{201D} \u{2013} 5\u{2212}3"), "\"it's\" - 5-3");
+    assert_eq!(normalizer("case").apply("Straße"), normalizer("case").apply("STRASSE"));
+    assert_eq!(normalizer("whitespace").apply("  a \t b  "), "a b");
+    assert_eq!(normalizer("none").apply(" A\u{00A0}"), " A\u{00A0}");
+}
+
+#[test]
+fn steps_run_in_a_fixed_order() {
+    let pipeline = normalizer("whitespace+case+invisible");
+    assert_eq!(pipeline, normalizer("invisible+case+whitespace"));
+    assert_eq!(pipeline.to_string(), "invisible+case+whitespace");
+    // Non-breaking spaces become spaces before whitespace is collapsed
+    assert_eq!(pipeline.apply(" Ann\u{00A0}\u{00A0}Lee "), "ann lee");
+
+    let pipeline: Normalizer = [TextNormalization::Case, TextNormalization::Case].into_iter().collect();
+    assert_eq!(pipeline.to_string(), "case");
+    assert!(pipeline.has_step(TextNormalization::Case));
+    assert!(Normalizer::new().is_empty());
+
+    assert_eq!("Unicode".parse::<TextNormalization>().unwrap(), TextNormalization::Nfkc);
+    assert!("accents".parse::<Normalizer>().is_err());
+}
+
+#[test]
+fn normalized_strings_compare_equal() {
+    let sheet1 = vec![row(&["1", "Ann Lee", "\u{201C}A\u{201D}"]), row(&["2", "Bob", "x"])];
+    let sheet2 = vec![row(&["1", "ANN\u{00A0}LEE", "\"A\""]), row(&["2", "bob", "X"])];
+
+    let diffs = WorksheetDiffer::new().with_key_columns(vec![0]).compare(&sheet1, &sheet2);
+    assert!(diffs.iter().all(|d| d.diff_type == DiffType::Modified));
+
+    let differ = WorksheetDiffer::new()
+        .with_key_columns(vec![0])
+        .with_normalizer(normalizer("invisible+punctuation+case"));
+    let diffs = differ.compare(&sheet1, &sheet2);
+    assert!(diffs.iter().all(|d| d.diff_type == DiffType::Identical));
+
+    // A column normalization replaces the general one
+    let diffs = differ.with_column_normalizer(2, Normalizer::new()).compare(&sheet1, &sheet2);
+    let modified: Vec<Vec<usize>> = diffs.iter().map(|d| d.modified_cells.clone()).collect();
+    assert_eq!(modified, vec![vec![2], vec![2]]);
+}
+
+#[test]
+fn column_normalizations_are_parsed() {
+    let column: ColumnNormalization = "Name=case+nfkc".parse().unwrap();
+    assert_eq!(column.column, "Name".parse().unwrap());
+    assert_eq!(column.normalizer, normalizer("nfkc+case"));
+    assert!(" Code = none ".parse::<ColumnNormalization>().unwrap().normalizer.is_empty());
+    assert!("Name".parse::<ColumnNormalization>().is_err());
+    assert!("Name=upper".parse::<ColumnNormalization>().is_err());
+}
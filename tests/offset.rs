@@ -0,0 +1,95 @@
+//! Comparison of sheets whose data does not start at A1.
+
+use std::path::PathBuf;
+
+use exceldiff::reader::{CellValue, Worksheet};
+use exceldiff::{ColumnSelector, ColumnSpec, DiffType, ExcelReader, FileReader, WorksheetDiffer};
+use rust_xlsxwriter::Workbook;
+
+/// Write a workbook with the given rows starting at C3
+fn write_workbook(name: &str, rows: &[[&str; 3]]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("exceldiff-offset-{}-{}.xlsx", std::process::id(), name));
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    for (row_idx, row) in rows.iter().enumerate() {
+        for (col_idx, value) in row.iter().enumerate() {
+            worksheet.write_string(2 + row_idx as u32, 2 + col_idx as u16, *value).unwrap();
+        }
+    }
+    workbook.save(&path).unwrap();
+    path
+}
+
+/// Read the sheets of two workbooks starting at C3
+fn read_pair(name: &str, old: &[[&str; 3]], new: &[[&str; 3]]) -> (Worksheet, Worksheet) {
+    let path1 = write_workbook(&format!("{}-old", name), old);
+    let path2 = write_workbook(&format!("{}-new", name), new);
+    let reader = ExcelReader::new();
+    let sheets = (
+        reader.read(path1.to_str().unwrap(), None).unwrap(),
+        reader.read(path2.to_str().unwrap(), None).unwrap(),
+    );
+    let _ = std::fs::remove_file(path1);
+    let _ = std::fs::remove_file(path2);
+    sheets
+}
+
+fn header(sheet: &Worksheet) -> &[CellValue] {
+    &sheet[0]
+}
+
+#[test]
+fn key_letter_refers_to_the_file_column() {
+    let (sheet1, sheet2) = read_pair(
+        "key",
+        &[["Code", "Name", "City"], ["K1", "Ann", "Ghent"], ["K2", "Bob", "Ypres"]],
+        &[["Code", "Name", "City"], ["K1", "Ann", "Bruges"], ["K2", "Rob", "Ypres"]],
+    );
+    assert_eq!(sheet1.origin, (2, 2));
+
+    let key: ColumnSpec = "C".parse().unwrap();
+    let key_col = key.resolve(header(&sheet1), sheet1.origin.1).unwrap();
+    assert_eq!(key_col, 0);
+
+    let diffs = WorksheetDiffer::new()
+        .with_key_columns(vec![key_col])
+        .compare_worksheets(&sheet1, &sheet2);
+    let modified: Vec<_> = diffs.iter().filter(|d| d.diff_type == DiffType::Modified).collect();
+    assert_eq!(modified.len(), 2);
+    assert!(diffs.iter().all(|d| matches!(d.diff_type, DiffType::Identical | DiffType::Modified)));
+    assert_eq!(modified[0].modified_cells, vec![2]);
+    assert_eq!(modified[1].modified_cells, vec![1]);
+}
+
+#[test]
+fn ignored_column_letter_refers_to_the_file_column() {
+    let (sheet1, sheet2) = read_pair(
+        "ignore",
+        &[["Code", "Name", "Updated"], ["K1", "Ann", "Monday"]],
+        &[["Code", "Name", "Updated"], ["K1", "Ann", "Tuesday"]],
+    );
+
+    let selector: ColumnSelector = "E".parse().unwrap();
+    let columns = selector.resolve(header(&sheet1), sheet1.origin.1).unwrap();
+    assert_eq!(columns, vec![2]);
+
+    let diffs = WorksheetDiffer::new()
+        .with_ignored_columns(columns)
+        .compare_worksheets(&sheet1, &sheet2);
+    assert!(diffs.iter().all(|d| d.diff_type == DiffType::Identical));
+}
+
+#[test]
+fn letters_left_of_the_sheet_are_an_error() {
+    let (sheet1, _) = read_pair("left", &[["Code", "Name", "City"]], &[["Code", "Name", "City"]]);
+
+    let spec: ColumnSpec = "A".parse().unwrap();
+    let error = spec.resolve(header(&sheet1), sheet1.origin.1).unwrap_err();
+    assert!(error.to_string().contains("left of the first column"));
+
+    // Header names and column numbers do not depend on where the sheet starts
+    let name: ColumnSpec = "City".parse().unwrap();
+    assert_eq!(name.resolve(header(&sheet1), sheet1.origin.1).unwrap(), 2);
+    let number: ColumnSpec = "2".parse().unwrap();
+    assert_eq!(number.resolve(header(&sheet1), sheet1.origin.1).unwrap(), 1);
+}
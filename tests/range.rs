@@ -0,0 +1,94 @@
+//! Selecting the cell range of a worksheet to compare.
+
+use exceldiff::reader::{CellValue, Worksheet};
+use exceldiff::{CellRange, ExcelReader, FileReader, RangeSelection};
+use rust_xlsxwriter::{Table, Workbook};
+
+fn range(s: &str) -> CellRange {
+    s.parse().unwrap()
+}
+
+fn texts(sheet: &Worksheet) -> Vec<Vec<String>> {
+    sheet.iter().map(|row| row.iter().map(CellValue::to_string).collect()).collect()
+}
+
+#[test]
+fn ranges_are_parsed_in_a1_notation() {
+    assert_eq!(range("A5:K2000"), CellRange::new((4, 0), (1999, 10)));
+    assert_eq!(range(" $B$3 "), CellRange::new((2, 1), (2, 1)));
+    let columns = range("C:E");
+    assert_eq!((columns.start(), columns.last_row, columns.last_col), ((0, 2), None, Some(4)));
+    let rows = range("5:10");
+    assert_eq!((rows.start(), rows.last_row, rows.last_col), ((4, 0), Some(9), None));
+
+    for text in ["A5:K2000", "C:E", "5:10"] {
+        assert_eq!(range(text).to_string(), text);
+    }
+    assert_eq!(range("B3").to_string(), "B3:B3");
+
+    for invalid in ["K2000:A5", "E:C", "0:5", "A", "A5:", "5"] {
+        assert!(invalid.parse::<CellRange>().is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn whole_columns_and_rows_end_with_the_sheet() {
+    assert_eq!(range("C:E").end_within((99, 20)), Some((99, 4)));
+    assert_eq!(range("5:10").end_within((99, 20)), Some((9, 20)));
+    assert_eq!(range("A5:K2000").end_within((99, 5)), Some((99, 5)));
+    // Entirely below the used area
+    assert_eq!(range("A200:B300").end_within((99, 5)), None);
+}
+
+#[test]
+fn selections_are_ranges_sheet_ranges_or_names() {
+    let parse = |s: &str| s.parse::<RangeSelection>().unwrap();
+    assert_eq!(parse("A5:K2000"), RangeSelection::Cells(range("A5:K2000")));
+    assert_eq!(
+        parse("'Q1 ''Final'' Data'!A5:K2000"),
+        RangeSelection::SheetCells("Q1 'Final' Data".to_string(), range("A5:K2000"))
+    );
+    assert_eq!(parse("Data!C:E"), RangeSelection::SheetCells("Data".to_string(), range("C:E")));
+    assert_eq!(parse("SalesTable"), RangeSelection::Name("SalesTable".to_string()));
+    // Names that look like a cell reference are cells, as in Excel
+    assert_eq!(parse("TBL1"), RangeSelection::Cells(range("TBL1")));
+
+    assert_eq!(parse("'Q1 Data'!A5:K2000").to_string(), "'Q1 Data'!A5:K2000");
+    assert!("Data!Sales".parse::<RangeSelection>().is_err());
+    assert!("two words".parse::<RangeSelection>().is_err());
+}
+
+#[test]
+fn ranges_names_and_tables_are_read_from_workbooks() {
+    let path = std::env::temp_dir().join(format!("exceldiff-range-{}.xlsx", std::process::id()));
+    let mut workbook = Workbook::new();
+    workbook.add_worksheet().set_name("Cover").unwrap();
+    let worksheet = workbook.add_worksheet().set_name("Q1 Data").unwrap();
+    for (row_idx, row) in [["ID", "Name", "City"], ["1", "Ann", "Ghent"], ["2", "Bob", "Ypres"]].iter().enumerate() {
+        for (col_idx, value) in row.iter().enumerate() {
+            worksheet.write_string(1 + row_idx as u32, 1 + col_idx as u16, *value).unwrap();
+        }
+    }
+    worksheet.add_table(1, 1, 3, 3, &Table::new().set_name("People")).unwrap();
+    workbook.define_name("Names", "='Q1 Data'!$C$2:$C$4").unwrap();
+    workbook.save(&path).unwrap();
+    let path_str = path.to_str().unwrap();
+    let reader = ExcelReader::new();
+    let read = |selection: &str, sheet: Option<&str>| reader.read_range(path_str, sheet, &selection.parse().unwrap());
+
+    let cells = read("C3:D4", Some("Q1 Data")).unwrap();
+    assert_eq!(texts(&cells), vec![vec!["Ann", "Ghent"], vec!["Bob", "Ypres"]]);
+    assert_eq!(cells.origin, (2, 2));
+
+    // The sheet of the range or name is read, whichever sheet is first
+    let names = read("Names", None).unwrap();
+    assert_eq!(texts(&names), vec![vec!["Name"], vec!["Ann"], vec!["Bob"]]);
+    assert_eq!(names.origin, (1, 2));
+    let table = read("people", None).unwrap();
+    assert_eq!(texts(&table)[0], vec!["ID", "Name", "City"]);
+    assert_eq!(table.origin, (1, 1));
+
+    assert!(read("'Q1 Data'!A1:B2", Some("Cover")).is_err());
+    assert!(read("Missing", None).is_err());
+    let _ = std::fs::remove_file(path);
+}
@@ -0,0 +1,64 @@
+//! Pairing of modified rows by their share of equal cells.
+
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{DiffType, RowDiff, WorksheetDiffer};
+
+fn text(value: String) -> CellValue {
+    CellValue::String(value)
+}
+
+/// Rows with unique first and last cells and the same cells in between
+fn rows(count: usize, version: &str) -> Vec<Row> {
+    (0..count)
+        .map(|idx| {
+            vec![
+                text(format!("{}-a{}", version, idx)),
+                text("x".to_string()),
+                text("y".to_string()),
+                text(format!("{}-b{}", version, idx)),
+            ]
+        })
+        .collect()
+}
+
+fn count(diffs: &[RowDiff], diff_type: DiffType) -> usize {
+    diffs.iter().filter(|d| d.diff_type == diff_type).count()
+}
+
+#[test]
+fn rows_sharing_only_common_values_are_modified_at_any_size() {
+    // Half of the cells are equal, but only in columns where every row is equal
+    for size in [99, 100, 101, 1_000] {
+        let diffs = WorksheetDiffer::new().compare(&rows(size, "old"), &rows(size, "new"));
+        assert_eq!(count(&diffs, DiffType::Modified), size, "{} rows", size);
+        assert_eq!(count(&diffs, DiffType::Removed), 0, "{} rows", size);
+        // Equally similar rows are paired in order
+        assert!(diffs.iter().all(|d| d.old_row == d.new_row));
+    }
+}
+
+#[test]
+fn rows_below_the_threshold_stay_unpaired() {
+    let diffs = WorksheetDiffer::new()
+        .with_similarity_threshold(0.75)
+        .compare(&rows(150, "old"), &rows(150, "new"));
+    assert_eq!(count(&diffs, DiffType::Removed), 150);
+    assert_eq!(count(&diffs, DiffType::Added), 150);
+}
+
+#[test]
+fn zero_threshold_pairs_rows_with_any_equal_cell() {
+    let row = |values: &[&str]| -> Row { values.iter().map(|v| text(v.to_string())).collect() };
+    let sheet1 = vec![row(&["a", "1"]), row(&["b", "2"])];
+    let sheet2 = vec![row(&["c", "3"]), row(&["a", "4"])];
+
+    let diffs = WorksheetDiffer::new()
+        .with_similarity_threshold(0.0)
+        .compare(&sheet1, &sheet2);
+    let modified: Vec<&RowDiff> = diffs.iter().filter(|d| d.diff_type == DiffType::Modified).collect();
+    assert_eq!(modified.len(), 1);
+    assert_eq!((modified[0].old_row, modified[0].new_row), (Some(0), Some(1)));
+    // Rows without any equal cell are never paired
+    assert_eq!(count(&diffs, DiffType::Removed), 1);
+    assert_eq!(count(&diffs, DiffType::Added), 1);
+}
@@ -0,0 +1,117 @@
+//! Streaming comparison of sorted .xlsx sheets.
+
+use std::path::PathBuf;
+
+use exceldiff::workbook::SheetOrigins;
+use exceldiff::{DiffType, ExcelReader, FileReader, RowDiff, StreamingWorkbook, WorksheetDiffer};
+use rust_xlsxwriter::Workbook;
+
+/// Write a workbook with the given text rows starting at C3
+fn write_workbook(name: &str, rows: &[[&str; 2]]) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("exceldiff-stream-{}-{}.xlsx", std::process::id(), name));
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    for (row_idx, row) in rows.iter().enumerate() {
+        for (col_idx, value) in row.iter().enumerate() {
+            worksheet.write_string(2 + row_idx as u32, 2 + col_idx as u16, *value).unwrap();
+        }
+    }
+    workbook.save(&path).unwrap();
+    path
+}
+
+fn stream_diffs(differ: &WorksheetDiffer, path1: &str, path2: &str) -> Vec<RowDiff> {
+    let mut old = StreamingWorkbook::open(path1).unwrap();
+    let mut new = StreamingWorkbook::open(path2).unwrap();
+    let (old_rows, new_rows) = (old.rows(None).unwrap(), new.rows(None).unwrap());
+    let origins = SheetOrigins::new(old_rows.origin(), new_rows.origin());
+    differ
+        .compare_iter(old_rows, new_rows)
+        .with_origins(origins)
+        .collect::<anyhow::Result<_>>()
+        .unwrap()
+}
+
+#[test]
+fn sheets_sorted_ignoring_case_are_merged() {
+    // Sorted as Excel sorts text, ignoring case
+    let path1 = write_workbook("case-old", &[["apple", "1"], ["Banana", "2"], ["cherry", "3"]]);
+    let path2 = write_workbook("case-new", &[["apple", "1"], ["Banana", "5"], ["Cherry", "3"], ["date", "4"]]);
+    let (path1, path2) = (path1.to_str().unwrap(), path2.to_str().unwrap());
+
+    let differ = WorksheetDiffer::new().with_key_columns(vec![0]);
+    let diffs = stream_diffs(&differ, path1, path2);
+    let types: Vec<DiffType> = diffs.iter().map(|d| d.diff_type).collect();
+    assert_eq!(
+        types,
+        vec![
+            DiffType::Identical,
+            DiffType::Modified,
+            // Keys that only differ in case are different keys, in character order
+            DiffType::Added,
+            DiffType::Removed,
+            DiffType::Added
+        ]
+    );
+    let _ = std::fs::remove_file(path1);
+    let _ = std::fs::remove_file(path2);
+}
+
+#[test]
+fn keys_differing_in_case_pair_in_any_order() {
+    // Both sorted ignoring case, with the case variants of "cherry" in a different order
+    let path1 = write_workbook(
+        "ties-old",
+        &[["apple", "1"], ["Cherry", "2"], ["cherry", "3"], ["date", "4"]],
+    );
+    let path2 = write_workbook(
+        "ties-new",
+        &[["apple", "1"], ["cherry", "3"], ["Cherry", "5"], ["CHERRY", "6"], ["date", "4"]],
+    );
+    let (path1, path2) = (path1.to_str().unwrap(), path2.to_str().unwrap());
+
+    let differ = WorksheetDiffer::new().with_key_columns(vec![0]);
+    let diffs = stream_diffs(&differ, path1, path2);
+    let rows: Vec<(DiffType, Option<usize>, Option<usize>)> =
+        diffs.iter().map(|d| (d.diff_type, d.old_row, d.new_row)).collect();
+    assert_eq!(
+        rows,
+        vec![
+            (DiffType::Identical, Some(0), Some(0)),
+            // The run of "cherry" keys in character order
+            (DiffType::Added, None, Some(3)),
+            (DiffType::Modified, Some(1), Some(2)),
+            (DiffType::Identical, Some(2), Some(1)),
+            (DiffType::Identical, Some(3), Some(4)),
+        ]
+    );
+    let _ = std::fs::remove_file(path1);
+    let _ = std::fs::remove_file(path2);
+}
+
+#[test]
+fn streamed_rows_have_the_addresses_of_loaded_rows() {
+    let path1 = write_workbook("origin-old", &[["a", "1"], ["b", "2"]]);
+    let path2 = write_workbook("origin-new", &[["a", "1"], ["b", "3"]]);
+    let (path1, path2) = (path1.to_str().unwrap(), path2.to_str().unwrap());
+
+    let differ = WorksheetDiffer::new().with_key_columns(vec![0]);
+    let streamed = stream_diffs(&differ, path1, path2);
+
+    let reader = ExcelReader::new();
+    let sheet1 = reader.read(path1, None).unwrap();
+    let sheet2 = reader.read(path2, None).unwrap();
+    let loaded = differ.compare_worksheets(&sheet1, &sheet2);
+
+    let addresses = |diffs: &[RowDiff]| -> Vec<(Option<String>, Option<String>)> {
+        diffs
+            .iter()
+            .map(|d| (d.old_address.clone(), d.new_address.clone()))
+            .collect()
+    };
+    assert_eq!(addresses(&streamed), addresses(&loaded));
+    assert_eq!(streamed[1].old_address.as_deref(), Some("C4"));
+    assert_eq!(streamed[1].modified_cells, loaded[1].modified_cells);
+    let _ = std::fs::remove_file(path1);
+    let _ = std::fs::remove_file(path2);
+}
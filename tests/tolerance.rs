@@ -0,0 +1,88 @@
+//! Numeric tolerance when comparing numbers.
+
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{ColumnTolerance, DiffType, RowDiff, Tolerance, WorksheetDiffer};
+
+fn row(values: &[f64]) -> Row {
+    values.iter().map(|&v| CellValue::Float(v)).collect()
+}
+
+fn types(diffs: &[RowDiff]) -> Vec<DiffType> {
+    diffs.iter().map(|d| d.diff_type).collect()
+}
+
+#[test]
+fn numbers_within_the_tolerance_are_equal() {
+    let tolerance = Tolerance::new(0.01, 0.0);
+    assert!(tolerance.allows(10.0, 10.01));
+    assert!(!tolerance.allows(10.0, 10.02));
+
+    let tolerance = Tolerance::new(0.0, 0.01);
+    assert!(tolerance.allows(1000.0, 990.0));
+    assert!(!tolerance.allows(1.0, 0.98));
+
+    // Either bound is enough
+    let tolerance = Tolerance::new(0.5, 0.01);
+    assert!(tolerance.allows(1.0, 1.5));
+    assert!(tolerance.allows(1000.0, 1010.0));
+    assert!(!tolerance.allows(10.0, 10.6));
+
+    // Without a tolerance, only rounding noise is allowed
+    assert!(Tolerance::default().is_zero());
+    assert!(Tolerance::default().allows(0.1 + 0.2, 0.3));
+    assert!(!Tolerance::default().allows(0.3, 0.3001));
+}
+
+#[test]
+fn tolerances_apply_to_rows_and_per_column() {
+    let sheet1 = vec![row(&[1.0, 100.0]), row(&[2.0, 200.0])];
+    let sheet2 = vec![row(&[1.0, 100.4]), row(&[2.0, 203.0])];
+
+    let diffs = WorksheetDiffer::new().with_key_columns(vec![0]).compare(&sheet1, &sheet2);
+    assert_eq!(types(&diffs), vec![DiffType::Modified, DiffType::Modified]);
+
+    let differ = WorksheetDiffer::new()
+        .with_key_columns(vec![0])
+        .with_tolerance(Tolerance::new(0.5, 0.0));
+    let diffs = differ.compare(&sheet1, &sheet2);
+    assert_eq!(types(&diffs), vec![DiffType::Identical, DiffType::Modified]);
+    assert_eq!(diffs[1].numeric_delta(1), Some(3.0));
+    assert_eq!(diffs[1].delta_text(1).as_deref(), Some("+3"));
+    // Only modified cells have a delta
+    assert_eq!(diffs[1].numeric_delta(0), None);
+
+    // A column tolerance replaces the general one
+    let diffs = differ
+        .with_column_tolerance(1, Tolerance::new(0.0, 0.02))
+        .compare(&sheet1, &sheet2);
+    assert_eq!(types(&diffs), vec![DiffType::Identical, DiffType::Identical]);
+}
+
+#[test]
+fn integers_and_floats_are_compared_as_numbers() {
+    let sheet1 = vec![vec![CellValue::Int(5), CellValue::Int(7)]];
+    let sheet2 = vec![vec![CellValue::Float(5.0), CellValue::Float(6.5)]];
+
+    let diffs = WorksheetDiffer::new().compare(&sheet1, &sheet2);
+    assert_eq!(types(&diffs), vec![DiffType::Modified]);
+    assert_eq!(diffs[0].modified_cells, vec![1]);
+    assert_eq!(diffs[0].delta_text(1).as_deref(), Some("-0.5"));
+}
+
+#[test]
+fn tolerances_are_parsed_as_numbers_or_percentages() {
+    assert_eq!("0.01".parse::<Tolerance>().unwrap(), Tolerance::new(0.01, 0.0));
+    assert_eq!(" 0.5% ".parse::<Tolerance>().unwrap(), Tolerance::new(0.0, 0.005));
+    assert!("-1".parse::<Tolerance>().is_err());
+    assert!("abc".parse::<Tolerance>().is_err());
+    assert_eq!(Tolerance::new(0.01, 0.0).to_string(), "±0.01");
+
+    let column: ColumnTolerance = "Rate=1%".parse().unwrap();
+    assert_eq!(column.column, "Rate".parse().unwrap());
+    assert_eq!(column.tolerance, Tolerance::new(0.0, 0.01));
+    // The last '=' separates the tolerance, so header names may contain one
+    let column: ColumnTolerance = "a=b=0.1".parse().unwrap();
+    assert_eq!(column.column, "a=b".parse().unwrap());
+    assert!("Price".parse::<ColumnTolerance>().is_err());
+    assert!("Price=cheap".parse::<ColumnTolerance>().is_err());
+}
@@ -0,0 +1,116 @@
+//! Comparison of cells holding the same value with different types.
+
+use exceldiff::datetime::DateFormat;
+use exceldiff::reader::{CellValue, Row};
+use exceldiff::{DiffType, RowDiff, WorksheetDiffer};
+
+fn text(value: &str) -> CellValue {
+    CellValue::String(value.to_string())
+}
+
+fn sheets() -> (Vec<Row>, Vec<Row>) {
+    let sheet1 = vec![
+        vec![text("ID"), text("Name"), text("City"), text("Amount"), text("Paid"), text("Due")],
+        vec![CellValue::Int(1), text("Ann"), text("Ghent"), text("42"), text("TRUE"), CellValue::Float(45352.0)],
+    ];
+    let sheet2 = vec![
+        vec![text("ID"), text("Name"), text("City"), text("Amount"), text("Paid"), text("Due")],
+        vec![
+            CellValue::Int(1),
+            text("Ann"),
+            text("Ghent"),
+            CellValue::Int(42),
+            CellValue::Bool(true),
+            CellValue::DateTime(45352.0),
+        ],
+    ];
+    (sheet1, sheet2)
+}
+
+fn data_row(diffs: &[RowDiff]) -> &RowDiff {
+    diffs.iter().find(|d| d.old_row == Some(1)).unwrap()
+}
+
+#[test]
+fn different_types_are_modified_without_coercion() {
+    let (sheet1, sheet2) = sheets();
+    let differs = [WorksheetDiffer::new(), WorksheetDiffer::new().with_key_columns(vec![0])];
+
+    for differ in differs {
+        let diffs = differ.compare(&sheet1, &sheet2);
+        let row = data_row(&diffs);
+        assert_eq!(row.diff_type, DiffType::Modified);
+        assert_eq!(row.new_row, Some(1));
+        assert_eq!(row.modified_cells, vec![3, 4, 5]);
+        assert!(row.type_changes.is_empty());
+    }
+}
+
+#[test]
+fn coerced_text_is_a_type_change() {
+    let (sheet1, sheet2) = sheets();
+    let differs = [
+        WorksheetDiffer::new().with_type_coercion(true),
+        WorksheetDiffer::new().with_type_coercion(true).with_key_columns(vec![0]),
+    ];
+
+    for differ in differs {
+        let diffs = differ.compare(&sheet1, &sheet2);
+        let row = data_row(&diffs);
+        // A date and its serial number stay different types
+        assert_eq!(row.diff_type, DiffType::Modified);
+        assert_eq!(row.modified_cells, vec![5]);
+        assert_eq!(row.type_changes, vec![3, 4]);
+    }
+}
+
+#[test]
+fn text_dates_are_coerced_with_the_given_formats() {
+    let sheet1 = vec![vec![CellValue::Int(1), text("01/03/2024")], vec![CellValue::Int(2), text("2024-03-02")]];
+    let sheet2 = vec![
+        vec![CellValue::Int(1), CellValue::DateTime(45352.0)],
+        vec![CellValue::Int(2), CellValue::DateTime(45353.0)],
+    ];
+
+    // ISO 8601 dates are always recognized
+    let diffs = WorksheetDiffer::new()
+        .with_type_coercion(true)
+        .with_key_columns(vec![0])
+        .compare(&sheet1, &sheet2);
+    let types: Vec<DiffType> = diffs.iter().map(|d| d.diff_type).collect();
+    assert_eq!(types, vec![DiffType::Modified, DiffType::Identical]);
+
+    let diffs = WorksheetDiffer::new()
+        .with_type_coercion(true)
+        .with_date_formats(vec!["%d/%m/%Y".parse().unwrap()])
+        .with_key_columns(vec![0])
+        .compare(&sheet1, &sheet2);
+    assert!(diffs.iter().all(|d| d.diff_type == DiffType::Identical));
+    assert!(diffs.iter().all(|d| d.type_changes == vec![1]));
+}
+
+#[test]
+fn date_formats_are_parsed() {
+    let format: DateFormat = "%d/%m/%Y".parse().unwrap();
+    assert_eq!(format.parse("1/3/2024"), Some(45352.0));
+    assert_eq!(format.parse(" 01/03/2024 "), Some(45352.0));
+    assert_eq!(format.parse("31/02/2024"), None);
+    assert_eq!(format.parse("01-03-2024"), None);
+    assert_eq!(format.parse("01/03/24"), None);
+    assert_eq!(format.parse("01/03/2024 12:00"), None);
+
+    let format: DateFormat = "%y%m%d %H:%M:%S".parse().unwrap();
+    assert_eq!(format.parse("240301 12:00:00"), Some(45352.5));
+    // Two-digit years from 30 are in the 1900s
+    assert_eq!(format.parse("300101 00:00:00"), Some(10959.0));
+    assert_eq!(format.parse("290101 00:00:00"), Some(47119.0));
+    assert_eq!(format.parse("240301 12:00"), None);
+
+    let format: DateFormat = "%Y-%m-%d 100%%".parse().unwrap();
+    assert_eq!(format.parse("2024-03-01 100%"), Some(45352.0));
+    assert_eq!(format.to_string(), "%Y-%m-%d 100%%");
+
+    assert!("%d/%m".parse::<DateFormat>().is_err());
+    assert!("%d/%m/%Y %p".parse::<DateFormat>().is_err());
+    assert!("%d/%m/%Y%".parse::<DateFormat>().is_err());
+}